@@ -0,0 +1,68 @@
+#version 440 core
+
+// --- Constants ---
+// B3 spline kernel, applied separably in x and y
+const float KERNEL[5] = float[]( 1.0/16.0, 1.0/4.0, 3.0/8.0, 1.0/4.0, 1.0/16.0 );
+
+// --- Structs ---
+
+// DenoiseSettings
+struct DenoiseSettings {
+    float colorPhi;     // How much the color may differ before a neighbour is ignored
+    float normalPhi;    // How much the normal may differ before a neighbour is ignored
+    float depthPhi;     // How much the depth may differ (relative to the center's depth) before a neighbour is ignored
+    float albedoPhi;    // How much the albedo may differ before a neighbour is ignored
+};
+
+// --- Inputs / outputs ---
+out vec4 color;
+
+uniform sampler2D colorTexture;     // The image being filtered
+uniform sampler2D albedoTexture;    // Auxiliary buffers from the raytracer
+uniform sampler2D normalTexture;
+uniform sampler2D depthTexture;
+uniform DenoiseSettings denoise;    // Denoise settings
+uniform int stepSize;               // Distance between the kernel's taps, doubled every iteration
+
+// The main function
+// One iteration of the edge-avoiding à-trous wavelet filter, see:
+// https://jo.dreggn.org/home/2010_atrous.pdf
+void main()
+{
+    ivec2   pixel = ivec2(gl_FragCoord.xy),
+            size = textureSize(colorTexture, 0);
+    vec3    centerColor = texelFetch(colorTexture, pixel, 0).rgb,
+            centerAlbedo = texelFetch(albedoTexture, pixel, 0).rgb,
+            centerNormal = texelFetch(normalTexture, pixel, 0).xyz;
+    float   centerDepth = texelFetch(depthTexture, pixel, 0).r;
+
+    vec3 sum = vec3(0);
+    float weightSum = 0;
+    for (int y = -2; y <= 2; y++) {
+        for (int x = -2; x <= 2; x++) {
+            ivec2 tap = clamp(pixel + ivec2(x, y) * stepSize, ivec2(0), size - 1);
+            vec3    tapColor = texelFetch(colorTexture, tap, 0).rgb,
+                    tapAlbedo = texelFetch(albedoTexture, tap, 0).rgb,
+                    tapNormal = texelFetch(normalTexture, tap, 0).xyz;
+            float   tapDepth = texelFetch(depthTexture, tap, 0).r;
+
+            // Edge-stopping functions, ignoring neighbours which are likely on another surface
+            vec3    colorDifference = centerColor - tapColor,
+                    albedoDifference = centerAlbedo - tapAlbedo,
+                    normalDifference = centerNormal - tapNormal;
+            float   depthDifference = (centerDepth - tapDepth) / max(centerDepth, 1e-3);
+
+            float   colorWeight = min(exp(-dot(colorDifference, colorDifference) / denoise.colorPhi), 1.0),
+                    albedoWeight = min(exp(-dot(albedoDifference, albedoDifference) / denoise.albedoPhi), 1.0),
+                    normalWeight = min(exp(-dot(normalDifference, normalDifference) / denoise.normalPhi), 1.0),
+                    depthWeight = min(exp(-depthDifference * depthDifference / denoise.depthPhi), 1.0);
+
+            float weight = KERNEL[x + 2] * KERNEL[y + 2] * colorWeight * albedoWeight * normalWeight * depthWeight;
+            sum += tapColor * weight;
+            weightSum += weight;
+        }
+    }
+
+    // (The center always has a weight, so the sum is never zero)
+    color = vec4(sum / weightSum, 1);
+}
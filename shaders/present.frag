@@ -0,0 +1,225 @@
+#version 440 core
+
+// --- Constants ---
+// Tone mapping operators (PostSettings.tone_mapping)
+const uint TONE_MAPPING_NONE        = 0;
+const uint TONE_MAPPING_REINHARD    = 1;
+const uint TONE_MAPPING_ACES        = 2;
+const uint TONE_MAPPING_AGX         = 3;
+
+// Ways of visualizing the image (Aov::view())
+const uint VIEW_LIGHT   = 0;    // Exposed, tone mapped and encoded
+const uint VIEW_COLOR   = 1;    // Encoded only, for colors already within [0, 1]
+const uint VIEW_NORMAL  = 2;    // Normals mapped from [-1, 1] to [0, 1]
+const uint VIEW_DEPTH   = 3;    // Brighter when closer, black if nothing was hit
+const uint VIEW_ID      = 4;    // A random color for each id, black for 0
+
+// Selection outline
+const int OUTLINE_WIDTH         = 2;                    // In pixels
+const vec3 OUTLINE_COLOR        = vec3(1, 0.6, 0.1);
+
+// Conversions between linear sRGB and LMS, used for white balancing
+// (Written as rows, so they are applied as "color * matrix")
+const mat3 LINEAR_TO_LMS = mat3(
+    3.90405e-1, 5.49941e-1, 8.92632e-3,
+    7.08416e-2, 9.63172e-1, 1.35775e-3,
+    2.31082e-2, 1.28021e-1, 9.36245e-1
+);
+const mat3 LMS_TO_LINEAR = mat3(
+     2.85847e+0, -1.62879e+0, -2.48910e-2,
+    -2.10182e-1,  1.15820e+0,  3.24281e-4,
+    -4.18120e-2, -1.18169e-1,  1.06867e+0
+);
+
+// --- Structs ---
+
+// PostSettings
+struct PostSettings {
+    float exposure;     // In stops
+    uint toneMapping;   // One of the TONE_MAPPING_ constants
+    vec3 whiteBalance;  // Scaling factors in LMS space
+    bool srgb;          // Encode the output with the sRGB transfer function
+};
+
+// --- Inputs / outputs ---
+out vec4 color;
+
+uniform sampler2D accumulatedFrame; // The averaged result of all frames rendered since the scene last changed
+uniform PostSettings post;          // Post settings
+uniform uint view;                  // How to visualize the image, one of the VIEW_ constants
+uniform sampler2D idTexture;        // The id pass, used to outline the selected object
+uniform uint selectedId;            // The id of the selected object, 0 for none
+
+// --- Tone mapping functions ---
+/**
+ * Fitted ACES filmic curve, including the sRGB input and output transforms.
+ * Thanks to: https://github.com/TheRealMJP/BakingLab/blob/master/BakingLab/ACES.hlsl
+ *
+ * @param color The linear color.
+ * @return The tone mapped color, within [0, 1].
+ */
+vec3 ACES(vec3 color) {
+    const mat3 inputMatrix = mat3(
+        0.59719, 0.07600, 0.02840,
+        0.35458, 0.90834, 0.13383,
+        0.04823, 0.01566, 0.83777
+    );
+    const mat3 outputMatrix = mat3(
+         1.60475, -0.10208, -0.00327,
+        -0.53108,  1.10813, -0.07276,
+        -0.07367, -0.00605,  1.07602
+    );
+
+    // RRT and ODT fit
+    vec3    v = inputMatrix * color,
+            a = v * (v + 0.0245786) - 0.000090537,
+            b = v * (0.983729 * v + 0.4329510) + 0.238081;
+
+    return clamp(outputMatrix * (a / b), 0.0, 1.0);
+}
+
+/**
+ * AgX tone mapping, with the default look.
+ * Thanks to: https://iolite-engine.com/blog_posts/minimal_agx_implementation
+ *
+ * @param color The linear color.
+ * @return The tone mapped color (linear), within [0, 1].
+ */
+vec3 AgX(vec3 color) {
+    const mat3 inset = mat3(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104
+    );
+    const mat3 outset = mat3(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116
+    );
+    const float minEv = -12.47393,
+                maxEv = 4.026069;
+
+    // Log encode within the AgX space
+    vec3 v = inset * color;
+    v = clamp(log2(max(v, 1e-10)), minEv, maxEv);
+    v = (v - minEv) / (maxEv - minEv);
+
+    // Sigmoid contrast curve (polynomial approximation)
+    vec3    v2 = v * v,
+            v4 = v2 * v2;
+    v = 15.5 * v4 * v2 - 40.14 * v4 * v + 31.96 * v4 - 6.868 * v2 * v + 0.4298 * v2 + 0.1191 * v - 0.00232;
+
+    // Back to linear
+    return clamp(pow(max(outset * v, 0.0), vec3(2.2)), 0.0, 1.0);
+}
+
+/**
+ * Applies the selected tone mapping operator.
+ *
+ * @param color The linear color.
+ * @return The tone mapped color (linear).
+ */
+vec3 ToneMap(vec3 color) {
+    switch (post.toneMapping) {
+        case TONE_MAPPING_REINHARD: return color / (1.0 + color);
+        case TONE_MAPPING_ACES:     return ACES(color);
+        case TONE_MAPPING_AGX:      return AgX(color);
+        default:                    return clamp(color, 0.0, 1.0);
+    }
+}
+
+/**
+ * Encodes a linear color with the sRGB transfer function.
+ *
+ * @param color The linear color, within [0, 1].
+ * @return The encoded color.
+ */
+vec3 LinearToSRGB(vec3 color) {
+    return mix(color * 12.92, 1.055 * pow(color, vec3(1.0 / 2.4)) - 0.055, greaterThan(color, vec3(0.0031308)));
+}
+
+/**
+ * Creates a random, but consistent, color for an id.
+ *
+ * @param id The id.
+ * @return The color, black for id 0.
+ */
+vec3 IdColor(float id) {
+    if (id == 0)
+        return vec3(0);
+
+    uint hash = uint(id) * 747796405u + 2891336453u;
+    hash = ((hash >> ((hash >> 28u) + 4u)) ^ hash) * 277803737u;
+    return vec3((hash >> 8u) & 0xFFu, (hash >> 16u) & 0xFFu, (hash >> 24u) & 0xFFu) / 255.0;
+}
+
+/**
+ * Checks if a pixel of the id pass belongs to the selected object.
+ *
+ * @param pixel The pixel, clamped to the texture.
+ * @return True if the pixel's id is the selected id.
+ */
+bool IsSelected(ivec2 pixel) {
+    pixel = clamp(pixel, ivec2(0), textureSize(idTexture, 0) - 1);
+    return uint(texelFetch(idTexture, pixel, 0).r + 0.5) == selectedId;
+}
+
+/**
+ * Checks if a pixel is on the outline of the selected object, just outside of it.
+ *
+ * @param pixel The pixel.
+ * @return True if the pixel is not part of the selected object, but one of its neighbours is.
+ */
+bool IsOutline(ivec2 pixel) {
+    if (selectedId == 0 || IsSelected(pixel))
+        return false;
+
+    return  IsSelected(pixel + ivec2(OUTLINE_WIDTH, 0)) || IsSelected(pixel - ivec2(OUTLINE_WIDTH, 0)) ||
+            IsSelected(pixel + ivec2(0, OUTLINE_WIDTH)) || IsSelected(pixel - ivec2(0, OUTLINE_WIDTH));
+}
+
+/**
+ * Visualizes a texel as selected by the view uniform.
+ *
+ * @param texel The texel of the image.
+ * @return The displayed color.
+ */
+vec3 Visualize(vec4 texel) {
+    // Visualize data which is not light
+    switch (view) {
+        case VIEW_COLOR:
+            return post.srgb ? LinearToSRGB(clamp(texel.rgb, 0.0, 1.0)) : clamp(texel.rgb, 0.0, 1.0);
+        case VIEW_NORMAL:
+            return texel.xyz * 0.5 + 0.5;
+        case VIEW_DEPTH:
+            return vec3(texel.r > 0 ? exp(-texel.r * 0.1) : 0.0);
+        case VIEW_ID:
+            return IdColor(texel.r);
+    }
+
+    vec3 light = texel.rgb;
+
+    // Expose and white balance
+    light *= exp2(post.exposure);
+    light = ((light * LINEAR_TO_LMS) * post.whiteBalance) * LMS_TO_LINEAR;
+
+    // Map to the displayable range and encode
+    vec3 displayColor = ToneMap(max(light, 0.0));
+    if (post.srgb)
+        displayColor = LinearToSRGB(displayColor);
+
+    return displayColor;
+}
+
+// The main function
+void main()
+{
+    ivec2 pixel = ivec2(gl_FragCoord.xy);
+    vec3 displayColor = Visualize(texelFetch( accumulatedFrame, pixel, 0 ));
+
+    // Outline the selected object
+    if (IsOutline(pixel))
+        displayColor = OUTLINE_COLOR;
+
+    color = vec4( displayColor, 1 );
+}
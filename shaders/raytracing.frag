@@ -1,15 +1,50 @@
 #version 440 core
 
 // --- Macros ---
-#define HitInfo0 HitInfo( false, 0.0, vec3(0), vec3(0), Material(vec4(0), vec4(0), vec4(0), 0.0) )
+#define HitInfo0 HitInfo( false, 0.0, vec3(0), vec3(0), 0, 0, vec2(0), vec4(1) )
 
 // --- Constants ---
 const float PI = 3.1415926;
-const vec3  sunPosition = vec3(0, 0, 0);
 const bool  CULL_FACE = true;
 const bool  CLIP_MESHES = false; // Disable until triangle raycasting becomes more expensive
 const float kEpsilion = 0.001;
 
+// Camera projections (RTCamera.projection)
+const uint PROJECTION_PERSPECTIVE       = 0;
+const uint PROJECTION_ORTHOGRAPHIC      = 1;
+const uint PROJECTION_FISHEYE           = 2;
+const uint PROJECTION_EQUIRECTANGULAR   = 3;
+
+// Stereo modes (RTCamera.stereoMode)
+const uint STEREO_OFF           = 0;
+const uint STEREO_SIDE_BY_SIDE  = 1;    // Left eye on the left half of the screen
+const uint STEREO_TOP_BOTTOM    = 2;    // Left eye on the top half of the screen
+
+// Debug view modes (RTSettings.debugMode)
+const uint DEBUG_OFF                = 0;
+const uint DEBUG_NORMALS            = 1;
+const uint DEBUG_BARYCENTRICS       = 2;
+const uint DEBUG_BOUNDING_BOXES     = 3;    // Heatmap of mesh bounding boxes the ray passed through
+const uint DEBUG_TRIANGLE_TESTS     = 4;    // Heatmap of ray-triangle tests
+const uint DEBUG_BOUNCES            = 5;    // Heatmap of the amount of surfaces a ray path hit
+const uint DEBUG_HIT_DISTANCE       = 6;
+const uint DEBUG_MATERIAL_ID        = 7;
+
+// Material models (RTMaterial.kind)
+const uint MATERIAL_LEGACY  = 0;    // Diffuse or specular bounce, blended by the smoothness
+const uint MATERIAL_PBR     = 1;    // Lambert diffuse and GGX microfacet specular, with metallic/roughness parameters
+
+// Optional features of PBR materials (RTMaterial.features bits)
+const uint FEATURE_CLEARCOAT    = 1;
+const uint FEATURE_SHEEN        = 2;
+const uint FEATURE_THIN_FILM    = 4;
+const uint FEATURE_SUBSURFACE   = 8;
+const int  SUBSURFACE_MAX_STEPS = 32;   // Scattering events inside an object before the light is considered absorbed
+
+// Motion blur of meshes (RTMeshInfo.motionIndex)
+const uint MOTION_SAMPLES   = 9;            // Matrices per moving mesh in the motion buffer, spread evenly over the shutter interval
+const uint NO_MOTION        = 0xFFFFFFFFu;  // Motion index of meshes which do not move
+
 // --- Structs ---
 
 // RTSettings
@@ -17,6 +52,18 @@ struct Settings {
     uint maxBounces;
     uint raysPerFrag;
     float divergeStrength;
+    uint debugMode;         // One of the DEBUG_ constants
+};
+
+// RTEnvironment
+struct Environment {
+    bool enabled;           // Black environment if disabled
+    vec3 skyColorHorizon;
+    vec3 skyColorZenith;
+    vec3 groundColor;
+    vec3 sunDirection;      // Direction towards the sun
+    float sunFocus;         // Higher values give a smaller sun
+    float sunIntensity;
 };
 
 // RTCamera
@@ -24,40 +71,60 @@ struct Camera {
     vec2 screenSize;
     float fov;
     float focusDistance;
+    float aperture;         // Radius of the lens, 0 for a pinhole camera
+    uint bladeCount;        // Amount of aperture blades, a round aperture is used if less than 3
+    float anamorphicRatio;  // Horizontal stretch of the aperture
+    uint projection;        // One of the PROJECTION_ constants
+    float orthoHeight;      // Height of the view in world units, for orthographic projection
+    uint stereoMode;        // One of the STEREO_ constants
+    float ipd;              // Interpupillary distance, the distance between the eyes
+    float convergence;      // Distance at which the eyes converge (zero parallax)
     vec3 pos;
     mat4 localToWorld;
 };
 
 // RTMaterial
 struct Material {
-    vec4 color;
-    vec4 emissionColor;
-    vec4 specularColor;
-    float smoothness;
+    vec4 color;             // Base color of PBR materials
+    vec4 emissionColor;     // Strength in the alpha
+    vec4 specularColor;     // Chance of a specular bounce in the alpha. Legacy only
+    float smoothness;       // Legacy only
+    float roughness;        // Perceptual roughness, squared for the GGX distribution. PBR only
+    float metallic;         // PBR only
+    uint kind;              // One of the MATERIAL_ constants
+
+    // PBR features, only used if their FEATURE_ bit is set
+    vec4 sheenColor;        // Strength in the alpha
+    vec4 subsurfaceColor;   // Mean free path in the alpha
+    float clearcoat;        // Strength of the clearcoat layer
+    float clearcoatRoughness;
+    float clearcoatIor;
+    uint features;          // FEATURE_ bits
+    float thinFilmThickness;    // In nanometers
+    float thinFilmIor;
 };
 
 // RTSphere
 struct Sphere {
     float radius;
-    vec3 center;
-    Material material;
+    uint objectId;      // Index of the scene node + 1, 0 if it does not belong to one
+    uint materialId;    // Index into the material buffer
+    vec3 center;        // Center when the shutter opens
+    vec3 centerEnd;     // Center when the shutter closes
 };
 
 // RTTriangle
 struct Triangle {
-    vec3        p0,
-                p1,
-                p2,
-                normal0,
-                normal1,
-                normal2;
-    Material    material;
+    uint    vertices[3];    // Indices into the position and normal buffers
+    uint    materialId;     // Index into the material buffer
 };
 
 // RTMeshInfo
 struct MeshInfo {
     uint    startIndex,
-            count;
+            count,
+            objectId,       // Index of the scene node + 1, 0 if it does not belong to one
+            motionIndex;    // Index of the mesh's first matrix in the motion buffer, or NO_MOTION
     vec3    boundingBoxMin,
             boundingBoxMax;
 };
@@ -68,22 +135,54 @@ struct HitInfo {
     float       dist;
     vec3        pos;
     vec3        normal;
-    Material    material;
+    uint        materialId;
+    uint        objectId;
+    vec2        barycentric;    // Barycentric coordinates (u, v) of a triangle hit
+    vec4        color;          // Vertex color of a triangle hit, multiplied with the material's color (white for spheres)
+};
+
+// Information about the first surface a ray hits, written to the auxiliary outputs (AOVs)
+struct SurfaceInfo {
+    vec3    albedo;
+    vec3    normal;
+    float   depth;
+    float   objectId;
+    float   materialId;
+};
+
+// The light a ray collects, split by the amount of bounces it took to reach the camera
+// (emission + direct + indirect = all light)
+struct LightInfo {
+    vec3    emission;   // Emitted by the first surface hit, or the environment if nothing was hit
+    vec3    direct;     // Reflected once, by the first surface hit
+    vec3    indirect;   // Reflected more than once
 };
 
 // Ray
 struct Ray {
     vec3 origin;
     vec3 dir;
+    float time;     // Time within the shutter interval, within [0, 1]
 };
 
 // --- Inputs / outputs ---
-out vec4 color;
+// (All outputs are blended into the accumulated average, using the alpha)
+layout (location = 0) out vec4 color;
+layout (location = 1) out vec4 albedo;      // Color of the first surface hit, or of the environment
+layout (location = 2) out vec4 normal;      // Normal of the first surface hit, zero if nothing was hit
+layout (location = 3) out vec4 depth;       // Distance to the first surface hit, zero if nothing was hit
+layout (location = 4) out vec4 emission;    // See LightInfo
+layout (location = 5) out vec4 id;          // Object id (r) and material id (g) of the first surface hit. Not blended
+layout (location = 6) out vec4 direct;      // See LightInfo
+layout (location = 7) out vec4 indirect;    // See LightInfo
 
 uniform Settings settings;  // Raytracing settings
 uniform Camera camera;      // Raytracing camera variables
+uniform Environment environment;    // Sky and sun
 uniform int spheresCount;
 uniform int meshesCount;
+uniform uint frame;             // Frame counter, used to vary the RNG between frames
+uniform uint accumulatedFrames; // Amount of frames accumulated in the render target, used for averaging
 
 // Buffer for holding sphere objects
 layout (std430, binding=0) buffer SphereBuffer
@@ -97,12 +196,48 @@ layout (std430, binding=1) buffer TriangleBuffer
     Triangle triangles[];
 };
 
+// Buffers for holding the vertices of all triangles, as 3 tightly packed floats each
+// (An array of vec3 would be padded to vec4 in std430)
+layout (std430, binding=5) buffer PositionBuffer
+{
+    float positions[];
+};
+layout (std430, binding=6) buffer NormalBuffer
+{
+    float normals[];
+};
+layout (std430, binding=7) buffer ColorBuffer
+{
+    vec4 colors[];
+};
+
 // Buffer for holding triangle mesh info
 layout (std430, binding=2) buffer MeshInfoBuffer
 {
     MeshInfo meshes[];
 };
 
+// Buffer for holding the motion of moving meshes: MOTION_SAMPLES matrices per mesh, transforming world space
+// at each sample time into world space when the shutter opens, where the mesh's vertices are
+layout (std430, binding=3) buffer MotionBuffer
+{
+    mat4 motion[];
+};
+
+// Buffer for holding the material table, indexed by the materialId of spheres and triangles
+layout (std430, binding=4) buffer MaterialBuffer
+{
+    Material materials[];
+};
+
+
+// Counters for the debug view modes, reset before each ray they are used for
+uint    debugBoxHits = 0,
+        debugTriangleTests = 0,
+        debugBounces = 0;
+
+// Whether rays also hit surfaces from behind, while walking inside an object for subsurface scattering
+bool    hitBackFaces = false;
 
 // --- Randomness functions ---
 
@@ -169,6 +304,35 @@ vec2 randVecCartesianNormDist(inout uint seed) {
     return pos * sqrt(abs(randFloatNormDist(seed))); // Normal distribution
 }
 
+/**
+ * Generates a uniformly distributed psuedo-random point on the camera's lens (aperture).
+ * If the camera has aperture blades, the point lies within a regular polygon, otherwise within a unit disk.
+ *
+ * @param seed The seed, which is changed after use.
+ * @return A psuedo-random point on the lens, within [-1, 1].
+ */
+vec2 randLensPoint(inout uint seed) {
+    // Round aperture
+    if (camera.bladeCount < 3) {
+        float   ang = randFloat(seed) * 2 * PI,
+                radius = sqrt(randFloat(seed));
+        return vec2(cos(ang), sin(ang)) * radius;
+    }
+
+    // Polygonal aperture: pick one of the triangles between the center and two neighbouring corners, and sample it uniformly
+    float   blade = floor(randFloat(seed) * camera.bladeCount),
+            bladeAng = 2 * PI / camera.bladeCount;
+    vec2    corner0 = vec2(cos(blade * bladeAng), sin(blade * bladeAng)),
+            corner1 = vec2(cos((blade + 1) * bladeAng), sin((blade + 1) * bladeAng));
+    float   u = randFloat(seed),
+            v = randFloat(seed);
+    if (u + v > 1) {
+        u = 1 - u;
+        v = 1 - v;
+    }
+    return corner0 * u + corner1 * v;
+}
+
 // --- Environment functions ---
 /**
  * Gets the environment light where a ray goes.
@@ -177,23 +341,232 @@ vec2 randVecCartesianNormDist(inout uint seed) {
  * @return The environment light for the ray. 
  */
 vec3 GetEnvironmentLight(Ray ray) {
-    return vec3(0, 0, 0);
-    // Set up environment
-    // TODO: Move these to another place.
-    vec3 	SkyColourHorizon = vec3(1,0,0),
-            SkyColourZenith = vec3(1,1,0),
-            GroundColour = vec3(0,0,0);
-    float   SunFocus = 10.f,
-            SunIntensity = 5.f;
-    
+    if (!environment.enabled)
+        return vec3(0, 0, 0);
+
     // Calculate gradients
     float skyGradientT = pow(smoothstep(0, 0.4, ray.dir.y), 0.35);
     float groundToSkyT = smoothstep(-0.01, 0, ray.dir.y);
-    vec3 skyGradient = mix(SkyColourHorizon, SkyColourZenith, skyGradientT);
-    float sun = pow(max(0, dot(ray.dir, sunPosition)), SunFocus) * SunIntensity;
+    vec3 skyGradient = mix(environment.skyColorHorizon, environment.skyColorZenith, skyGradientT);
+    float sun = pow(max(0, dot(ray.dir, environment.sunDirection)), environment.sunFocus) * environment.sunIntensity;
 
     // Combine ground, sky, and sun, and return the final color
-    return mix(GroundColour, skyGradient, groundToSkyT) + sun * int(groundToSkyT>=1);
+    return mix(environment.groundColor, skyGradient, groundToSkyT) + sun * int(groundToSkyT>=1);
+}
+
+// --- Material functions ---
+/**
+ * Creates an orthonormal basis around a normal.
+ *
+ * @param normal The normal, which becomes the z axis.
+ * @return The basis, transforming from tangent space to world space.
+ */
+mat3 TangentFrame(vec3 normal) {
+    vec3    up = abs(normal.y) < 0.999 ? vec3(0, 1, 0) : vec3(1, 0, 0),
+            tangent = normalize(cross(up, normal)),
+            bitangent = cross(normal, tangent);
+    return mat3(tangent, bitangent, normal);
+}
+
+/**
+ * Schlick's approximation of the Fresnel reflectance.
+ *
+ * @param cosTheta The cosine of the angle between the view direction and the (micro)surface normal.
+ * @param f0 The reflectance at normal incidence.
+ * @return The reflectance.
+ */
+vec3 FresnelSchlick(float cosTheta, vec3 f0) {
+    return f0 + (1 - f0) * pow(1 - clamp(cosTheta, 0.0, 1.0), 5);
+}
+
+/**
+ * The GGX (Trowbridge-Reitz) normal distribution function.
+ *
+ * @param NdotH The cosine of the angle between the surface normal and the half vector.
+ * @param alpha The GGX roughness (the perceptual roughness squared).
+ * @return The density of microfacets oriented along the half vector.
+ */
+float DistributionGGX(float NdotH, float alpha) {
+    float   alpha2 = alpha * alpha,
+            d = NdotH * NdotH * (alpha2 - 1) + 1;
+    return alpha2 / (PI * d * d);
+}
+
+/**
+ * The Smith masking function for the GGX distribution, for one direction.
+ *
+ * @param NdotX The cosine of the angle between the surface normal and the direction.
+ * @param alpha The GGX roughness.
+ * @return The fraction of microfacets visible from the direction.
+ */
+float SmithG1GGX(float NdotX, float alpha) {
+    float alpha2 = alpha * alpha;
+    return 2 * NdotX / (NdotX + sqrt(alpha2 + (1 - alpha2) * NdotX * NdotX));
+}
+
+/**
+ * Converts an index of refraction to the reflectance at normal incidence, for a surface in air.
+ *
+ * @param ior The index of refraction.
+ * @return The reflectance.
+ */
+float IorToF0(float ior) {
+    float r = (ior - 1) / (ior + 1);
+    return r * r;
+}
+
+/**
+ * Checks if a PBR material has a feature enabled.
+ *
+ * @param material The material.
+ * @param feature One of the FEATURE_ constants.
+ * @return True if the feature is enabled.
+ */
+bool HasFeature(Material material, uint feature) {
+    return (material.features & feature) != 0;
+}
+
+/**
+ * Calculates the reflectance of a surface covered by a thin film, whose reflections interfere with each other (Airy summation).
+ * The surface below the film is treated as a dielectric with the index of refraction matching its reflectance,
+ * and s-polarized light of one wavelength is used for each of red, green and blue.
+ *
+ * @param cosTheta The cosine of the angle between the view direction and the (micro)surface normal.
+ * @param f0 The reflectance of the surface below the film at normal incidence.
+ * @param thickness The thickness of the film, in nanometers.
+ * @param filmIor The index of refraction of the film.
+ * @return The reflectance.
+ */
+vec3 ThinFilmFresnel(float cosTheta, vec3 f0, float thickness, float filmIor) {
+    const vec3 wavelengths = vec3(650, 510, 475);
+
+    // Angles within the film and the surface below it (Snell's law)
+    vec3    sqrtF0 = min(sqrt(f0), 0.99),
+            surfaceIor = (1 + sqrtF0) / (1 - sqrtF0);
+    float   cos1 = clamp(cosTheta, 0.0, 1.0),
+            sin1Squared = 1 - cos1 * cos1,
+            cos2 = sqrt(max(1 - sin1Squared / (filmIor * filmIor), 0.0));
+    vec3    cos3 = sqrt(max(1 - sin1Squared / (surfaceIor * surfaceIor), 0.0));
+
+    // Reflection amplitudes at the top and bottom of the film, and the interference of the light reflected by them
+    float   r12 = (cos1 - filmIor * cos2) / (cos1 + filmIor * cos2);
+    vec3    r23 = (filmIor * cos2 - surfaceIor * cos3) / (filmIor * cos2 + surfaceIor * cos3),
+            interference = 2 * r12 * r23 * cos(4 * PI * filmIor * thickness * cos2 / wavelengths);
+
+    return (r12 * r12 + r23 * r23 + interference) / (1 + r12 * r12 * r23 * r23 + interference);
+}
+
+/**
+ * Gets the Fresnel reflectance of the base layer of a PBR material, through its thin film if it has one.
+ *
+ * @param material The material.
+ * @param cosTheta The cosine of the angle between the view direction and the (micro)surface normal.
+ * @return The reflectance.
+ */
+vec3 BaseFresnel(Material material, float cosTheta) {
+    vec3 f0 = mix(vec3(0.04), material.color.rgb, material.metallic);
+    if (HasFeature(material, FEATURE_THIN_FILM))
+        return ThinFilmFresnel(cosTheta, f0, material.thinFilmThickness, material.thinFilmIor);
+    return FresnelSchlick(cosTheta, f0);
+}
+
+/**
+ * Approximates the fraction of light reflected by the sheen lobe, for a white sheen with strength 1.
+ * (A fit of the lobe integrated over the hemisphere, which never exceeds 9%)
+ *
+ * @param NdotV The cosine of the angle between the surface normal and the view direction.
+ * @return The fraction of reflected light.
+ */
+float SheenAlbedo(float NdotV) {
+    return 0.09 * (1 - NdotV) * (1 - NdotV);
+}
+
+/**
+ * Gets the chance of sampling the specular lobe of a PBR material rather than the diffuse lobe,
+ * based on how much light each of them reflects.
+ *
+ * @param material The material.
+ * @param NdotV The cosine of the angle between the surface normal and the view direction.
+ * @return The chance, within [0, 1].
+ */
+float SpecularProbability(Material material, float NdotV) {
+    const vec3 luminance = vec3(0.2126, 0.7152, 0.0722);
+    float   specular = dot(BaseFresnel(material, NdotV), luminance),
+            diffuse = dot(material.color.rgb, luminance) * (1 - material.metallic);
+    return specular / max(specular + diffuse, 1e-6);
+}
+
+/**
+ * Evaluates the BSDF of the base layer of a PBR material: Lambert diffuse plus GGX specular with Smith masking.
+ * The diffuse part is weighted by the light not reflected specularly towards the viewer, and disappears for metals, so no energy is created.
+ * A sheen is added to the diffuse part, which is scaled down by the light the sheen reflects.
+ *
+ * @param material The material.
+ * @param normal The surface normal, facing the view direction.
+ * @param viewDir The direction towards the viewer.
+ * @param lightDir The direction towards the light, in the same hemisphere as the normal.
+ * @param diffuseLobe Whether to include the diffuse lobe, which is left out when it is replaced by subsurface scattering.
+ * @param pdf The probability density of SamplePBR() choosing the light direction.
+ *
+ * @return The BSDF times the cosine of the light direction.
+ */
+vec3 EvaluatePBR(Material material, vec3 normal, vec3 viewDir, vec3 lightDir, bool diffuseLobe, out float pdf) {
+    vec3    halfDir = normalize(viewDir + lightDir);
+    float   NdotV = max(dot(normal, viewDir), 1e-4),
+            NdotL = max(dot(normal, lightDir), 1e-4),
+            NdotH = max(dot(normal, halfDir), 0.0),
+            VdotH = max(dot(viewDir, halfDir), 1e-4),
+            alpha = max(material.roughness * material.roughness, 1e-3);
+
+    float   distribution = DistributionGGX(NdotH, alpha);
+    vec3    specular = distribution * SmithG1GGX(NdotV, alpha) * SmithG1GGX(NdotL, alpha) * BaseFresnel(material, VdotH) / (4 * NdotV * NdotL),
+            diffuse = vec3(0);
+    if (diffuseLobe) {
+        diffuse = (1 - BaseFresnel(material, NdotV)) * material.color.rgb / PI;
+        if (HasFeature(material, FEATURE_SHEEN)) {
+            vec3 sheen = material.sheenColor.rgb * material.sheenColor.w;
+            diffuse = diffuse * (1 - max(sheen.r, max(sheen.g, sheen.b)) * SheenAlbedo(NdotV)) + sheen * pow(1 - max(dot(lightDir, halfDir), 0.0), 5);
+        }
+        diffuse *= 1 - material.metallic;
+    }
+
+    // The lobes are sampled with the specular probability, so the pdf is a mix of theirs
+    float specularChance = SpecularProbability(material, NdotV);
+    pdf = mix(diffuseLobe ? NdotL / PI : 0.0, distribution * NdotH / (4 * VdotH), specularChance);
+    return (diffuse + specular) * NdotL;
+}
+
+/**
+ * Samples a microfacet normal from the GGX distribution, with a density of D * NdotH.
+ *
+ * @param normal The surface normal.
+ * @param alpha The GGX roughness.
+ * @param seed The seed, which is changed after use.
+ * @return The microfacet normal.
+ */
+vec3 SampleGGX(vec3 normal, float alpha, inout uint seed) {
+    float   u = randFloat(seed),
+            phi = 2 * PI * randFloat(seed),
+            cosTheta = sqrt((1 - u) / (1 + (alpha * alpha - 1) * u)),
+            sinTheta = sqrt(max(1 - cosTheta * cosTheta, 0.0));
+    return TangentFrame(normal) * vec3(sinTheta * cos(phi), sinTheta * sin(phi), cosTheta);
+}
+
+/**
+ * Scatters a ray off a legacy material, bouncing either diffusely or (with the specular chance) specularly.
+ *
+ * @param material The material.
+ * @param normal The surface normal.
+ * @param seed The seed, which is changed after use.
+ * @param dir The direction of the ray, which is set to the scattered direction.
+ * @param weight The color the ray is multiplied with.
+ */
+void SampleLegacy(Material material, vec3 normal, inout uint seed, inout vec3 dir, out vec3 weight) {
+    bool 	isSpecular  = material.specularColor.w >= randFloat(seed);
+    vec3 	specularDir = reflect(dir, normal),
+            diffuseDir  = normalize(normal + randVecNormDist(seed));
+    dir = normalize(mix(diffuseDir, specularDir, material.smoothness * float(isSpecular)));
+    weight = mix(material.color.rgb, material.specularColor.rgb, float(isSpecular));
 }
 
 // --- Ray intersection functions ---
@@ -207,7 +580,8 @@ vec3 GetEnvironmentLight(Ray ray) {
  */
 HitInfo RaySphere(Ray ray, Sphere sphere) {	
     HitInfo hitInfo = HitInfo0;
-    vec3 offsetRayOrigin = ray.origin - sphere.center;
+    vec3 center = mix(sphere.center, sphere.centerEnd, ray.time);
+    vec3 offsetRayOrigin = ray.origin - center;
 
     // Solve for distance with a quadratic equation
     float a = dot(ray.dir, ray.dir);
@@ -221,12 +595,16 @@ HitInfo RaySphere(Ray ray, Sphere sphere) {
     if (discriminant >= 0) {
         float dist = (-b - sqrt(abs(discriminant))) / (2 * a);
 
+        // (From inside the sphere, only the far intersection is in front of the ray)
+        if (dist < 0 && hitBackFaces)
+            dist = (-b + sqrt(abs(discriminant))) / (2 * a);
+
         // (If the intersection happens behind the ray, ignore it)
         if (dist >= 0) {
             hitInfo.didHit = true;
             hitInfo.dist = dist;
             hitInfo.pos = ray.origin + ray.dir * dist;
-            hitInfo.normal = normalize(hitInfo.pos - sphere.center);
+            hitInfo.normal = normalize(hitInfo.pos - center);
         }
     }
 
@@ -234,22 +612,60 @@ HitInfo RaySphere(Ray ray, Sphere sphere) {
     return hitInfo;
 }
 
+/**
+ * Gets the position of a vertex from the position buffer.
+ *
+ * @param vertex The index of the vertex.
+ * @return The position.
+ */
+vec3 VertexPosition(uint vertex) {
+    return vec3(positions[vertex * 3u], positions[vertex * 3u + 1u], positions[vertex * 3u + 2u]);
+}
+
+/**
+ * Gets the normal of a vertex from the normal buffer.
+ *
+ * @param vertex The index of the vertex.
+ * @return The normal.
+ */
+vec3 VertexNormal(uint vertex) {
+    return vec3(normals[vertex * 3u], normals[vertex * 3u + 1u], normals[vertex * 3u + 2u]);
+}
+
+/**
+ * Interpolates the vertex colors of a triangle.
+ *
+ * @param triangle The triangle.
+ * @param barycentric The barycentric coordinates (u, v) of the point on the triangle.
+ *
+ * @return The color at the point.
+ */
+vec4 TriangleColor(Triangle triangle, vec2 barycentric) {
+    float w = 1.0 - barycentric.x - barycentric.y;
+    return colors[triangle.vertices[0]] * w
+         + colors[triangle.vertices[1]] * barycentric.x
+         + colors[triangle.vertices[2]] * barycentric.y;
+}
+
 /**
  * Checks for an intersection between a ray and a triangle.
  * Uses the Möller-Trumbore algorithm, see:
  * https://en.wikipedia.org/wiki/M%C3%B6ller%E2%80%93Trumbore_intersection_algorithm
+ * The normal is not set, as it is only needed for the closest hit (see TriangleNormal()).
  *
  * @param ray The ray.
- * @param triangle The triangle.
+ * @param p0 The position of the first vertex of the triangle.
+ * @param p1 The position of the second vertex of the triangle.
+ * @param p2 The position of the third vertex of the triangle.
  *
  * @return The hit information from the (possible) intersection.
  */
-HitInfo RayTriangle(Ray ray, Triangle triangle) {
+HitInfo RayTriangle(Ray ray, vec3 p0, vec3 p1, vec3 p2) {
     HitInfo hitInfo = HitInfo0;
 
     // Define vectors
-    vec3    v0 = triangle.p1 - triangle.p0,
-            v1 = triangle.p2 - triangle.p0,
+    vec3    v0 = p1 - p0,
+            v1 = p2 - p0,
             v0v1c = cross( v0, v1 );
     
     // Define determinant and inverse determinant
@@ -257,7 +673,7 @@ HitInfo RayTriangle(Ray ray, Triangle triangle) {
             invDeterminant = 1.0 / determinant;
 
     // If culling is enabled, verify that ray passes through triangle the right direction
-    if ( CULL_FACE && determinant < kEpsilion )
+    if ( CULL_FACE && !hitBackFaces && determinant < kEpsilion )
         return hitInfo;
     
     // (Check if ray is parallel with triangle)
@@ -265,7 +681,7 @@ HitInfo RayTriangle(Ray ray, Triangle triangle) {
         return hitInfo;
 
     // Calculate distance to triangle and barycentric coordinates
-    vec3    v3 = ray.origin - triangle.p0,
+    vec3    v3 = ray.origin - p0,
             v3dirc = cross( v3, ray.dir );
 
     float   dist = dot( v3, v0v1c ) * invDeterminant,
@@ -277,11 +693,43 @@ HitInfo RayTriangle(Ray ray, Triangle triangle) {
     hitInfo.didHit  = dist >= 0.0 && u >= 0.0 && v >= 0.0 && w >= 0.0;
     hitInfo.dist    = dist;
     hitInfo.pos     = ray.origin + ray.dir * dist;
-    hitInfo.normal  = normalize( triangle.normal0 * w + triangle.normal1 * u + triangle.normal2 * v );
+    hitInfo.barycentric = vec2( u, v );
 
     return hitInfo;
 }
 
+/**
+ * Gets the matrix which moves a ray from where a moving mesh is at a time into where it was when the shutter opened.
+ * The matrices of the two closest samples are interpolated.
+ *
+ * @param motionIndex The index of the mesh's first matrix in the motion buffer.
+ * @param time The time within the shutter interval, within [0, 1].
+ *
+ * @return The matrix, from world space at the time to world space when the shutter opened.
+ */
+mat4 MotionMatrix(uint motionIndex, float time) {
+    float sampleTime = clamp(time, 0.0, 1.0) * float(MOTION_SAMPLES - 1);
+    uint sampleIndex = min( uint(sampleTime), MOTION_SAMPLES - 2 );
+    mat4    m0 = motion[motionIndex + sampleIndex],
+            m1 = motion[motionIndex + sampleIndex + 1];
+    return m0 + ( m1 - m0 ) * ( sampleTime - float(sampleIndex) );
+}
+
+/**
+ * Interpolates the vertex normals of a triangle.
+ *
+ * @param triangle The triangle.
+ * @param barycentric The barycentric coordinates (u, v) of the point on the triangle.
+ *
+ * @return The normal at the point.
+ */
+vec3 TriangleNormal(Triangle triangle, vec2 barycentric) {
+    float w = 1.0 - barycentric.x - barycentric.y;
+    return normalize( VertexNormal(triangle.vertices[0]) * w
+                    + VertexNormal(triangle.vertices[1]) * barycentric.x
+                    + VertexNormal(triangle.vertices[2]) * barycentric.y );
+}
+
 /**
  * Checks for an intersection between a ray and a bounding box.
  * Thanks to:   https://gist.github.com/DomNomNom/46bb1ce47f68d255fd5d
@@ -324,7 +772,8 @@ HitInfo CalculateRayCollision(Ray ray) {
         if (hitInfo.didHit && ( closestHit.dist < 0 || hitInfo.dist < closestHit.dist ) )
         {
             closestHit = hitInfo;
-            closestHit.material = sphere.material;
+            closestHit.materialId = sphere.materialId;
+            closestHit.objectId = sphere.objectId;
         }
     }
 
@@ -332,22 +781,42 @@ HitInfo CalculateRayCollision(Ray ray) {
     for (int i = 0; i < meshesCount; i++) {
         MeshInfo meshInfo = meshes[i];
 
+        // Move the ray instead of the mesh, so the triangles can be tested where they were when the shutter opened
+        // (The direction is not normalized, so distances along the ray stay the same)
+        Ray meshRay = ray;
+        mat3 normalMatrix = mat3(1);
+        if (meshInfo.motionIndex != NO_MOTION) {
+            mat4 toShutterOpen = MotionMatrix(meshInfo.motionIndex, ray.time);
+            meshRay.origin = ( toShutterOpen * vec4(ray.origin, 1) ).xyz;
+            meshRay.dir = mat3(toShutterOpen) * ray.dir;
+            normalMatrix = transpose( mat3(toShutterOpen) );
+        }
+
         // Cull meshes
-        if (!RayBoundingBox( ray, meshInfo.boundingBoxMin, meshInfo.boundingBoxMax ))
+        if (!RayBoundingBox( meshRay, meshInfo.boundingBoxMin, meshInfo.boundingBoxMax ))
             continue;
+        debugBoxHits++;
         
         for (uint j = meshInfo.startIndex; j < meshInfo.startIndex + meshInfo.count; j++) {
             Triangle triangle = triangles[j];
+            vec3    p0 = VertexPosition(triangle.vertices[0]),
+                    p1 = VertexPosition(triangle.vertices[1]),
+                    p2 = VertexPosition(triangle.vertices[2]);
 
             // "Clip" meshes (cull triangles) if enabled
-            if ( CLIP_MESHES && !RayBoundingBox( ray, min(min(triangle.p0, triangle.p1), triangle.p2), max(max(triangle.p0, triangle.p1), triangle.p2) ) )
+            if ( CLIP_MESHES && !RayBoundingBox( meshRay, min(min(p0, p1), p2), max(max(p0, p1), p2) ) )
                 continue;
 
-            HitInfo hitInfo = RayTriangle(ray, triangle);
+            HitInfo hitInfo = RayTriangle(meshRay, p0, p1, p2);
+            debugTriangleTests++;
             if (hitInfo.didHit && ( closestHit.dist < 0 || hitInfo.dist < closestHit.dist ) )
             {
                 closestHit = hitInfo;
-                closestHit.material = triangle.material;
+                closestHit.pos = ray.origin + ray.dir * hitInfo.dist;
+                closestHit.normal = normalize( normalMatrix * TriangleNormal(triangle, hitInfo.barycentric) );
+                closestHit.color = TriangleColor(triangle, hitInfo.barycentric);
+                closestHit.materialId = triangle.materialId;
+                closestHit.objectId = meshInfo.objectId;
             }
         }
     }
@@ -356,39 +825,213 @@ HitInfo CalculateRayCollision(Ray ray) {
     return closestHit;
 }
 
+/**
+ * Walks a ray through the inside of an object, for random walk subsurface scattering.
+ * The ray travels an exponentially distributed distance (set by the mean free path) before scattering in a random direction
+ * and being tinted by the subsurface color, until it leaves the object or is absorbed.
+ *
+ * @param material The material of the object.
+ * @param normal The surface normal where the ray enters, facing against the ray.
+ * @param seed The seed, which is changed after use.
+ * @param ray The ray, starting where it enters the object. It is changed to leave the object diffusely.
+ * @param weight The color the ray is multiplied with.
+ *
+ * @return False if the ray was absorbed inside the object.
+ */
+bool SubsurfaceWalk(Material material, vec3 normal, inout uint seed, inout Ray ray, out vec3 weight) {
+    weight = vec3(1);
+    float meanFreePath = max(material.subsurfaceColor.w, 1e-4);
+
+    // Enter the object diffusely, letting the ray hit its surface from the inside
+    ray.origin -= normal * kEpsilion;
+    ray.dir = normalize(-normal + randVecNormDist(seed));
+    hitBackFaces = true;
+
+    for (int i = 0; i < SUBSURFACE_MAX_STEPS; i++) {
+        float distance = -log(max(randFloat(seed), 1e-7)) * meanFreePath;
+        HitInfo hitInfo = CalculateRayCollision(ray);
+
+        // (If nothing is hit, the ray has already left the object)
+        if (!hitInfo.didHit) {
+            hitBackFaces = false;
+            return true;
+        }
+
+        // Leave the object if its surface is closer than the next scattering event
+        if (hitInfo.dist < distance) {
+            vec3 outwardNormal = faceforward(hitInfo.normal, -ray.dir, hitInfo.normal);
+            ray.origin = hitInfo.pos + outwardNormal * kEpsilion;
+            ray.dir = normalize(outwardNormal + randVecNormDist(seed));
+            hitBackFaces = false;
+            return true;
+        }
+
+        ray.origin += ray.dir * distance;
+        ray.dir = randVecNormDist(seed);
+        weight *= material.subsurfaceColor.rgb;
+    }
+
+    hitBackFaces = false;
+    return false;
+}
+
+/**
+ * Scatters a ray off the clearcoat layer of a PBR material, importance sampling its GGX distribution.
+ *
+ * @param material The material.
+ * @param normal The surface normal, facing against the ray.
+ * @param coatChance The chance the clearcoat was chosen with, which the weight is divided by.
+ * @param seed The seed, which is changed after use.
+ * @param dir The direction of the ray, which is set to the scattered direction.
+ * @param weight The color the ray is multiplied with.
+ *
+ * @return False if the ray was absorbed, ending its path.
+ */
+bool SampleClearcoat(Material material, vec3 normal, float coatChance, inout uint seed, inout vec3 dir, out vec3 weight) {
+    weight = vec3(0);
+    vec3    viewDir = -dir;
+    float   alpha = max(material.clearcoatRoughness * material.clearcoatRoughness, 1e-3);
+    vec3    halfDir = SampleGGX(normal, alpha, seed);
+    dir = reflect(-viewDir, halfDir);
+    if (dot(normal, dir) <= 0)
+        return false;
+
+    // (The distribution cancels out against the pdf)
+    float   NdotV = max(dot(normal, viewDir), 1e-4),
+            NdotL = max(dot(normal, dir), 1e-4),
+            NdotH = max(dot(normal, halfDir), 1e-4),
+            VdotH = max(dot(viewDir, halfDir), 1e-4),
+            fresnel = FresnelSchlick(VdotH, vec3(IorToF0(material.clearcoatIor))).x;
+    weight = vec3(material.clearcoat * fresnel * SmithG1GGX(NdotV, alpha) * SmithG1GGX(NdotL, alpha) * VdotH / (NdotV * NdotH * coatChance));
+    return true;
+}
+
+/**
+ * Scatters a ray off a PBR material.
+ * The clearcoat, if enabled, is chosen with the chance of its reflectance. Otherwise either the GGX distribution of half vectors
+ * or the cosine weighted diffuse lobe (or the subsurface random walk replacing it) is importance sampled.
+ *
+ * @param material The material.
+ * @param normal The surface normal, facing against the ray.
+ * @param seed The seed, which is changed after use.
+ * @param ray The ray, starting at the surface. Its direction (and origin, for subsurface scattering) is changed to scatter it.
+ * @param weight The color the ray is multiplied with (the BSDF times the cosine, divided by the pdf).
+ *
+ * @return False if the ray was absorbed, ending its path.
+ */
+bool SamplePBR(Material material, vec3 normal, inout uint seed, inout Ray ray, out vec3 weight) {
+    weight = vec3(0);
+    vec3 viewDir = -ray.dir;
+    float NdotV = dot(normal, viewDir);
+    if (NdotV <= 0)
+        return false;
+
+    // (Light passing through the clearcoat is weighted by the chance of not reflecting off it, which cancels out against the chance of passing)
+    if (HasFeature(material, FEATURE_CLEARCOAT)) {
+        float coatChance = material.clearcoat * FresnelSchlick(NdotV, vec3(IorToF0(material.clearcoatIor))).x;
+        if (randFloat(seed) < coatChance)
+            return SampleClearcoat(material, normal, coatChance, seed, ray.dir, weight);
+    }
+
+    float   specularChance = SpecularProbability(material, NdotV);
+    bool    subsurface = HasFeature(material, FEATURE_SUBSURFACE);
+    if (randFloat(seed) < specularChance) {
+        ray.dir = reflect(-viewDir, SampleGGX(normal, max(material.roughness * material.roughness, 1e-3), seed));
+    } else if (subsurface) {
+        // The light entering the surface is the light not reflected specularly
+        vec3 walkWeight;
+        if (!SubsurfaceWalk(material, normal, seed, ray, walkWeight))
+            return false;
+        weight = (1 - BaseFresnel(material, NdotV)) * (1 - material.metallic) * walkWeight / (1 - specularChance);
+        return true;
+    } else {
+        ray.dir = normalize(normal + randVecNormDist(seed));
+    }
+
+    // Directions below the surface are absorbed
+    if (dot(normal, ray.dir) <= 0)
+        return false;
+
+    float pdf;
+    vec3 bsdfCos = EvaluatePBR(material, normal, viewDir, ray.dir, !subsurface, pdf);
+    if (pdf <= 0)
+        return false;
+    weight = bsdfCos / pdf;
+    return true;
+}
+
+/**
+ * Scatters a ray off a surface, using the model of its material.
+ *
+ * @param material The material.
+ * @param normal The surface normal.
+ * @param seed The seed, which is changed after use.
+ * @param ray The ray, starting at the surface, which is changed to scatter it.
+ * @param weight The color the ray is multiplied with.
+ *
+ * @return False if the ray was absorbed, ending its path.
+ */
+bool Scatter(Material material, vec3 normal, inout uint seed, inout Ray ray, out vec3 weight) {
+    if (material.kind == MATERIAL_PBR)
+        return SamplePBR(material, faceforward(normal, ray.dir, normal), seed, ray, weight);
+
+    SampleLegacy(material, normal, seed, ray.dir, weight);
+    return true;
+}
+
+/**
+ * Adds light collected by a ray to the right part of the light information.
+ *
+ * @param light The light information.
+ * @param bounce The amount of times the ray had bounced when collecting the light.
+ * @param collectedLight The collected light.
+ */
+void AddLight(inout LightInfo light, int bounce, vec3 collectedLight) {
+    if (bounce == 0)        light.emission += collectedLight;
+    else if (bounce == 1)   light.direct += collectedLight;
+    else                    light.indirect += collectedLight;
+}
+
 /**
  * Traces a ray's path as it bounces around the scene, collecting hit information along the way.
  *
  * @param ray The ray.
  * @param seed The seed, which is changed after use.
+ * @param surface Information about the first surface the ray hits.
+ * @param light The collected light, split by the amount of bounces.
  *
  * @return The end color of the ray.
  */
-vec3 Trace(Ray ray, inout uint seed) {
+vec3 Trace(Ray ray, inout uint seed, out SurfaceInfo surface, out LightInfo light) {
     vec3 	incomingLight = vec3(0),
             rayColor = vec3(1);
     bool	hitAny = false;
+    surface = SurfaceInfo(vec3(0), vec3(0), 0.0, 0.0, 0.0);
+    light = LightInfo(vec3(0), vec3(0), vec3(0));
     
     for (int i = 0; i < settings.maxBounces; i++)
     {
         HitInfo hitInfo = CalculateRayCollision(ray);
         if (hitInfo.didHit)
         {
+            debugBounces++;
+            Material material = materials[hitInfo.materialId];
+            material.color *= hitInfo.color;
+            if (!hitAny)
+                surface = SurfaceInfo(material.color.rgb, hitInfo.normal, hitInfo.dist, float(hitInfo.objectId), float(hitInfo.materialId + 1u));
             hitAny = true;
-            Material material = hitInfo.material;
             
-            // Calculate new pos and dir
-            ray.origin = hitInfo.pos;
-
-            bool 	isSpecular  = material.specularColor.w >= randFloat(seed);
-            vec3 	specularDir = reflect(ray.dir, hitInfo.normal),
-                    diffuseDir  = normalize(hitInfo.normal + randVecNormDist(seed));
-            ray.dir = normalize(mix(diffuseDir, specularDir, material.smoothness * int(isSpecular)));
-
-            // Update light and color
+            // Update light
             vec3 emittedLight = material.emissionColor.xyz * material.emissionColor.w;
             incomingLight += emittedLight * rayColor;
-            rayColor *= mix(material.color, material.specularColor, isSpecular);
+            AddLight(light, i, emittedLight * rayColor);
+
+            // Calculate new pos and dir, and the color the surface reflects along it
+            vec3 reflectedColor;
+            ray.origin = hitInfo.pos;
+            if (!Scatter(material, hitInfo.normal, seed, ray, reflectedColor))
+                break;
+            rayColor *= reflectedColor;
 
             // Early exit if ray color ~= 0
             // (Use some randomness to avoid "artificial" look)
@@ -398,7 +1041,11 @@ vec3 Trace(Ray ray, inout uint seed) {
         } else 
         {
             // If the ray did not hit anything, sample color from environment and return
-            incomingLight += GetEnvironmentLight(ray) * rayColor;
+            vec3 environmentLight = GetEnvironmentLight(ray);
+            if (!hitAny)
+                surface.albedo = clamp(environmentLight, 0.0, 1.0);
+            incomingLight += environmentLight * rayColor;
+            AddLight(light, i, environmentLight * rayColor);
             break;
         }
     }
@@ -407,40 +1054,242 @@ vec3 Trace(Ray ray, inout uint seed) {
     return incomingLight;
 }
 
+// --- Camera functions ---
+/**
+ * Generates a ray from the camera through a point on the screen, using the camera's projection.
+ * The ray is jittered for anti-aliasing, starts at a random point on the lens for depth of field,
+ * and is given a random time within the shutter interval for motion blur.
+ *
+ * @param uv The point on the image, within [0, 1].
+ * @param imageSize The size of the image in pixels. Smaller than the screen when it is split between two eyes.
+ * @param eyeOffset The eye's offset to the right of the camera, 0 when not rendering in stereo.
+ * @param seed The seed, which is changed after use.
+ * @param ray The generated ray.
+ *
+ * @return False if the point is outside the image (e.g. outside the circle of a fisheye), true otherwise.
+ */
+bool GenerateCameraRay(vec2 uv, vec2 imageSize, float eyeOffset, inout uint seed, out Ray ray) {
+    float   aspect = imageSize.x / imageSize.y;
+    vec3    camUp = normalize(camera.localToWorld[1].xyz),
+            camRight = normalize(camera.localToWorld[0].xyz),
+            camFront = normalize(camera.localToWorld[2].xyz),
+            origin = camera.pos,
+            eyeRight = camRight,
+            focusPoint;
+
+    switch (camera.projection) {
+        // Parallel rays from a plane
+        case PROJECTION_ORTHOGRAPHIC: {
+            vec2    jitter = randVecCartesianNormDist(seed) * settings.divergeStrength / imageSize,
+                    planeSize = vec2(camera.orthoHeight * aspect, camera.orthoHeight);
+            vec3    originLocal = vec3((uv - 0.5 + jitter) * planeSize, 0);
+            origin = (camera.localToWorld * vec4(originLocal, 1)).xyz;
+            focusPoint = origin + camFront * camera.focusDistance;
+            break;
+        }
+
+        // Equidistant fisheye, where the image circle fits the screen's height and covers the whole fov
+        case PROJECTION_FISHEYE: {
+            vec2    jitter = randVecCartesianNormDist(seed) * settings.divergeStrength / imageSize,
+                    p = (uv - 0.5 + jitter) * 2 * vec2(aspect, 1);
+            float   r = length(p);
+            if (r > 1)
+                return false;
+
+            float   theta = r * camera.fov * 0.5 * PI / 180.0,
+                    phi = atan(p.y, p.x);
+            vec3    dirLocal = vec3(sin(theta) * cos(phi), sin(theta) * sin(phi), cos(theta));
+            focusPoint = origin + (camera.localToWorld * vec4(dirLocal, 0)).xyz * camera.focusDistance;
+            break;
+        }
+
+        // Full 360 degree panorama, longitude along x and latitude along y
+        case PROJECTION_EQUIRECTANGULAR: {
+            vec2    jitter = randVecCartesianNormDist(seed) * settings.divergeStrength / imageSize;
+            float   longitude = (uv.x - 0.5 + jitter.x) * 2 * PI,
+                    latitude = (uv.y - 0.5 + jitter.y) * PI;
+            vec3    dirLocal = vec3(cos(latitude) * sin(longitude), sin(latitude), cos(latitude) * cos(longitude));
+            focusPoint = origin + (camera.localToWorld * vec4(dirLocal, 0)).xyz * camera.focusDistance;
+
+            // Omni-directional stereo: the eyes sit on a circle, perpendicular to the horizontal view direction
+            // (The separation fades out towards the poles, where the eyes would otherwise swap sides)
+            eyeRight = (camera.localToWorld * vec4(cos(longitude), 0, -sin(longitude), 0)).xyz * cos(latitude);
+            break;
+        }
+
+        // Pinhole perspective through the focus plane
+        default: {
+            float   planeHeight = camera.focusDistance * tan(camera.fov * 0.5 * PI / 180.0) * 2.0,
+                    planeWidth = planeHeight * aspect;
+            vec3    viewParams = vec3( planeWidth, planeHeight, camera.focusDistance ),
+                    focusPointLocal = vec3(uv - 0.5, 1) * viewParams;
+
+            // (The jitter is scaled with the focus distance, so anti-aliasing does not depend on it)
+            vec2 jitter = randVecCartesianNormDist(seed) * settings.divergeStrength * camera.focusDistance / imageSize.x;
+            focusPoint = (camera.localToWorld * vec4(focusPointLocal, 1)).xyz + camRight*jitter.x + camUp*jitter.y;
+            break;
+        }
+    }
+
+    // Move the ray to the eye, and aim it so that both eyes converge at the convergence distance (off-axis stereo)
+    // (Flat projections converge on a plane, the wide angle ones on a sphere)
+    if (eyeOffset != 0) {
+        bool    planar = camera.projection == PROJECTION_PERSPECTIVE || camera.projection == PROJECTION_ORTHOGRAPHIC;
+        vec3    dir = normalize(focusPoint - origin);
+        vec3    convergencePoint = origin + dir * camera.convergence / (planar ? dot(dir, camFront) : 1.0);
+
+        origin += eyeRight * eyeOffset;
+        dir = normalize(convergencePoint - origin);
+        focusPoint = origin + dir * camera.focusDistance / (planar ? dot(dir, camFront) : 1.0);
+    }
+
+    // Start the ray at a random point on the lens, so only the focus plane is sharp (thin lens model)
+    vec2 lensPoint = randLensPoint(seed) * camera.aperture * vec2(camera.anamorphicRatio, 1);
+
+    ray.origin = origin + camRight*lensPoint.x + camUp*lensPoint.y;
+    ray.dir = normalize(focusPoint - ray.origin);
+    ray.time = randFloat(seed);
+    return true;
+}
+
+// --- Debug functions ---
+/**
+ * Maps a value to a heatmap color, from blue (cold) through green to red (hot).
+ *
+ * @param t The value, within [0, 1].
+ * @return The color.
+ */
+vec3 Heatmap(float t) {
+    t = clamp(t, 0.0, 1.0);
+    return clamp(vec3(4 * t - 2, 2 - abs(4 * t - 2), 2 - 4 * t), 0.0, 1.0);
+}
+
+/**
+ * Creates a random, but consistent, color for an id.
+ *
+ * @param id The id.
+ * @return The color.
+ */
+vec3 IdColor(uint id) {
+    uint hash = id * 747796405u + 2891336453u;
+    hash = ((hash >> ((hash >> 28u) + 4u)) ^ hash) * 277803737u;
+    return vec3((hash >> 8u) & 0xFFu, (hash >> 16u) & 0xFFu, (hash >> 24u) & 0xFFu) / 255.0;
+}
+
+/**
+ * Calculates the color of a ray in the current debug view mode.
+ *
+ * @param ray The ray.
+ * @param seed The seed, which is changed after use.
+ *
+ * @return The debug color, within [0, 1].
+ */
+vec3 DebugColor(Ray ray, inout uint seed) {
+    debugBoxHits = 0;
+    debugTriangleTests = 0;
+    debugBounces = 0;
+
+    // The amount of bounces needs a full path
+    if (settings.debugMode == DEBUG_BOUNCES) {
+        SurfaceInfo surface;
+        LightInfo light;
+        Trace(ray, seed, surface, light);
+        return Heatmap(float(debugBounces) / float(max(settings.maxBounces, 1)));
+    }
+
+    HitInfo hitInfo = CalculateRayCollision(ray);
+    switch (settings.debugMode) {
+        case DEBUG_NORMALS:
+            return hitInfo.didHit ? hitInfo.normal * 0.5 + 0.5 : vec3(0);
+        case DEBUG_BARYCENTRICS:
+            // (Spheres have no barycentric coordinates, and are shown in gray)
+            if (!hitInfo.didHit)                    return vec3(0);
+            if (hitInfo.barycentric == vec2(0))     return vec3(0.25);
+            return vec3(1 - hitInfo.barycentric.x - hitInfo.barycentric.y, hitInfo.barycentric);
+        case DEBUG_BOUNDING_BOXES:
+            return Heatmap(float(debugBoxHits) / float(max(meshesCount, 1)));
+        case DEBUG_TRIANGLE_TESTS:
+            // (Logarithmic, as the amount varies a lot)
+            return Heatmap(log2(1.0 + float(debugTriangleTests)) / 16.0);
+        case DEBUG_HIT_DISTANCE:
+            return hitInfo.didHit ? vec3(exp(-hitInfo.dist * 0.1)) : vec3(0);
+        case DEBUG_MATERIAL_ID:
+            return hitInfo.didHit ? IdColor(hitInfo.materialId + 1u) : vec3(0);
+    }
+    return vec3(0);
+}
+
 // The main function
 void main()
 {
     // Create seed for RNG
     vec2 uv = vec2( gl_FragCoord.x / camera.screenSize.x, gl_FragCoord.y / camera.screenSize.y );
     uint i = uint( gl_FragCoord.y * camera.screenSize.x + gl_FragCoord.x );
-    uint seed = i; //+ Frame * 719393; // TODO: Add frame counter so RNG changes over time
-
-    // Calculate focus point
-    float   planeHeight = camera.focusDistance * tan(camera.fov * 0.5 * PI / 180.0) * 2.0,
-            planeWidth = planeHeight * (camera.screenSize.x / camera.screenSize.y);
-    vec3    viewParams = vec3( planeWidth, planeHeight, camera.focusDistance );
+    uint seed = i + frame * 719393;
 
-    vec3    focusPointLocal = vec3(uv - 0.5, 1) * viewParams,
-            focusPoint = (camera.localToWorld * vec4(focusPointLocal, 1)).xyz,
-            camUp = normalize(camera.localToWorld[1].xyz),
-            camRight = normalize(camera.localToWorld[0].xyz);
+    // Split the screen between the eyes when rendering in stereo
+    vec2 imageSize = camera.screenSize;
+    float eyeOffset = 0;
+    if (camera.stereoMode == STEREO_SIDE_BY_SIDE) {
+        bool rightEye = uv.x >= 0.5;
+        uv.x = uv.x * 2 - float(rightEye);
+        imageSize.x *= 0.5;
+        eyeOffset = camera.ipd * (rightEye ? 0.5 : -0.5);
+    }
+    else if (camera.stereoMode == STEREO_TOP_BOTTOM) {
+        bool rightEye = uv.y < 0.5;
+        uv.y = uv.y * 2 - float(!rightEye);
+        imageSize.y *= 0.5;
+        eyeOffset = camera.ipd * (rightEye ? 0.5 : -0.5);
+    }
 
     // Fire rays
     Ray ray;
+    SurfaceInfo surface;
+    LightInfo light;
     vec3 totalIncomingLight = vec3(0);
+    SurfaceInfo totalSurface = SurfaceInfo(vec3(0), vec3(0), 0.0, 0.0, 0.0);
+    LightInfo totalLight = LightInfo(vec3(0), vec3(0), vec3(0));
 
     for ( int i = 0; i < settings.raysPerFrag; i++ )
     {
-        // Calculate ray origin and dir
-        vec2 jitter = randVecCartesianNormDist(seed) * settings.divergeStrength / camera.screenSize.x;
-        vec3 focusPointJittered = focusPoint + camRight*jitter.x + camUp*jitter.y;
+        // (Rays outside the image stay black)
+        if (!GenerateCameraRay(uv, imageSize, eyeOffset, seed, ray))
+            continue;
+
+        // (Debug views only produce a color, the other outputs stay empty)
+        if (settings.debugMode != DEBUG_OFF) {
+            totalIncomingLight += DebugColor(ray, seed);
+        }
+        else {
+            totalIncomingLight += Trace(ray, seed, surface, light);
+            totalSurface.albedo += surface.albedo;
+            totalSurface.normal += surface.normal;
+            totalSurface.depth += surface.depth;
+            totalLight.emission += light.emission;
+            totalLight.direct += light.direct;
+            totalLight.indirect += light.indirect;
 
-        ray.origin = camera.pos;
-        ray.dir = normalize(focusPointJittered - ray.origin);
-        totalIncomingLight += Trace(ray, seed);
+            // (Ids cannot be averaged, so the first ray's are used)
+            if (i == 0) {
+                totalSurface.objectId = surface.objectId;
+                totalSurface.materialId = surface.materialId;
+            }
+        }
     }
 
     // Return final color (average of the frag's rays)
+    // The alpha is used to blend the color into the accumulated average of the previous frames
+    float blend = 1.0 / float(accumulatedFrames + 1);
     vec3 fragCol = totalIncomingLight / settings.raysPerFrag;
-    color = vec4( fragCol, 1 );
+    color = vec4( fragCol, blend );
+
+    // Return the averaged surface information
+    albedo = vec4( totalSurface.albedo / settings.raysPerFrag, blend );
+    normal = vec4( totalSurface.normal / settings.raysPerFrag, blend );
+    depth = vec4( vec3(totalSurface.depth / settings.raysPerFrag), blend );
+    emission = vec4( totalLight.emission / settings.raysPerFrag, blend );
+    id = vec4( totalSurface.objectId, totalSurface.materialId, 0, 1 );
+    direct = vec4( totalLight.direct / settings.raysPerFrag, blend );
+    indirect = vec4( totalLight.indirect / settings.raysPerFrag, blend );
 }
\ No newline at end of file
@@ -0,0 +1,42 @@
+#version 440 core
+
+// --- Constants ---
+const ivec2 GLYPH_SIZE = ivec2(8, 16);  // Size of a glyph, in font pixels
+
+// Text styles (TextStyle), as text and background colors
+const uint STYLE_NORMAL     = 0;
+const uint STYLE_HIGHLIGHT  = 1;
+const uint STYLE_ACCENT     = 2;
+const vec4 TEXT_COLORS[3]       = vec4[]( vec4(1, 1, 1, 1), vec4(0, 0, 0, 1), vec4(1, 0.8, 0.3, 1) );
+const vec4 BACKGROUND_COLORS[3] = vec4[]( vec4(0, 0, 0, 0.6), vec4(0.85, 0.85, 0.85, 0.9), vec4(0, 0, 0, 0.75) );
+
+// --- Inputs / outputs ---
+out vec4 color;
+
+uniform usampler2D font;    // One glyph per row, one byte (8 pixels, leftmost in the highest bit) per texel
+uniform usampler2D text;    // The character (r, 0 is empty) and style (g) of each cell of the screen, starting at the top left
+uniform uint firstChar;     // The character of the font's first glyph
+uniform int scale;          // Screen pixels per font pixel
+uniform int screenHeight;   // Height of the screen, in pixels
+
+// The main function
+void main()
+{
+    // Font pixel, starting at the top left of the screen
+    ivec2   pixel = ivec2(gl_FragCoord.x, screenHeight - 1 - int(gl_FragCoord.y)) / scale,
+            cell = pixel / GLYPH_SIZE,
+            glyphPixel = pixel % GLYPH_SIZE;
+
+    // (The grid may not cover the edges of the screen)
+    if (any(greaterThanEqual(cell, textureSize(text, 0))))
+        discard;
+
+    uvec2 cellData = texelFetch(text, cell, 0).rg;
+    if (cellData.r == 0)
+        discard;
+
+    uint style = min(cellData.g, STYLE_ACCENT);
+    uint glyphRow = texelFetch(font, ivec2(glyphPixel.y, cellData.r - firstChar), 0).r;
+    bool lit = ((glyphRow >> (7 - glyphPixel.x)) & 1u) != 0;
+    color = lit ? TEXT_COLORS[style] : BACKGROUND_COLORS[style];
+}
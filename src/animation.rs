@@ -0,0 +1,538 @@
+use crate::camera::Camera;
+use crate::material::MaterialLibrary;
+use crate::scene::Scene;
+
+/**
+ * Enum for the different ways of interpolating between keyframes.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    // Catmull-Rom spline through the keyframes
+    Cubic,
+}
+
+/**
+ * Interpolation functions.
+ */
+impl Interpolation {
+    /**
+     * Parses an interpolation from its name.
+     */
+    fn from_str( name: &str ) -> Result<Interpolation, String> {
+        match name {
+            "step"      => { Ok(Interpolation::Step) },
+            "linear"    => { Ok(Interpolation::Linear) },
+            "cubic"     => { Ok(Interpolation::Cubic) },
+            e => { Err(format!("Unknown interpolation '{e}'")) },
+        }
+    }
+}
+
+/**
+ * Enum for the properties which can be animated.
+ * Node targets refer to scene nodes by name, and material targets to materials in the material library by name.
+ */
+#[derive(Clone, PartialEq)]
+pub enum AnimationTarget {
+    NodeTranslation(String),
+    NodeRotation(String),
+    NodeScale(String),
+    MaterialColor(String),
+    // (rgb, strength)
+    MaterialEmission(String),
+    CameraPosition,
+    CameraAngle,
+    CameraFov,
+}
+
+/**
+ * AnimationTarget functions.
+ */
+impl AnimationTarget {
+    /**
+     * Parses an animation target from its property name, and node or material name if the property requires one.
+     */
+    fn from_str( property: &str, name: Option<&str> ) -> Result<AnimationTarget, String> {
+        let node_name = || name.map( |name| name.to_string() ).ok_or( format!("Property '{property}' requires a node name") );
+        let material_name = || name.map( |name| name.to_string() ).ok_or( format!("Property '{property}' requires a material name") );
+        match property {
+            "node.translation"  => { Ok(AnimationTarget::NodeTranslation( node_name()? )) },
+            "node.rotation"     => { Ok(AnimationTarget::NodeRotation( node_name()? )) },
+            "node.scale"        => { Ok(AnimationTarget::NodeScale( node_name()? )) },
+            "material.color"    => { Ok(AnimationTarget::MaterialColor( material_name()? )) },
+            "material.emission" => { Ok(AnimationTarget::MaterialEmission( material_name()? )) },
+            "camera.position"   => { Ok(AnimationTarget::CameraPosition) },
+            "camera.angle"      => { Ok(AnimationTarget::CameraAngle) },
+            "camera.fov"        => { Ok(AnimationTarget::CameraFov) },
+            e => { Err(format!("Unknown property '{e}'")) },
+        }
+    }
+}
+
+/**
+ * Struct for a keyframe.
+ * Values are always stored as vec4s, properties with fewer components only use the first ones.
+ */
+#[derive(Clone, Copy)]
+pub struct Keyframe {
+    pub time: f32,
+    pub value: glm::Vec4,
+}
+
+/**
+ * Struct for an animation track, which animates a single property.
+ */
+pub struct Track {
+    pub target: AnimationTarget,
+    pub interpolation: Interpolation,
+    keyframes: Vec<Keyframe>,
+}
+
+/**
+ * Track functions.
+ */
+#[allow(dead_code)]
+impl Track {
+    /**
+     * Creates a new track without any keyframes.
+     *
+     * @param target The property which is animated.
+     * @param interpolation How to interpolate between keyframes.
+     */
+    pub fn new( target: AnimationTarget, interpolation: Interpolation ) -> Track {
+        Track { target, interpolation, keyframes: Vec::new() }
+    }
+
+    /**
+     * Adds a keyframe to the track, keeping the keyframes sorted by time.
+     *
+     * @param time The time of the keyframe, in seconds.
+     * @param value The value of the property at that time.
+     */
+    pub fn add_keyframe( &mut self, time: f32, value: glm::Vec4 ) -> &mut Track {
+        let index = self.keyframes.partition_point( |keyframe| keyframe.time <= time );
+        self.keyframes.insert( index, Keyframe { time, value } );
+        self
+    }
+
+    /**
+     * Gets the keyframes of the track, sorted by time.
+     */
+    pub fn keyframes( &self ) -> &[Keyframe] {
+        &self.keyframes
+    }
+
+    /**
+     * Gets the time of the last keyframe.
+     */
+    pub fn duration( &self ) -> f32 {
+        self.keyframes.last().map_or( 0.0, |keyframe| keyframe.time )
+    }
+
+    /**
+     * Evaluates the track at a given time.
+     * Before the first and after the last keyframe, the value is held constant.
+     *
+     * @param time The time, in seconds.
+     * @return The interpolated value, or None if the track has no keyframes.
+     */
+    pub fn sample( &self, time: f32 ) -> Option<glm::Vec4> {
+        let ( first, last ) = ( self.keyframes.first()?, self.keyframes.last()? );
+        if time <= first.time { return Some( first.value ) }
+        if time >= last.time { return Some( last.value ) }
+
+        // Find the keyframes surrounding the time
+        let next = self.keyframes.partition_point( |keyframe| keyframe.time <= time );
+        let ( k1, k2 ) = ( &self.keyframes[next - 1], &self.keyframes[next] );
+        let t = ( time - k1.time ) / ( k2.time - k1.time );
+
+        Some( match self.interpolation {
+            Interpolation::Step => { k1.value },
+            Interpolation::Linear => { glm::lerp( &k1.value, &k2.value, t ) },
+            Interpolation::Cubic => {
+                // Neighbouring keyframes are clamped at the ends of the track
+                let ( p0, p1, p2, p3 ) = (
+                    self.keyframes[next.saturating_sub( 2 )].value,
+                    k1.value,
+                    k2.value,
+                    self.keyframes[( next + 1 ).min( self.keyframes.len() - 1 )].value,
+                );
+                let ( t2, t3 ) = ( t * t, t * t * t );
+                ( p1 * 2.0
+                    + ( p2 - p0 ) * t
+                    + ( p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3 ) * t2
+                    + ( p1 * 3.0 - p0 - p2 * 3.0 + p3 ) * t3 ) * 0.5
+            },
+        } )
+    }
+}
+
+/**
+ * Struct for an animation, which is a collection of tracks.
+ */
+pub struct Animation {
+    pub tracks: Vec<Track>,
+    pub looping: bool,
+}
+
+/**
+ * Animation functions.
+ */
+#[allow(dead_code)]
+impl Animation {
+    /**
+     * Creates a new, empty, animation.
+     */
+    pub fn new() -> Animation {
+        Animation { tracks: Vec::new(), looping: false }
+    }
+
+    /**
+     * Loads an animation from a file.
+     * The file consists of lines on the following forms, where lines starting with '#' are ignored:
+     *  loop <true/false>
+     *  track <property> [node/material name] <step/linear/cubic>
+     *  key <time> <value> [value] [value] [value]
+     * Keys belong to the last track declared above them.
+     *
+     * @param path The path to the animation file.
+     * @return The animation, or an error message if the file could not be read or parsed.
+     */
+    pub fn load_from_file( path: &str ) -> Result<Animation, String> {
+        let source = std::fs::read_to_string( path )
+            .map_err( |err| format!( "Failed to read animation file '{path}': {err}" ) )?;
+        Animation::parse( &source, path )
+    }
+
+    /**
+     * Parses an animation from the contents of an animation file (see load_from_file).
+     *
+     * @param source The contents of the file.
+     * @param path The path of the file, used in error messages.
+     *
+     * @return The animation, or an error message if the file could not be parsed.
+     */
+    pub fn parse( source: &str, path: &str ) -> Result<Animation, String> {
+        let mut animation = Animation::new();
+        for ( line_number, line ) in source.lines().enumerate() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let error = |message: String| format!( "{path}:{}: {message}", line_number + 1 );
+
+            match words.as_slice() {
+                [] => { },
+                [comment, ..] if comment.starts_with( '#' ) => { },
+
+                ["loop", looping] => {
+                    animation.looping = looping.parse().map_err( |_| error( format!( "Invalid boolean '{looping}'" ) ) )?;
+                },
+
+                ["track", property, interpolation] | ["track", property, _, interpolation] => {
+                    let name = if words.len() == 4 { Some( words[2] ) } else { None };
+                    animation.tracks.push( Track::new(
+                        AnimationTarget::from_str( property, name ).map_err( error )?,
+                        Interpolation::from_str( interpolation ).map_err( error )?,
+                    ) );
+                },
+
+                ["key", time, values @ ..] if !values.is_empty() && values.len() <= 4 => {
+                    let parse = |word: &str| word.parse::<f32>().map_err( |_| error( format!( "Invalid number '{word}'" ) ) );
+                    let mut value = glm::Vec4::zeros();
+                    for ( i, word ) in values.iter().enumerate() {
+                        value[i] = parse( word )?;
+                    }
+                    let time = parse( time )?;
+
+                    animation.tracks.last_mut()
+                        .ok_or( error( "Keyframe declared before any track".to_string() ) )?
+                        .add_keyframe( time, value );
+                },
+
+                _ => { return Err( error( format!( "Could not parse '{line}'" ) ) ) },
+            }
+        }
+
+        Ok( animation )
+    }
+
+    /**
+     * Gets the duration of the animation, which is the time of the last keyframe in any track.
+     */
+    pub fn duration( &self ) -> f32 {
+        self.tracks.iter().map( |track| track.duration() ).fold( 0.0, f32::max )
+    }
+
+    /**
+     * Evaluates the tracks targeting scene nodes at a given time and applies the values to them.
+     * Tracks targeting nodes which do not exist are ignored.
+     *
+     * @param time The time, in seconds. Wraps around if the animation is looping.
+     * @param scene The scene.
+     */
+    pub fn apply_to_scene( &self, time: f32, scene: &mut Scene ) {
+        let time = self.wrap_time( time );
+        for track in &self.tracks {
+            let Some( value ) = track.sample( time ) else { continue };
+            match &track.target {
+                AnimationTarget::NodeTranslation( name ) => {
+                    if let Some( node ) = scene.node_mut( name ) { node.transform.translation = value.xyz(); }
+                },
+                AnimationTarget::NodeRotation( name ) => {
+                    if let Some( node ) = scene.node_mut( name ) { node.transform.rotation = value.xyz(); }
+                },
+                AnimationTarget::NodeScale( name ) => {
+                    if let Some( node ) = scene.node_mut( name ) { node.transform.scale = value.xyz(); }
+                },
+                _ => { },
+            }
+        }
+    }
+
+    /**
+     * Evaluates the tracks targeting materials at a given time and applies the values to them.
+     * Tracks targeting materials which do not exist are ignored.
+     *
+     * @param time The time, in seconds. Wraps around if the animation is looping.
+     * @param materials The material library.
+     */
+    pub fn apply_to_materials( &self, time: f32, materials: &mut MaterialLibrary ) {
+        let time = self.wrap_time( time );
+        for track in &self.tracks {
+            let Some( value ) = track.sample( time ) else { continue };
+            match &track.target {
+                AnimationTarget::MaterialColor( name ) => {
+                    if let Some( material ) = materials.by_name_mut( name ) { material.color = value; }
+                },
+                AnimationTarget::MaterialEmission( name ) => {
+                    if let Some( material ) = materials.by_name_mut( name ) { material.emission_color = value; }
+                },
+                _ => { },
+            }
+        }
+    }
+
+    /**
+     * Evaluates the tracks targeting the camera at a given time and applies the values to it.
+     *
+     * @param time The time, in seconds. Wraps around if the animation is looping.
+     * @param camera The camera.
+     */
+    pub fn apply_to_camera( &self, time: f32, camera: &mut Camera ) {
+        let time = self.wrap_time( time );
+        for track in &self.tracks {
+            let Some( value ) = track.sample( time ) else { continue };
+            match &track.target {
+                AnimationTarget::CameraPosition => {
+                    camera.set_vars( Some( value.xyz() ), None, None, None, None );
+                },
+                AnimationTarget::CameraAngle => {
+                    camera.set_vars( None, Some( value.xyz() ), None, None, None );
+                },
+                AnimationTarget::CameraFov => {
+                    camera.set_vars( None, None, Some( value.x ), None, None );
+                },
+                _ => { },
+            }
+        }
+    }
+
+    /**
+     * Wraps a time around the animation's duration if it is looping.
+     *
+     * @param time The time, in seconds.
+     * @return The wrapped time.
+     */
+    fn wrap_time( &self, time: f32 ) -> f32 {
+        let duration = self.duration();
+        if self.looping && duration > 0.0 { time.rem_euclid( duration ) } else { time }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Creates a track with scalar keyframes at the given times.
+     */
+    fn track( interpolation: Interpolation, keys: &[( f32, f32 )] ) -> Track {
+        let mut track = Track::new( AnimationTarget::CameraFov, interpolation );
+        for &( time, value ) in keys {
+            track.add_keyframe( time, glm::vec4( value, 0.0, 0.0, 0.0 ) );
+        }
+        track
+    }
+
+    /**
+     * Samples the first component of a track.
+     */
+    fn sample( track: &Track, time: f32 ) -> f32 {
+        track.sample( time ).unwrap().x
+    }
+
+    /**
+     * Asserts that two values are (almost) equal.
+     */
+    fn assert_close( actual: f32, expected: f32 ) {
+        assert!( ( actual - expected ).abs() < 1e-5, "expected {expected}, got {actual}" );
+    }
+
+    #[test]
+    fn empty_tracks_have_no_value() {
+        assert!( Track::new( AnimationTarget::CameraFov, Interpolation::Linear ).sample( 1.0 ).is_none() );
+    }
+
+    #[test]
+    fn values_are_held_outside_of_the_keyframes() {
+        for interpolation in [ Interpolation::Step, Interpolation::Linear, Interpolation::Cubic ] {
+            let track = track( interpolation, &[ ( 1.0, 10.0 ), ( 2.0, 20.0 ), ( 3.0, 40.0 ) ] );
+            assert_eq!( sample( &track, 0.0 ), 10.0 );
+            assert_eq!( sample( &track, 1.0 ), 10.0 );
+            assert_eq!( sample( &track, 3.0 ), 40.0 );
+            assert_eq!( sample( &track, 5.0 ), 40.0 );
+        }
+    }
+
+    #[test]
+    fn step_holds_the_previous_keyframe() {
+        let track = track( Interpolation::Step, &[ ( 0.0, 1.0 ), ( 1.0, 2.0 ), ( 2.0, 3.0 ) ] );
+        assert_eq!( sample( &track, 0.5 ), 1.0 );
+        assert_eq!( sample( &track, 0.999 ), 1.0 );
+        assert_eq!( sample( &track, 1.0 ), 2.0 );
+        assert_eq!( sample( &track, 1.5 ), 2.0 );
+    }
+
+    #[test]
+    fn linear_interpolates_between_keyframes() {
+        let track = track( Interpolation::Linear, &[ ( 0.0, 0.0 ), ( 2.0, 10.0 ), ( 3.0, 0.0 ) ] );
+        assert_close( sample( &track, 0.5 ), 2.5 );
+        assert_close( sample( &track, 2.0 ), 10.0 );
+        assert_close( sample( &track, 2.25 ), 7.5 );
+    }
+
+    #[test]
+    fn cubic_passes_through_the_keyframes_smoothly() {
+        let track = track( Interpolation::Cubic, &[ ( 0.0, 0.0 ), ( 1.0, 1.0 ), ( 2.0, 0.0 ), ( 3.0, 1.0 ) ] );
+        assert_close( sample( &track, 1.0 ), 1.0 );
+        assert_close( sample( &track, 2.0 ), 0.0 );
+
+        // Unlike linear interpolation, the curve eases out of and into the keyframes
+        assert_close( sample( &track, 1.5 ), 0.5 );
+        assert!( sample( &track, 1.25 ) > 0.75 );
+        assert!( sample( &track, 1.75 ) < 0.25 );
+
+        // The first segment uses its first keyframe as the missing neighbour: (p2 * t + (4 * p2 - p3) * t^2 + (p3 - 3 * p2) * t^3) / 2
+        assert_close( sample( &track, 0.5 ), 0.5 * ( 1.0 * 0.5 + 4.0 * 0.25 - 3.0 * 0.125 ) );
+    }
+
+    #[test]
+    fn cubic_interpolates_each_component() {
+        let mut track = Track::new( AnimationTarget::CameraPosition, Interpolation::Cubic );
+        track.add_keyframe( 0.0, glm::vec4( 0.0, 0.0, 0.0, 0.0 ) )
+            .add_keyframe( 1.0, glm::vec4( 1.0, 2.0, 3.0, 0.0 ) )
+            .add_keyframe( 2.0, glm::vec4( 2.0, 4.0, 6.0, 0.0 ) )
+            .add_keyframe( 3.0, glm::vec4( 3.0, 6.0, 9.0, 0.0 ) );
+
+        // Between evenly spaced keyframes on a line, the curve stays on the line
+        let value = track.sample( 1.5 ).unwrap();
+        assert_close( value.x, 1.5 );
+        assert_close( value.y, 3.0 );
+        assert_close( value.z, 4.5 );
+    }
+
+    #[test]
+    fn keyframes_are_sorted_by_time() {
+        let track = track( Interpolation::Linear, &[ ( 2.0, 20.0 ), ( 0.0, 0.0 ), ( 1.0, 10.0 ) ] );
+        let times: Vec<f32> = track.keyframes().iter().map( |keyframe| keyframe.time ).collect();
+        assert_eq!( times, vec![ 0.0, 1.0, 2.0 ] );
+        assert_eq!( track.duration(), 2.0 );
+    }
+
+    #[test]
+    fn looping_wraps_the_time_around_the_duration() {
+        let mut animation = Animation::new();
+        animation.tracks.push( track( Interpolation::Linear, &[ ( 0.0, 0.0 ), ( 4.0, 4.0 ) ] ) );
+        assert_eq!( animation.wrap_time( 5.0 ), 5.0 );
+
+        animation.looping = true;
+        assert_close( animation.wrap_time( 5.0 ), 1.0 );
+        assert_close( animation.wrap_time( 8.5 ), 0.5 );
+        assert_close( animation.wrap_time( -1.0 ), 3.0 );
+        assert_close( animation.wrap_time( 2.0 ), 2.0 );
+    }
+
+    #[test]
+    fn looping_an_empty_animation_keeps_the_time() {
+        let animation = Animation { tracks: Vec::new(), looping: true };
+        assert_eq!( animation.wrap_time( 3.0 ), 3.0 );
+    }
+
+    #[test]
+    fn loads_the_scene_animation() {
+        let animation = Animation::load_from_file( "resources/animations/scene.anim" ).unwrap();
+        assert!( animation.looping );
+        assert_eq!( animation.tracks.len(), 1 );
+
+        let track = &animation.tracks[0];
+        assert!( track.target == AnimationTarget::NodeTranslation( "orange_sphere".to_string() ) );
+        assert!( track.interpolation == Interpolation::Cubic );
+        assert_eq!( track.keyframes().len(), 17 );
+        assert_close( animation.duration(), 12.5664 );
+        assert_eq!( track.keyframes()[1].value, glm::vec4( 38.27, 70.71, 0.0, 0.0 ) );
+    }
+
+    #[test]
+    fn parses_every_target() {
+        let source = "
+            # Comment
+            track node.rotation knight linear
+            key 0 1 2 3
+            track node.scale knight step
+            track material.color gold linear
+            track material.emission blue_light linear
+            key 1 1 1 1 5
+            track camera.position linear
+            track camera.angle linear
+            track camera.fov cubic
+            key 0 60
+        ";
+        let animation = Animation::parse( source, "test.anim" ).unwrap();
+        let targets: Vec<AnimationTarget> = animation.tracks.iter().map( |track| track.target.clone() ).collect();
+        assert!( targets == vec![
+            AnimationTarget::NodeRotation( "knight".to_string() ),
+            AnimationTarget::NodeScale( "knight".to_string() ),
+            AnimationTarget::MaterialColor( "gold".to_string() ),
+            AnimationTarget::MaterialEmission( "blue_light".to_string() ),
+            AnimationTarget::CameraPosition,
+            AnimationTarget::CameraAngle,
+            AnimationTarget::CameraFov,
+        ] );
+        assert!( !animation.looping );
+        assert_eq!( animation.tracks[3].keyframes()[0].value, glm::vec4( 1.0, 1.0, 1.0, 5.0 ) );
+        assert_eq!( animation.tracks[6].keyframes()[0].value, glm::vec4( 60.0, 0.0, 0.0, 0.0 ) );
+    }
+
+    #[test]
+    fn malformed_files_are_errors() {
+        let cases = [
+            ( "track camera.fov linear\nkey 0 1\nkey", "test.anim:3: Could not parse 'key'" ),
+            ( "track camera.fov linear\nkey 0 1 2 3 4 5", "Could not parse" ),
+            ( "track camera.fov linear\nkey zero 1", "Invalid number 'zero'" ),
+            ( "track camera.fov linear\nkey 0 one", "Invalid number 'one'" ),
+            ( "key 0 1", "Keyframe declared before any track" ),
+            ( "loop maybe", "Invalid boolean 'maybe'" ),
+            ( "track camera.fov smooth", "Unknown interpolation 'smooth'" ),
+            ( "track node.position knight linear", "Unknown property 'node.position'" ),
+            ( "track camera.zoom linear", "Unknown property 'camera.zoom'" ),
+            ( "track node.translation linear", "requires a node name" ),
+            ( "track material.color linear", "requires a material name" ),
+            ( "animate knight", "Could not parse 'animate knight'" ),
+        ];
+        for ( source, message ) in cases {
+            let error = Animation::parse( source, "test.anim" ).err().unwrap_or_else( || panic!( "{source:?} was parsed" ) );
+            assert!( error.contains( message ), "'{error}' does not mention '{message}'" );
+        }
+        assert!( Animation::load_from_file( "resources/animations/missing.anim" ).is_err() );
+    }
+}
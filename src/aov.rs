@@ -0,0 +1,74 @@
+/**
+ * Enum for the outputs (AOVs, arbitrary output variables) of the raytracing shader.
+ * The values are the attachments of the render target the shader draws to, matching the shader's output locations.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Aov {
+    // All light, the "beauty" image
+    Color = 0,
+    // Color of the first surface hit, or of the environment
+    Albedo = 1,
+    // World space normal of the first surface hit
+    Normal = 2,
+    // Distance to the first surface hit
+    Depth = 3,
+    // Light emitted by the first surface hit, or the environment
+    Emission = 4,
+    // Object id (r) and material id (g) of the first surface hit
+    Id = 5,
+    // Light reflected once
+    Direct = 6,
+    // Light reflected more than once
+    Indirect = 7,
+}
+
+// All AOVs, in order
+pub const AOVS: [Aov; 8] = [Aov::Color, Aov::Albedo, Aov::Normal, Aov::Depth, Aov::Emission, Aov::Id, Aov::Direct, Aov::Indirect];
+
+/**
+ * Aov functions.
+ */
+impl Aov {
+    /**
+     * Gets the index of the render target attachment holding the AOV.
+     */
+    pub fn attachment( self ) -> usize {
+        self as usize
+    }
+
+    /**
+     * Gets the next AOV, for cycling through them.
+     */
+    pub fn next( self ) -> Aov {
+        AOVS[( self as usize + 1 ) % AOVS.len()]
+    }
+
+    /**
+     * Gets the name of the AOV, as used in file names.
+     */
+    pub fn name( self ) -> &'static str {
+        match self {
+            Aov::Color => "color",
+            Aov::Albedo => "albedo",
+            Aov::Normal => "normal",
+            Aov::Depth => "depth",
+            Aov::Emission => "emission",
+            Aov::Id => "id",
+            Aov::Direct => "direct",
+            Aov::Indirect => "indirect",
+        }
+    }
+
+    /**
+     * Gets how the present shader should visualize the AOV, one of its VIEW_ constants.
+     */
+    pub fn view( self ) -> u32 {
+        match self {
+            Aov::Color | Aov::Emission | Aov::Direct | Aov::Indirect => 0,
+            Aov::Albedo => 1,
+            Aov::Normal => 2,
+            Aov::Depth => 3,
+            Aov::Id => 4,
+        }
+    }
+}
@@ -0,0 +1,283 @@
+use std::path::{Path, PathBuf};
+
+use glutin::event::VirtualKeyCode;
+
+use crate::camera::Camera;
+
+// Amount of bookmark slots (one for each digit key)
+pub const BOOKMARK_SLOTS: usize = 10;
+
+/**
+ * Struct for a saved camera viewpoint.
+ */
+#[derive(Clone, Copy)]
+pub struct Bookmark {
+    pub pos: glm::Vec3,
+    pub ang: glm::Vec3,
+    pub fov: f32,
+}
+
+/**
+ * Bookmark functions.
+ */
+impl Bookmark {
+    /**
+     * Creates a bookmark of the camera's current viewpoint.
+     *
+     * @param camera The camera.
+     */
+    pub fn from_camera( camera: &Camera ) -> Bookmark {
+        Bookmark { pos: camera.pos(), ang: camera.ang(), fov: camera.fov() }
+    }
+
+    /**
+     * Moves the camera to the bookmarked viewpoint.
+     *
+     * @param camera The camera.
+     */
+    pub fn apply( &self, camera: &mut Camera ) {
+        camera.set_vars( Some( self.pos ), Some( self.ang ), Some( self.fov ), None, None );
+    }
+}
+
+/**
+ * Struct for a set of numbered bookmarks, persisted to a file.
+ */
+pub struct Bookmarks {
+    slots: [Option<Bookmark>; BOOKMARK_SLOTS],
+    path: PathBuf,
+}
+
+/**
+ * Bookmarks functions.
+ */
+impl Bookmarks {
+    /**
+     * Loads bookmarks from a file.
+     * If the file does not exist, or cannot be parsed, the bookmarks start out empty.
+     * Each line of the file is on the form: <slot> <pos x> <pos y> <pos z> <ang x> <ang y> <ang z> <fov>
+     *
+     * @param path The path of the bookmark file. Also used when saving.
+     */
+    pub fn load( path: &Path ) -> Bookmarks {
+        let mut bookmarks = Bookmarks { slots: [None; BOOKMARK_SLOTS], path: path.to_path_buf() };
+
+        let Ok( source ) = std::fs::read_to_string( path ) else { return bookmarks };
+        for ( line_number, line ) in source.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with( '#' ) {
+                continue;
+            }
+
+            let values: Vec<f32> = line.split_whitespace().filter_map( |word| word.parse().ok() ).collect();
+            match values.as_slice() {
+                &[slot, px, py, pz, ax, ay, az, fov] if ( slot as usize ) < BOOKMARK_SLOTS => {
+                    bookmarks.slots[slot as usize] = Some( Bookmark {
+                        pos: glm::vec3( px, py, pz ),
+                        ang: glm::vec3( ax, ay, az ),
+                        fov,
+                    } );
+                },
+                _ => println!( "{}:{}: Ignoring invalid bookmark '{line}'", path.display(), line_number + 1 ),
+            }
+        }
+
+        bookmarks
+    }
+
+    /**
+     * Saves the bookmarks to the file they were loaded from.
+     *
+     * @return Ok if the bookmarks were saved, an error message otherwise.
+     */
+    pub fn save( &self ) -> Result<(), String> {
+        let mut source = String::from( "# <slot> <pos x> <pos y> <pos z> <ang x> <ang y> <ang z> <fov>\n" );
+        for ( slot, bookmark ) in self.slots.iter().enumerate() {
+            if let Some( b ) = bookmark {
+                source += &format!( "{slot} {} {} {} {} {} {} {}\n", b.pos.x, b.pos.y, b.pos.z, b.ang.x, b.ang.y, b.ang.z, b.fov );
+            }
+        }
+
+        std::fs::write( &self.path, source ).map_err( |err| format!( "Failed to save bookmarks to '{}': {err}", self.path.display() ) )
+    }
+
+    /**
+     * Gets the bookmark in a slot.
+     *
+     * @param slot The slot.
+     * @return The bookmark, or None if the slot is empty or out of range.
+     */
+    pub fn get( &self, slot: usize ) -> Option<Bookmark> {
+        self.slots.get( slot ).copied().flatten()
+    }
+
+    /**
+     * Stores a bookmark in a slot, and saves the bookmarks to disk.
+     *
+     * @param slot The slot.
+     * @param bookmark The bookmark.
+     *
+     * @return Ok if the bookmarks were saved, an error message otherwise.
+     */
+    pub fn set( &mut self, slot: usize, bookmark: Bookmark ) -> Result<(), String> {
+        *self.slots.get_mut( slot ).ok_or( format!( "Bookmark slot {slot} does not exist" ) )? = Some( bookmark );
+        self.save()
+    }
+}
+
+/**
+ * Struct for a smooth camera transition between two viewpoints.
+ */
+pub struct CameraTransition {
+    from: Bookmark,
+    to: Bookmark,
+    elapsed: f32,
+    duration: f32,
+}
+
+/**
+ * CameraTransition functions.
+ */
+impl CameraTransition {
+    /**
+     * Creates a transition.
+     *
+     * @param from The viewpoint to start at, usually the camera's current one.
+     * @param to The viewpoint to end at.
+     * @param duration The duration of the transition, in seconds.
+     */
+    pub fn new( from: Bookmark, to: Bookmark, duration: f32 ) -> CameraTransition {
+        // Rotate the shortest way around, as the camera's angles are not wrapped
+        let mut to = to;
+        let two_pi = glm::two_pi::<f32>();
+        for i in 0..3 {
+            let difference = ( to.ang[i] - from.ang[i] + glm::pi::<f32>() ).rem_euclid( two_pi ) - glm::pi::<f32>();
+            to.ang[i] = from.ang[i] + difference;
+        }
+
+        CameraTransition { from, to, elapsed: 0.0, duration }
+    }
+
+    /**
+     * Advances the transition and moves the camera accordingly.
+     *
+     * @param dt The time since the last update, in seconds.
+     * @param camera The camera.
+     *
+     * @return True if the transition has finished.
+     */
+    pub fn update( &mut self, dt: f32, camera: &mut Camera ) -> bool {
+        self.elapsed += dt;
+        let t = if self.duration > 0.0 { ( self.elapsed / self.duration ).min( 1.0 ) } else { 1.0 };
+        let t = t * t * ( 3.0 - 2.0 * t ); // Smoothstep, to ease in and out
+
+        Bookmark {
+            pos: glm::lerp( &self.from.pos, &self.to.pos, t ),
+            ang: glm::lerp( &self.from.ang, &self.to.ang, t ),
+            fov: self.from.fov + ( self.to.fov - self.from.fov ) * t,
+        }.apply( camera );
+
+        self.elapsed >= self.duration
+    }
+}
+
+/**
+ * Gets the bookmark slot belonging to a digit key.
+ *
+ * @param key The key.
+ * @return The slot, or None if the key is not a digit.
+ */
+pub fn slot_for_key( key: VirtualKeyCode ) -> Option<usize> {
+    match key {
+        VirtualKeyCode::Key0 => Some(0),
+        VirtualKeyCode::Key1 => Some(1),
+        VirtualKeyCode::Key2 => Some(2),
+        VirtualKeyCode::Key3 => Some(3),
+        VirtualKeyCode::Key4 => Some(4),
+        VirtualKeyCode::Key5 => Some(5),
+        VirtualKeyCode::Key6 => Some(6),
+        VirtualKeyCode::Key7 => Some(7),
+        VirtualKeyCode::Key8 => Some(8),
+        VirtualKeyCode::Key9 => Some(9),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Gets a path in the temporary directory for a test's bookmark file, removing any file left there by an earlier run.
+     */
+    fn temp_path( name: &str ) -> PathBuf {
+        let path = std::env::temp_dir().join( format!( "bookmarks_{}_{name}.txt", std::process::id() ) );
+        let _ = std::fs::remove_file( &path );
+        path
+    }
+
+    /**
+     * Asserts that two values are (almost) equal.
+     */
+    fn assert_close( actual: f32, expected: f32 ) {
+        assert!( ( actual - expected ).abs() < 1e-5, "expected {expected}, got {actual}" );
+    }
+
+    #[test]
+    fn saved_bookmarks_are_loaded_again() {
+        let path = temp_path( "roundtrip" );
+        let mut bookmarks = Bookmarks::load( &path );
+        assert!( ( 0..BOOKMARK_SLOTS ).all( |slot| bookmarks.get( slot ).is_none() ) );
+
+        let bookmark = Bookmark { pos: glm::vec3( 1.5, -2.0, 30.25 ), ang: glm::vec3( 0.1, -3.0, 0.0 ), fov: 75.0 };
+        bookmarks.set( 3, bookmark ).unwrap();
+        assert!( bookmarks.set( BOOKMARK_SLOTS, bookmark ).is_err() );
+
+        let loaded = Bookmarks::load( &path );
+        std::fs::remove_file( &path ).unwrap();
+        let slot = loaded.get( 3 ).unwrap();
+        assert_eq!( ( slot.pos, slot.ang, slot.fov ), ( bookmark.pos, bookmark.ang, bookmark.fov ) );
+        assert!( ( 0..BOOKMARK_SLOTS ).filter( |&slot| slot != 3 ).all( |slot| loaded.get( slot ).is_none() ) );
+    }
+
+    #[test]
+    fn invalid_lines_are_skipped() {
+        let path = temp_path( "invalid" );
+        std::fs::write( &path, "\
+            # <slot> <pos x> <pos y> <pos z> <ang x> <ang y> <ang z> <fov>\n\
+            \n\
+            1 1 2 3 0 0.5 0 60\n\
+            2 1 2 3 0 zero 0 60\n\
+            4 1 2 3 0 0 0\n\
+            5 1 2 3 0 0 0 60 90\n\
+            10 1 2 3 0 0 0 60\n\
+        " ).unwrap();
+
+        let bookmarks = Bookmarks::load( &path );
+        std::fs::remove_file( &path ).unwrap();
+        let slot = bookmarks.get( 1 ).unwrap();
+        assert_eq!( ( slot.pos, slot.ang, slot.fov ), ( glm::vec3( 1.0, 2.0, 3.0 ), glm::vec3( 0.0, 0.5, 0.0 ), 60.0 ) );
+        assert!( ( 0..BOOKMARK_SLOTS ).filter( |&slot| slot != 1 ).all( |slot| bookmarks.get( slot ).is_none() ) );
+    }
+
+    #[test]
+    fn transitions_take_the_shortest_rotation() {
+        let from = Bookmark { pos: glm::vec3( 0.0, 0.0, 0.0 ), ang: glm::vec3( -3.0, 3.0, 0.0 ), fov: 60.0 };
+        let to = Bookmark { pos: glm::vec3( 10.0, 0.0, 0.0 ), ang: glm::vec3( 3.0, -3.0, 0.5 ), fov: 90.0 };
+        let mut transition = CameraTransition::new( from, to, 2.0 );
+        let mut camera = Camera::new();
+
+        // Halfway, the angles have crossed +-PI instead of turning back through zero
+        assert!( !transition.update( 1.0, &mut camera ) );
+        assert_close( camera.ang().x, -std::f32::consts::PI );
+        assert_close( camera.ang().y, std::f32::consts::PI );
+        assert_close( camera.ang().z, 0.25 );
+        assert_close( camera.pos().x, 5.0 );
+        assert_close( camera.fov(), 75.0 );
+
+        // At the end, the angles are equivalent to the bookmark's
+        assert!( transition.update( 1.0, &mut camera ) );
+        assert_close( camera.ang().x, 3.0 - std::f32::consts::TAU );
+        assert_close( camera.ang().y, std::f32::consts::TAU - 3.0 );
+        assert_close( camera.pos().x, 10.0 );
+    }
+}
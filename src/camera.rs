@@ -1,5 +1,120 @@
 extern crate nalgebra_glm as glm;
 
+use std::f32::consts::PI;
+
+// Smallest convergence distance, as the eyes aim their rays at the point this far in front of the camera
+const MIN_CONVERGENCE: f32 = 0.01;
+
+/**
+ * Enum for the projection used to generate the camera's rays.
+ * The values match the PROJECTION_ constants in the raytracing shader.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Projection {
+    // Regular pinhole/thin lens camera
+    Perspective = 0,
+    // Parallel rays, the view's size is set by the ortho height
+    Orthographic = 1,
+    // Equidistant fisheye, covering the fov within a circle
+    Fisheye = 2,
+    // Full 360 degree panorama
+    Equirectangular = 3,
+}
+
+/**
+ * Projection functions.
+ */
+impl Projection {
+    /**
+     * Gets the next projection, for cycling through them.
+     */
+    pub fn next( self ) -> Projection {
+        match self {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Fisheye,
+            Projection::Fisheye => Projection::Equirectangular,
+            Projection::Equirectangular => Projection::Perspective,
+        }
+    }
+
+    /**
+     * Gets the name of the projection, as used on the command line.
+     */
+    pub fn name( self ) -> &'static str {
+        match self {
+            Projection::Perspective => "perspective",
+            Projection::Orthographic => "orthographic",
+            Projection::Fisheye => "fisheye",
+            Projection::Equirectangular => "equirectangular",
+        }
+    }
+
+    /**
+     * Parses a projection from its name.
+     *
+     * @param name The name, see name().
+     * @return The projection, or None if the name is unknown.
+     */
+    pub fn from_name( name: &str ) -> Option<Projection> {
+        [Projection::Perspective, Projection::Orthographic, Projection::Fisheye, Projection::Equirectangular]
+            .into_iter()
+            .find( |projection| projection.name() == name )
+    }
+}
+
+/**
+ * Enum for how the camera renders in stereo.
+ * The values match the STEREO_ constants in the raytracing shader.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StereoMode {
+    // A single (mono) image
+    Off = 0,
+    // Left eye on the left half of the image, right eye on the right half
+    SideBySide = 1,
+    // Left eye on the top half of the image, right eye on the bottom half
+    TopBottom = 2,
+}
+
+/**
+ * StereoMode functions.
+ */
+impl StereoMode {
+    /**
+     * Gets the next stereo mode, for cycling through them.
+     */
+    pub fn next( self ) -> StereoMode {
+        match self {
+            StereoMode::Off => StereoMode::SideBySide,
+            StereoMode::SideBySide => StereoMode::TopBottom,
+            StereoMode::TopBottom => StereoMode::Off,
+        }
+    }
+
+    /**
+     * Gets the name of the stereo mode, as used on the command line.
+     */
+    pub fn name( self ) -> &'static str {
+        match self {
+            StereoMode::Off => "off",
+            StereoMode::SideBySide => "side-by-side",
+            StereoMode::TopBottom => "top-bottom",
+        }
+    }
+
+    /**
+     * Parses a stereo mode from its name.
+     *
+     * @param name The name, see name().
+     * @return The stereo mode, or None if the name is unknown.
+     */
+    pub fn from_name( name: &str ) -> Option<StereoMode> {
+        [StereoMode::Off, StereoMode::SideBySide, StereoMode::TopBottom]
+            .into_iter()
+            .find( |stereo_mode| stereo_mode.name() == name )
+    }
+}
+
 /**
  * Struct for a camera.
  */
@@ -11,6 +126,26 @@ pub struct Camera {
     z_near: f32,
     z_far: f32,
 
+    // Lens properties
+    aperture: f32,
+    focus_distance: f32,
+    blade_count: u32,
+    anamorphic_ratio: f32,
+    autofocus: bool,
+
+    // Projection properties
+    projection: Projection,
+    ortho_height: f32,
+
+    // Stereo properties
+    stereo_mode: StereoMode,
+    ipd: f32,
+    convergence: f32,
+
+    // Shutter properties
+    shutter_open: f32,
+    shutter_close: f32,
+
     // Calculated properties
     rts: glm::Mat4,
     left: glm::Vec3,
@@ -33,6 +168,18 @@ impl Camera {
             fov: 90.0,
             z_near: 1.0,
             z_far: 1000.0,
+            aperture: 0.0,
+            focus_distance: 1.0,
+            blade_count: 0,
+            anamorphic_ratio: 1.0,
+            autofocus: true,
+            projection: Projection::Perspective,
+            ortho_height: 10.0,
+            stereo_mode: StereoMode::Off,
+            ipd: 0.065,
+            convergence: 3.0,
+            shutter_open: 0.0,
+            shutter_close: 0.0,
             rts: glm::Mat4::identity(),
             left: glm::zero(),
             up: glm::zero(),
@@ -111,12 +258,157 @@ impl Camera {
         self.calculate_rts()
     }
 
+    /**
+     * Sets the lens parameters used for depth of field.
+     * Parameters can be left as "None", in which case they aren't updated.
+     *
+     * @param aperture The radius of the lens. 0 gives a pinhole camera, where everything is in focus.
+     * @param focus_distance The distance to the plane which is in focus.
+     * @param blade_count The amount of aperture blades, shaping the bokeh as a polygon. Less than 3 gives a round aperture.
+     * @param anamorphic_ratio The horizontal stretch of the aperture.
+     */
+    pub fn set_lens(
+        &mut self,
+        aperture: Option<f32>,
+        focus_distance: Option<f32>,
+        blade_count: Option<u32>,
+        anamorphic_ratio: Option<f32>
+    ) -> &Camera {
+        if let Some(aperture_defined) = aperture { self.aperture = aperture_defined.max(0.0); }
+        if let Some(focus_distance_defined) = focus_distance { self.focus_distance = focus_distance_defined; }
+        if let Some(blade_count_defined) = blade_count { self.blade_count = blade_count_defined; }
+        if let Some(anamorphic_ratio_defined) = anamorphic_ratio { self.anamorphic_ratio = anamorphic_ratio_defined; }
+        self
+    }
+
+    /**
+     * Enables or disables autofocus.
+     * While enabled, the focus distance should be updated every frame from a ray cast through the center of the screen.
+     */
+    pub fn set_autofocus( &mut self, autofocus: bool ) -> &Camera {
+        self.autofocus = autofocus;
+        self
+    }
+
+    /**
+     * Sets the projection.
+     * Parameters can be left as "None", in which case they aren't updated.
+     *
+     * @param projection The projection.
+     * @param ortho_height The height of the view in world units, used by the orthographic projection.
+     */
+    pub fn set_projection( &mut self, projection: Option<Projection>, ortho_height: Option<f32> ) -> &Camera {
+        if let Some(projection_defined) = projection { self.projection = projection_defined; }
+        if let Some(ortho_height_defined) = ortho_height { self.ortho_height = ortho_height_defined.max(0.0); }
+        self
+    }
+
+    /**
+     * Sets the stereo parameters.
+     * Parameters can be left as "None", in which case they aren't updated.
+     * The convergence is kept at least MIN_CONVERGENCE, as the eyes would aim their rays at themselves at zero distance.
+     *
+     * @param stereo_mode How the eyes are laid out in the image, or Off for a mono image.
+     * @param ipd The interpupillary distance, the distance between the eyes.
+     * @param convergence The distance at which the eyes converge. Objects at this distance appear at the screen's depth.
+     */
+    pub fn set_stereo( &mut self, stereo_mode: Option<StereoMode>, ipd: Option<f32>, convergence: Option<f32> ) -> &Camera {
+        if let Some(stereo_mode_defined) = stereo_mode { self.stereo_mode = stereo_mode_defined; }
+        if let Some(ipd_defined) = ipd { self.ipd = ipd_defined.max(0.0); }
+        if let Some(convergence_defined) = convergence { self.convergence = convergence_defined.max(MIN_CONVERGENCE); }
+        self
+    }
+
+    /**
+     * Sets the shutter interval used for motion blur, relative to the time of the frame.
+     * Parameters can be left as "None", in which case they aren't updated.
+     * Motion blur is disabled if the shutter closes as soon as it opens.
+     *
+     * @param open The time the shutter opens, in seconds.
+     * @param close The time the shutter closes, in seconds. Clamped to not be before it opens.
+     */
+    pub fn set_shutter( &mut self, open: Option<f32>, close: Option<f32> ) -> &Camera {
+        if let Some(open_defined) = open { self.shutter_open = open_defined; }
+        if let Some(close_defined) = close { self.shutter_close = close_defined; }
+        self.shutter_close = self.shutter_close.max(self.shutter_open);
+        self
+    }
+
+    /**
+     * Calculates the ray through a point on the screen, like GenerateCameraRay() in the raytracing shader,
+     * but without anti-aliasing jitter or depth of field.
+     * In stereo, the point is mapped to its eye's half of the screen, but the ray starts between the eyes.
+     *
+     * @param point The point, in pixels from the top left corner of the screen.
+     * @param screen_size The size of the screen, in pixels.
+     *
+     * @return The origin and (normalized) direction of the ray, or None if the point is outside of the image.
+     */
+    pub fn screen_ray( &self, point: glm::Vec2, screen_size: glm::Vec2 ) -> Option<( glm::Vec3, glm::Vec3 )> {
+        // (The shader's uv starts at the bottom left)
+        let mut uv = glm::vec2( point.x / screen_size.x, 1.0 - point.y / screen_size.y );
+        let mut image_size = screen_size;
+        match self.stereo_mode {
+            StereoMode::SideBySide => {
+                uv.x = uv.x * 2.0 - if uv.x >= 0.5 { 1.0 } else { 0.0 };
+                image_size.x *= 0.5;
+            },
+            StereoMode::TopBottom => {
+                uv.y = uv.y * 2.0 - if uv.y < 0.5 { 0.0 } else { 1.0 };
+                image_size.y *= 0.5;
+            },
+            StereoMode::Off => {},
+        }
+        let aspect = image_size.x / image_size.y;
+        let to_world = |local: glm::Vec3, w: f32| ( self.rts * glm::vec4( local.x, local.y, local.z, w ) ).xyz();
+
+        match self.projection {
+            Projection::Orthographic => {
+                let plane_size = glm::vec2( self.ortho_height * aspect, self.ortho_height );
+                let origin_local = ( uv - glm::vec2( 0.5, 0.5 ) ).component_mul( &plane_size );
+                Some( ( to_world( glm::vec3( origin_local.x, origin_local.y, 0.0 ), 1.0 ), self.front ) )
+            },
+            Projection::Fisheye => {
+                let p = ( uv - glm::vec2( 0.5, 0.5 ) ).component_mul( &glm::vec2( 2.0 * aspect, 2.0 ) );
+                let r = p.norm();
+                if r > 1.0 {
+                    return None;
+                }
+                let ( theta, phi ) = ( r * ( self.fov * 0.5 ).to_radians(), p.y.atan2( p.x ) );
+                let dir_local = glm::vec3( theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos() );
+                Some( ( self.pos, to_world( dir_local, 0.0 ).normalize() ) )
+            },
+            Projection::Equirectangular => {
+                let ( longitude, latitude ) = ( ( uv.x - 0.5 ) * 2.0 * PI, ( uv.y - 0.5 ) * PI );
+                let dir_local = glm::vec3( latitude.cos() * longitude.sin(), latitude.sin(), latitude.cos() * longitude.cos() );
+                Some( ( self.pos, to_world( dir_local, 0.0 ).normalize() ) )
+            },
+            Projection::Perspective => {
+                let plane_height = self.focus_distance * ( self.fov * 0.5 ).to_radians().tan() * 2.0;
+                let focus_point_local = glm::vec3( ( uv.x - 0.5 ) * plane_height * aspect, ( uv.y - 0.5 ) * plane_height, self.focus_distance );
+                Some( ( self.pos, ( to_world( focus_point_local, 1.0 ) - self.pos ).normalize() ) )
+            },
+        }
+    }
+
     // --- Getters
     pub fn pos( &self )     -> glm::Vec3 { self.pos }
     pub fn ang( &self )     -> glm::Vec3 { self.ang }
     pub fn fov( &self )     -> f32 { self.fov }
     pub fn z_near( &self )  -> f32 { self.z_near }
     pub fn z_far( &self )   -> f32 { self.z_far }
+    pub fn aperture( &self )            -> f32 { self.aperture }
+    pub fn focus_distance( &self )      -> f32 { self.focus_distance }
+    pub fn blade_count( &self )         -> u32 { self.blade_count }
+    pub fn anamorphic_ratio( &self )    -> f32 { self.anamorphic_ratio }
+    pub fn autofocus( &self )           -> bool { self.autofocus }
+    pub fn projection( &self )          -> Projection { self.projection }
+    pub fn ortho_height( &self )        -> f32 { self.ortho_height }
+    pub fn stereo_mode( &self )         -> StereoMode { self.stereo_mode }
+    pub fn ipd( &self )                 -> f32 { self.ipd }
+    pub fn convergence( &self )         -> f32 { self.convergence }
+    pub fn shutter_open( &self )        -> f32 { self.shutter_open }
+    pub fn shutter_close( &self )       -> f32 { self.shutter_close }
     pub fn rts( &self )     -> glm::Mat4 { self.rts }
     pub fn left( &self )    -> glm::Vec3 { self.left }
     pub fn front( &self )   -> glm::Vec3 { self.front }
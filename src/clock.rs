@@ -0,0 +1,66 @@
+use std::time::Instant;
+
+/**
+ * Enum for the clock driving the gameloop.
+ * The realtime clock follows the wall clock, while the fixed clock only advances when told to, one frame at a time.
+ * The fixed clock is used for offline rendering, where every frame must be deterministic.
+ */
+pub enum Clock {
+    Realtime { start: Instant, prev: Instant },
+    Fixed { frame: u32, fps: f32 },
+}
+
+/**
+ * Clock functions.
+ */
+impl Clock {
+    /**
+     * Creates a clock which follows the wall clock, starting now.
+     */
+    pub fn realtime() -> Clock {
+        let now = Instant::now();
+        Clock::Realtime { start: now, prev: now }
+    }
+
+    /**
+     * Creates a clock which advances at a fixed timestep.
+     *
+     * @param fps The amount of frames per second.
+     */
+    pub fn fixed( fps: f32 ) -> Clock {
+        Clock::Fixed { frame: 0, fps }
+    }
+
+    /**
+     * Gets the elapsed and delta time.
+     * For the realtime clock, the delta time is the time since the last tick.
+     * For the fixed clock, the time stays the same until advance_frame() is called.
+     *
+     * @return The elapsed time and delta time, in seconds.
+     */
+    pub fn tick( &mut self ) -> ( f32, f32 ) {
+        match self {
+            Clock::Realtime { start, prev } => {
+                let time = Instant::now();
+                let ( time_elapsed, dt ) = (
+                    time.duration_since( *start ).as_secs_f32(),
+                    time.duration_since( *prev ).as_secs_f32(),
+                );
+                *prev = time;
+                ( time_elapsed, dt )
+            },
+            Clock::Fixed { frame, fps } => {
+                ( *frame as f32 / *fps, 1.0 / *fps )
+            },
+        }
+    }
+
+    /**
+     * Advances the fixed clock by one frame. Does nothing for the realtime clock.
+     */
+    pub fn advance_frame( &mut self ) {
+        if let Clock::Fixed { frame, .. } = self {
+            *frame += 1;
+        }
+    }
+}
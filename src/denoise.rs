@@ -0,0 +1,284 @@
+use std::ptr;
+
+use crate::framebuffer::{RenderTarget, RenderTargetBuilder};
+use crate::aov::Aov;
+use crate::shader::{Shader, ShaderBuilder};
+
+// B3 spline kernel, applied separably in x and y (same as the denoise shader)
+const KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+
+/**
+ * Struct for the settings of the edge-avoiding à-trous denoiser.
+ * Each phi controls how much a neighbour may differ from the filtered pixel before it is ignored,
+ * so lower values preserve more edges but remove less noise.
+ */
+#[derive(Clone, Copy)]
+pub struct DenoiseSettings {
+    // Amount of filter iterations, the kernel's footprint doubles with each one
+    pub iterations: u32,
+    // Halved every iteration, as the image gets smoother
+    pub color_phi: f32,
+    pub normal_phi: f32,
+    // Relative to the depth of the filtered pixel
+    pub depth_phi: f32,
+    pub albedo_phi: f32,
+}
+
+/**
+ * DenoiseSettings functions.
+ */
+impl DenoiseSettings {
+    /**
+     * Creates the default denoise settings.
+     */
+    pub fn new() -> DenoiseSettings {
+        DenoiseSettings { iterations: 5, color_phi: 0.5, normal_phi: 0.1, depth_phi: 0.01, albedo_phi: 0.05 }
+    }
+
+    /**
+     * Gets the color phi of an iteration.
+     *
+     * @param iteration The iteration, starting at 0.
+     */
+    fn color_phi_at( &self, iteration: u32 ) -> f32 {
+        self.color_phi * 0.5f32.powi( iteration as i32 )
+    }
+}
+
+/**
+ * Struct for the GPU denoiser, which filters the raytracer's output using its auxiliary buffers.
+ * The iterations ping-pong between two render targets.
+ */
+pub struct Denoiser {
+    shader: Shader,
+    targets: [RenderTarget; 2],
+}
+
+/**
+ * Denoiser functions.
+ */
+impl Denoiser {
+    /**
+     * Creates the denoiser's shader and render targets.
+     *
+     * @param width The width of the image, in pixels.
+     * @param height The height of the image, in pixels.
+     */
+    pub unsafe fn new( width: u32, height: u32 ) -> Denoiser {
+        Denoiser {
+            shader: ShaderBuilder::new()
+                .attach_shader( "shaders/raytracing.vert" )
+                .attach_shader( "shaders/denoise.frag" )
+                .link(),
+            targets: [
+                RenderTargetBuilder::new( width, height ).add_color_attachment( gl::RGBA32F ).link(),
+                RenderTargetBuilder::new( width, height ).add_color_attachment( gl::RGBA32F ).link(),
+            ],
+        }
+    }
+
+    /**
+     * Denoises the raytracer's output.
+     *
+     * @param input The render target holding the raytracer's color and auxiliary buffers.
+     * @param settings The denoise settings. At least one iteration is run.
+     * @param vao The vertex array of the screen quad.
+     * @param index_count The amount of indices in the screen quad.
+     *
+     * @return The render target holding the denoised image, in attachment 0.
+     */
+    pub unsafe fn run( &self, input: &RenderTarget, settings: &DenoiseSettings, vao: u32, index_count: i32 ) -> &RenderTarget {
+        // Blending would mix the result with the previous contents of the target
+        gl::Disable( gl::BLEND );
+        self.shader.activate();
+        gl::Uniform1f( self.shader.get_uniform_location( "denoise.normalPhi" ), settings.normal_phi );
+        gl::Uniform1f( self.shader.get_uniform_location( "denoise.depthPhi" ), settings.depth_phi );
+        gl::Uniform1f( self.shader.get_uniform_location( "denoise.albedoPhi" ), settings.albedo_phi );
+        for ( unit, ( name, attachment ) ) in [( "albedoTexture", Aov::Albedo ), ( "normalTexture", Aov::Normal ), ( "depthTexture", Aov::Depth )].into_iter().enumerate() {
+            input.bind_texture( attachment.attachment(), unit as u32 + 1 );
+            gl::Uniform1i( self.shader.get_uniform_location( name ), unit as i32 + 1 );
+        }
+        gl::Uniform1i( self.shader.get_uniform_location( "colorTexture" ), 0 );
+
+        let iterations = settings.iterations.max( 1 );
+        for iteration in 0..iterations {
+            // Read the previous iteration's result, or the raytracer's output for the first one
+            let target = &self.targets[iteration as usize % 2];
+            if iteration == 0 {
+                input.bind_texture( Aov::Color.attachment(), 0 );
+            } else {
+                self.targets[( iteration as usize + 1 ) % 2].bind_texture( 0, 0 );
+            }
+
+            target.bind();
+            gl::Uniform1f( self.shader.get_uniform_location( "denoise.colorPhi" ), settings.color_phi_at( iteration ) );
+            gl::Uniform1i( self.shader.get_uniform_location( "stepSize" ), 1 << iteration );
+            gl::BindVertexArray( vao );
+            gl::DrawElements( gl::TRIANGLES, index_count, gl::UNSIGNED_INT, ptr::null() );
+        }
+
+        gl::Enable( gl::BLEND );
+        &self.targets[( iterations as usize - 1 ) % 2]
+    }
+
+    /**
+     * Uploads an image which was denoised on the CPU, so it can be drawn like the result of run().
+     *
+     * @param pixels The denoised pixels as RGBA floats, starting at the top left corner.
+     * @return The render target holding the image, in attachment 0.
+     */
+    pub unsafe fn upload( &self, pixels: &[f32] ) -> &RenderTarget {
+        self.targets[0].write_pixels( 0, pixels );
+        &self.targets[0]
+    }
+}
+
+/**
+ * Denoises an image on the CPU, using the same filter as the GPU denoiser.
+ * Meant for headless rendering, where the result is written to disk rather than shown every frame.
+ * All buffers are RGBA floats (4 per pixel), in the same row order.
+ *
+ * @param color The noisy image.
+ * @param albedo The albedo buffer.
+ * @param normal The normal buffer.
+ * @param depth The depth buffer (only the red channel is used).
+ * @param width The width of the image, in pixels.
+ * @param height The height of the image, in pixels.
+ * @param settings The denoise settings. At least one iteration is run.
+ *
+ * @return The denoised image.
+ */
+pub fn denoise_cpu( color: &[f32], albedo: &[f32], normal: &[f32], depth: &[f32], width: usize, height: usize, settings: &DenoiseSettings ) -> Vec<f32> {
+    let rgb = |buffer: &[f32], index: usize| glm::vec3( buffer[index * 4], buffer[index * 4 + 1], buffer[index * 4 + 2] );
+    let edge_weight = |difference: f32, phi: f32| ( -difference / phi ).exp().min( 1.0 );
+
+    let mut current = color.to_vec();
+    let mut next = vec![0.0f32; current.len()];
+    for iteration in 0..settings.iterations.max( 1 ) {
+        let step_size = 1i64 << iteration;
+        let color_phi = settings.color_phi_at( iteration );
+
+        for y in 0..height {
+            for x in 0..width {
+                let center = y * width + x;
+                let ( center_color, center_albedo, center_normal, center_depth ) = (
+                    rgb( &current, center ), rgb( albedo, center ), rgb( normal, center ), depth[center * 4],
+                );
+
+                let mut sum = glm::Vec3::zeros();
+                let mut weight_sum = 0.0;
+                for ( ky, kernel_y ) in KERNEL.iter().enumerate() {
+                    for ( kx, kernel_x ) in KERNEL.iter().enumerate() {
+                        let tap_x = ( x as i64 + ( kx as i64 - 2 ) * step_size ).clamp( 0, width as i64 - 1 ) as usize;
+                        let tap_y = ( y as i64 + ( ky as i64 - 2 ) * step_size ).clamp( 0, height as i64 - 1 ) as usize;
+                        let tap = tap_y * width + tap_x;
+
+                        // Edge-stopping functions, ignoring neighbours which are likely on another surface
+                        let tap_color = rgb( &current, tap );
+                        let depth_difference = ( center_depth - depth[tap * 4] ) / center_depth.max( 1e-3 );
+                        let weight = kernel_x * kernel_y
+                            * edge_weight( ( center_color - tap_color ).norm_squared(), color_phi )
+                            * edge_weight( ( center_albedo - rgb( albedo, tap ) ).norm_squared(), settings.albedo_phi )
+                            * edge_weight( ( center_normal - rgb( normal, tap ) ).norm_squared(), settings.normal_phi )
+                            * edge_weight( depth_difference * depth_difference, settings.depth_phi );
+
+                        sum += tap_color * weight;
+                        weight_sum += weight;
+                    }
+                }
+
+                let filtered = sum / weight_sum;
+                next[center * 4..center * 4 + 4].copy_from_slice( &[filtered.x, filtered.y, filtered.z, 1.0] );
+            }
+        }
+        std::mem::swap( &mut current, &mut next );
+    }
+
+    current
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = 16;
+
+    /**
+     * Creates a SIZE x SIZE RGBA image, with the color of each pixel given by its coordinates.
+     */
+    fn image( color: impl Fn( usize, usize ) -> glm::Vec3 ) -> Vec<f32> {
+        ( 0..SIZE * SIZE ).flat_map( |index| {
+            let color = color( index % SIZE, index / SIZE );
+            [ color.x, color.y, color.z, 1.0 ]
+        } ).collect()
+    }
+
+    /**
+     * Gets the red channel of a pixel.
+     */
+    fn red( image: &[f32], x: usize, y: usize ) -> f32 {
+        image[( y * SIZE + x ) * 4]
+    }
+
+    /**
+     * Calculates the variance of the red channel of an image.
+     */
+    fn variance( image: &[f32] ) -> f32 {
+        let values: Vec<f32> = image.iter().step_by( 4 ).copied().collect();
+        let mean = values.iter().sum::<f32>() / values.len() as f32;
+        values.iter().map( |value| ( value - mean ) * ( value - mean ) ).sum::<f32>() / values.len() as f32
+    }
+
+    /**
+     * Denoises a SIZE x SIZE image with the default settings.
+     */
+    fn denoise( color: &[f32], albedo: &[f32], normal: &[f32] ) -> Vec<f32> {
+        let depth = image( |_, _| glm::vec3( 5.0, 0.0, 0.0 ) );
+        denoise_cpu( color, albedo, normal, &depth, SIZE, SIZE, &DenoiseSettings::new() )
+    }
+
+    #[test]
+    fn constant_images_are_unchanged() {
+        let color = image( |_, _| glm::vec3( 0.3, 0.5, 0.7 ) );
+        let ( albedo, normal ) = ( image( |_, _| glm::vec3( 0.8, 0.8, 0.8 ) ), image( |_, _| glm::vec3( 0.0, 1.0, 0.0 ) ) );
+        let denoised = denoise( &color, &albedo, &normal );
+        assert!( denoised.iter().zip( &color ).all( |( a, b )| ( a - b ).abs() < 1e-5 ) );
+    }
+
+    #[test]
+    fn noise_is_smoothed_in_flat_regions() {
+        // Deterministic noise around 0.5, varying from pixel to pixel
+        let color = image( |x, y| glm::vec3( 0.5 + 0.2 * ( ( x * 7 + y * 13 ) % 5 ) as f32 / 4.0 - 0.1, 0.5, 0.5 ) );
+        let ( albedo, normal ) = ( image( |_, _| glm::vec3( 0.8, 0.8, 0.8 ) ), image( |_, _| glm::vec3( 0.0, 1.0, 0.0 ) ) );
+        let denoised = denoise( &color, &albedo, &normal );
+
+        assert!( variance( &denoised ) < variance( &color ) * 0.1, "variance went from {} to {}", variance( &color ), variance( &denoised ) );
+        let mean = denoised.iter().step_by( 4 ).sum::<f32>() / ( SIZE * SIZE ) as f32;
+        assert!( ( mean - 0.5 ).abs() < 0.02 );
+    }
+
+    #[test]
+    fn albedo_and_normal_edges_are_not_blurred() {
+        // A dark left half and a bright right half, which only the color tells apart at first
+        let color = image( |x, _| if x < SIZE / 2 { glm::vec3( 0.4, 0.4, 0.4 ) } else { glm::vec3( 0.6, 0.6, 0.6 ) } );
+        let split = |left: glm::Vec3, right: glm::Vec3| image( |x, _| if x < SIZE / 2 { left } else { right } );
+        let ( albedo, normal ) = ( image( |_, _| glm::vec3( 0.8, 0.8, 0.8 ) ), image( |_, _| glm::vec3( 0.0, 1.0, 0.0 ) ) );
+        let ( albedo_edge, normal_edge ) = (
+            split( glm::vec3( 1.0, 0.0, 0.0 ), glm::vec3( 0.0, 0.0, 1.0 ) ),
+            split( glm::vec3( 0.0, 1.0, 0.0 ), glm::vec3( 1.0, 0.0, 0.0 ) ),
+        );
+
+        // Without edges in the auxiliary buffers, the pixels next to the edge bleed into each other
+        let blurred = denoise( &color, &albedo, &normal );
+        assert!( red( &blurred, SIZE / 2 - 1, 4 ) > 0.42 );
+        assert!( red( &blurred, SIZE / 2, 4 ) < 0.58 );
+
+        // With them, the halves are kept apart
+        for denoised in [ denoise( &color, &albedo_edge, &normal ), denoise( &color, &albedo, &normal_edge ) ] {
+            for y in 0..SIZE {
+                assert!( ( red( &denoised, SIZE / 2 - 1, y ) - 0.4 ).abs() < 1e-3 );
+                assert!( ( red( &denoised, SIZE / 2, y ) - 0.6 ).abs() < 1e-3 );
+            }
+        }
+    }
+}
@@ -0,0 +1,383 @@
+use crate::aov::Aov;
+use crate::camera::Camera;
+use crate::gui::Gui;
+use crate::material::MaterialLibrary;
+use crate::postprocess::PostSettings;
+use crate::raycast::{RayHit, Primitive};
+use crate::raytracing::{RTSettings, RTMaterial, RTEnvironment, MaterialKind, MaterialFeature, MATERIAL_FEATURES};
+use crate::scene::{Scene, Geometry};
+
+// Tabs of the editor panel, in the order of the panel functions below
+pub const EDITOR_TABS: [&str; 4] = ["Render", "Camera", "Scene", "Environment"];
+
+// Amount of rows used by the inspector panel
+pub const INSPECTOR_HEIGHT: i32 = 12;
+
+/**
+ * Adds the widgets for the raytracing, display and post settings.
+ *
+ * @param gui The GUI, within a panel.
+ * @param settings The raytracing settings.
+ * @param post The post settings.
+ * @param denoise_enabled Whether the displayed image is denoised.
+ * @param displayed_aov The render pass shown on the screen.
+ */
+pub fn render_panel( gui: &mut Gui, settings: &mut RTSettings, post: &mut PostSettings, denoise_enabled: &mut bool, displayed_aov: &mut Aov ) {
+    gui.slider_u32( "Bounces", &mut settings.max_bounces, 1, 16 );
+    gui.slider_u32( "Rays/frag", &mut settings.rays_per_frag, 1, 32 );
+    gui.slider( "Diverge", &mut settings.diverge_strength, 0.0, 1.0 );
+    if gui.choice( "Debug view", settings.debug_mode.name() ) {
+        settings.debug_mode = settings.debug_mode.next();
+    }
+    if gui.choice( "Render pass", displayed_aov.name() ) {
+        *displayed_aov = displayed_aov.next();
+    }
+    gui.checkbox( "Denoise", denoise_enabled );
+
+    gui.heading( "Post" );
+    gui.slider( "Exposure", &mut post.exposure, -10.0, 10.0 );
+    if gui.choice( "Tone mapping", post.tone_mapping.name() ) {
+        post.tone_mapping = post.tone_mapping.next();
+    }
+    gui.slider( "Temperature", &mut post.temperature, -100.0, 100.0 );
+    gui.slider( "Tint", &mut post.tint, -100.0, 100.0 );
+    gui.checkbox( "sRGB", &mut post.srgb );
+}
+
+/**
+ * Adds the widgets for the camera's view, lens, projection and stereo parameters.
+ *
+ * @param gui The GUI, within a panel.
+ * @param camera The camera.
+ */
+pub fn camera_panel( gui: &mut Gui, camera: &mut Camera ) {
+    let mut position = camera.pos();
+    if gui.drag3( "Position", &mut position, 0.05 ) {
+        camera.set_vars( Some( position ), None, None, None, None );
+    }
+    let mut angle = camera.ang().map( f32::to_degrees );
+    if gui.drag3( "Angle", &mut angle, 0.5 ) {
+        camera.set_vars( None, Some( angle.map( f32::to_radians ) ), None, None, None );
+    }
+    let mut fov = camera.fov();
+    if gui.slider( "FOV", &mut fov, 10.0, 170.0 ) {
+        camera.set_vars( None, None, Some( fov ), None, None );
+    }
+
+    gui.heading( "Lens" );
+    let mut aperture = camera.aperture();
+    if gui.slider( "Aperture", &mut aperture, 0.0, 2.0 ) {
+        camera.set_lens( Some( aperture ), None, None, None );
+    }
+    let mut focus_distance = camera.focus_distance();
+    if gui.drag( "Focus", &mut focus_distance, 0.05 ) {
+        camera.set_lens( None, Some( focus_distance.max( 0.01 ) ), None, None );
+    }
+    let mut autofocus = camera.autofocus();
+    if gui.checkbox( "Autofocus", &mut autofocus ) {
+        camera.set_autofocus( autofocus );
+    }
+    let mut blade_count = camera.blade_count();
+    if gui.slider_u32( "Blades", &mut blade_count, 0, 12 ) {
+        camera.set_lens( None, None, Some( blade_count ), None );
+    }
+    let mut anamorphic_ratio = camera.anamorphic_ratio();
+    if gui.slider( "Anamorphic", &mut anamorphic_ratio, 0.25, 4.0 ) {
+        camera.set_lens( None, None, None, Some( anamorphic_ratio ) );
+    }
+
+    gui.heading( "Projection" );
+    if gui.choice( "Projection", camera.projection().name() ) {
+        camera.set_projection( Some( camera.projection().next() ), None );
+    }
+    let mut ortho_height = camera.ortho_height();
+    if gui.drag( "Ortho height", &mut ortho_height, 0.05 ) {
+        camera.set_projection( None, Some( ortho_height.max( 0.01 ) ) );
+    }
+    if gui.choice( "Stereo", camera.stereo_mode().name() ) {
+        camera.set_stereo( Some( camera.stereo_mode().next() ), None, None );
+    }
+    let ( mut ipd, mut convergence ) = ( camera.ipd(), camera.convergence() );
+    if gui.drag( "IPD", &mut ipd, 0.001 ) {
+        camera.set_stereo( None, Some( ipd.max( 0.0 ) ), None );
+    }
+    if gui.drag( "Convergence", &mut convergence, 0.05 ) {
+        camera.set_stereo( None, None, Some( convergence ) );
+    }
+}
+
+/**
+ * Adds a list of the scene's nodes, and widgets for the transform, geometry and material of the selected one.
+ * The scene is only marked as changed when a node was edited, and editing the material only changes the material library.
+ *
+ * @param gui The GUI, within a panel.
+ * @param scene The scene.
+ * @param materials The material library.
+ * @param selected The index of the selected node, changed when another one is clicked.
+ */
+pub fn scene_panel( gui: &mut Gui, scene: &mut Scene, materials: &mut MaterialLibrary, selected: &mut Option<usize> ) {
+    // List the nodes, indented below their parents
+    gui.heading( "Nodes" );
+    for index in 0..scene.nodes().len() {
+        let node = &scene.nodes()[index];
+        let mut depth = 0;
+        let mut parent = node.parent();
+        while let Some( parent_index ) = parent {
+            depth += 1;
+            parent = scene.nodes()[parent_index].parent();
+        }
+        let kind = match node.geometry {
+            Geometry::Empty => "empty",
+            Geometry::Sphere { .. } => "sphere",
+            Geometry::Mesh { .. } => "mesh",
+            Geometry::Light { .. } => "light",
+        };
+        if gui.selectable( &format!( "{}{} ({kind})", "  ".repeat( depth ), node.name ), Some( index ) == *selected ) {
+            *selected = Some( index );
+        }
+    }
+    let Some( node ) = selected.and_then( |index| scene.nodes().get( index ) ) else {
+        return;
+    };
+    let name = node.name.clone();
+
+    // Transform
+    gui.heading( &name );
+    let mut transform = node.transform;
+    let mut rotation = transform.rotation.map( f32::to_degrees );
+    let mut transform_changed = gui.drag3( "Position", &mut transform.translation, 0.05 );
+    transform_changed |= gui.drag3( "Rotation", &mut rotation, 0.5 );
+    transform_changed |= gui.drag3( "Scale", &mut transform.scale, 0.01 );
+    transform.rotation = rotation.map( f32::to_radians );
+
+    // Geometry, and the material it uses
+    // (All triangles of a mesh use the material of the first one)
+    let is_light = matches!( node.geometry, Geometry::Light { .. } );
+    let ( mut radius, mut material_id ) = match &node.geometry {
+        Geometry::Empty => ( None, None ),
+        Geometry::Sphere { radius, material_id } | Geometry::Light { radius, material_id } => ( Some( *radius ), Some( *material_id ) ),
+        Geometry::Mesh { triangles, .. } => ( None, triangles.first().map( |triangle| triangle.material_id ) ),
+    };
+    let mut geometry_changed = radius.as_mut().is_some_and( |radius| gui.drag( "Radius", radius, 0.05 ) );
+    if let Some( id ) = material_id.as_mut() {
+        if gui.choice( "Material", materials.name( *id ).unwrap_or( "?" ) ) && materials.len() > 0 {
+            *id = ( *id + 1 ) % materials.len() as u32;
+            geometry_changed = true;
+        }
+    }
+
+    // Edit the material itself (which is shared by every node using it)
+    if let Some( id ) = material_id {
+        if let Some( mut material ) = materials.get( id ).copied() {
+            let material_changed = if is_light { light_widgets( gui, &mut material ) } else { material_widgets( gui, &mut material ) };
+            if material_changed {
+                if let Some( library_material ) = materials.get_mut( id ) {
+                    *library_material = material;
+                }
+            }
+        }
+    }
+
+    // Write the changes back
+    if !transform_changed && !geometry_changed {
+        return;
+    }
+    if let Some( node ) = scene.node_mut( &name ) {
+        node.transform = transform;
+        match &mut node.geometry {
+            Geometry::Sphere { radius: node_radius, material_id: node_material_id } | Geometry::Light { radius: node_radius, material_id: node_material_id } => {
+                *node_radius = radius.unwrap_or( *node_radius ).max( 0.0 );
+                *node_material_id = material_id.unwrap_or( *node_material_id );
+            },
+            Geometry::Mesh { triangles, .. } => {
+                if let Some( material_id ) = material_id {
+                    triangles.iter_mut().for_each( |triangle| triangle.material_id = material_id );
+                }
+            },
+            Geometry::Empty => {},
+        }
+    }
+}
+
+/**
+ * Adds the widgets for the material of a light: the color and strength of its emission.
+ *
+ * @param gui The GUI, within a panel.
+ * @param material The material.
+ *
+ * @return Whether the material changed.
+ */
+fn light_widgets( gui: &mut Gui, material: &mut RTMaterial ) -> bool {
+    let ( mut color, mut strength ) = ( material.emission_color.xyz(), material.emission_color.w );
+    let changed = gui.color( "Color", &mut color ) | gui.drag( "Strength", &mut strength, 0.05 );
+    if changed {
+        material.color = glm::vec4( color.x, color.y, color.z, 1.0 );
+        material.emission_color = glm::vec4( color.x, color.y, color.z, strength.max( 0.0 ) );
+    }
+    changed
+}
+
+/**
+ * Adds the widgets for a material.
+ *
+ * @param gui The GUI, within a panel.
+ * @param material The material.
+ *
+ * @return Whether the material changed.
+ */
+fn material_widgets( gui: &mut Gui, material: &mut RTMaterial ) -> bool {
+    let mut changed = false;
+
+    if gui.choice( "Model", material.kind.name() ) {
+        material.kind = material.kind.next();
+        changed = true;
+    }
+
+    let mut color = material.color.xyz();
+    if gui.color( "Color", &mut color ) {
+        material.color = glm::vec4( color.x, color.y, color.z, material.color.w );
+        changed = true;
+    }
+
+    // The alpha of the emission color is its strength, and the alpha of the specular color is the chance of a specular bounce
+    let ( mut emission, mut strength ) = ( material.emission_color.xyz(), material.emission_color.w );
+    if gui.color( "Emission", &mut emission ) | gui.drag( "Strength", &mut strength, 0.05 ) {
+        material.emission_color = glm::vec4( emission.x, emission.y, emission.z, strength.max( 0.0 ) );
+        changed = true;
+    }
+    match material.kind {
+        MaterialKind::Legacy => {
+            let ( mut specular, mut specular_chance ) = ( material.specular_color.xyz(), material.specular_color.w );
+            if gui.color( "Specular", &mut specular ) | gui.slider( "Specular %", &mut specular_chance, 0.0, 1.0 ) {
+                material.specular_color = glm::vec4( specular.x, specular.y, specular.z, specular_chance );
+                changed = true;
+            }
+            changed |= gui.slider( "Smoothness", &mut material.smoothness, 0.0, 1.0 );
+        },
+        MaterialKind::Pbr => {
+            changed |= gui.slider( "Roughness", &mut material.roughness, 0.0, 1.0 );
+            changed |= gui.slider( "Metallic", &mut material.metallic, 0.0, 1.0 );
+            for feature in MATERIAL_FEATURES {
+                changed |= feature_widgets( gui, material, feature );
+            }
+        },
+    }
+
+    changed
+}
+
+/**
+ * Adds a checkbox for a feature of a PBR material, followed by the widgets for its values if it is enabled.
+ *
+ * @param gui The GUI, within a panel.
+ * @param material The material.
+ * @param feature The feature.
+ *
+ * @return Whether the material changed.
+ */
+fn feature_widgets( gui: &mut Gui, material: &mut RTMaterial, feature: MaterialFeature ) -> bool {
+    let label = match feature {
+        MaterialFeature::Clearcoat => "Clearcoat",
+        MaterialFeature::Sheen => "Sheen",
+        MaterialFeature::ThinFilm => "Thin film",
+        MaterialFeature::Subsurface => "Subsurface",
+    };
+    let mut enabled = material.has_feature( feature );
+    let mut changed = gui.checkbox( label, &mut enabled );
+    material.set_feature( feature, enabled );
+    if !enabled {
+        return changed;
+    }
+
+    match feature {
+        MaterialFeature::Clearcoat => {
+            changed |= gui.slider( " Strength", &mut material.clearcoat, 0.0, 1.0 );
+            changed |= gui.slider( " Roughness", &mut material.clearcoat_roughness, 0.0, 1.0 );
+            changed |= gui.slider( " IOR", &mut material.clearcoat_ior, 1.0, 3.0 );
+        },
+        MaterialFeature::Sheen => {
+            let ( mut sheen, mut strength ) = ( material.sheen_color.xyz(), material.sheen_color.w );
+            if gui.color( " Color", &mut sheen ) | gui.slider( " Strength", &mut strength, 0.0, 1.0 ) {
+                material.sheen_color = glm::vec4( sheen.x, sheen.y, sheen.z, strength );
+                changed = true;
+            }
+        },
+        MaterialFeature::ThinFilm => {
+            changed |= gui.slider( " Thickness", &mut material.thin_film_thickness, 0.0, 1000.0 );
+            changed |= gui.slider( " IOR", &mut material.thin_film_ior, 1.0, 3.0 );
+        },
+        MaterialFeature::Subsurface => {
+            // The alpha of the subsurface color is the mean free path
+            let ( mut subsurface, mut mean_free_path ) = ( material.subsurface_color.xyz(), material.subsurface_color.w );
+            if gui.color( " Color", &mut subsurface ) | gui.drag( " Free path", &mut mean_free_path, 0.005 ) {
+                material.subsurface_color = glm::vec4( subsurface.x, subsurface.y, subsurface.z, mean_free_path.max( 0.001 ) );
+                changed = true;
+            }
+        },
+    }
+    changed
+}
+
+/**
+ * Adds the widgets for the environment.
+ *
+ * @param gui The GUI, within a panel.
+ * @param environment The environment.
+ */
+pub fn environment_panel( gui: &mut Gui, environment: &mut RTEnvironment ) {
+    if gui.button( "Reset" ) {
+        *environment = RTEnvironment { enabled: environment.enabled, ..RTEnvironment::new() };
+    }
+    gui.checkbox( "Enabled", &mut environment.enabled );
+    gui.color( "Horizon", &mut environment.sky_color_horizon );
+    gui.color( "Zenith", &mut environment.sky_color_zenith );
+    gui.color( "Ground", &mut environment.ground_color );
+
+    gui.heading( "Sun" );
+    gui.slider( "Elevation", &mut environment.sun_elevation, -90.0, 90.0 );
+    gui.slider( "Azimuth", &mut environment.sun_azimuth, -180.0, 180.0 );
+    gui.slider( "Focus", &mut environment.sun_focus, 1.0, 1000.0 );
+    gui.slider( "Intensity", &mut environment.sun_intensity, 0.0, 50.0 );
+}
+
+/**
+ * Adds the information about a picked point: the node, the primitive and the material which were hit.
+ *
+ * @param gui The GUI, within a panel of INSPECTOR_HEIGHT rows (including its title).
+ * @param hit The hit of the ray cast through the picked point.
+ * @param scene The scene the ray was cast against.
+ * @param materials The material library.
+ */
+pub fn inspector_panel( gui: &mut Gui, hit: &RayHit, scene: &Scene, materials: &MaterialLibrary ) {
+    let format_vec3 = |v: glm::Vec3| format!( "({:.2}, {:.2}, {:.2})", v.x, v.y, v.z );
+
+    let name = ( hit.object_id as usize ).checked_sub( 1 ).and_then( |index| scene.nodes().get( index ) ).map_or( "?", |node| node.name.as_str() );
+    gui.label( &format!( "Node: {name}" ) );
+    gui.label( &match hit.primitive {
+        Primitive::Sphere { index } => format!( "Hit: sphere {index}" ),
+        Primitive::Triangle { mesh, index } => format!( "Hit: triangle {index} of mesh {mesh}" ),
+    } );
+    gui.label( &format!( "Distance: {:.3}", hit.dist ) );
+    gui.label( &format!( "Position: {}", format_vec3( hit.pos ) ) );
+    gui.label( &format!( "Normal: {}", format_vec3( hit.normal ) ) );
+
+    let Some( material ) = materials.get( hit.material_id ) else {
+        gui.label( &format!( "Material: {} (missing)", hit.material_id ) );
+        return;
+    };
+    gui.heading( &format!( "Material: {}", materials.name( hit.material_id ).unwrap_or( "?" ) ) );
+    gui.label( &format!( "Model: {}", material.kind.name() ) );
+    gui.label( &format!( "Color: {}", format_vec3( material.color.xyz() ) ) );
+    gui.label( &format!( "Emission: {} x {:.2}", format_vec3( material.emission_color.xyz() ), material.emission_color.w ) );
+    match material.kind {
+        MaterialKind::Legacy => {
+            gui.label( &format!( "Specular: {}, {:.0}%", format_vec3( material.specular_color.xyz() ), material.specular_color.w * 100.0 ) );
+            gui.label( &format!( "Smoothness: {:.2}", material.smoothness ) );
+        },
+        MaterialKind::Pbr => {
+            gui.label( &format!( "Roughness: {:.2}, metallic: {:.2}", material.roughness, material.metallic ) );
+            let features: Vec<&str> = MATERIAL_FEATURES.iter().filter( |&&feature| material.has_feature( feature ) ).map( |feature| feature.name() ).collect();
+            gui.label( &format!( "Features: {}", if features.is_empty() { "none".to_string() } else { features.join( ", " ) } ) );
+        },
+    }
+}
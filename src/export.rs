@@ -0,0 +1,385 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+
+use crate::camera::{Projection, StereoMode};
+use crate::postprocess::{PostSettings, ToneMapping};
+
+/**
+ * Enum for the image formats frames can be exported as.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    // 8-bit, tone mapped and encoded by the post pass
+    Png,
+    // 32-bit float, linear (before the post pass)
+    Exr,
+}
+
+/**
+ * Struct for the settings of an offline render (image sequence export).
+ */
+pub struct ExportSettings {
+    pub out_dir: PathBuf,
+    // If None, the length of the animation is used
+    pub frames: Option<u32>,
+    pub fps: f32,
+    pub samples: u32,
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub ffmpeg: bool,
+    // Camera bookmark to render from
+    pub bookmark: Option<usize>,
+    // Camera projection to render with
+    pub projection: Option<Projection>,
+    // Stereo layout, interpupillary distance and convergence distance to render with
+    pub stereo_mode: Option<StereoMode>,
+    pub ipd: Option<f32>,
+    pub convergence: Option<f32>,
+    // Shutter angle in degrees, the part of each frame's interval that is motion blurred
+    pub shutter_angle: f32,
+    // Post settings used for png frames and ffmpeg
+    pub post: PostSettings,
+    // Denoise the frames (on the CPU) before writing them
+    pub denoise: bool,
+    // Also write the other AOVs (albedo, normal, depth, ...) as separate exr files
+    pub passes: bool,
+}
+
+/**
+ * ExportSettings functions.
+ */
+impl ExportSettings {
+    /**
+     * Parses export settings from the command line arguments.
+     * Exporting is enabled with `--export <dir>`, the other arguments are optional:
+     *  --frames <n>        Amount of frames to render (default: length of the animation)
+     *  --fps <n>           Frame rate of the animation clock (default: 24)
+     *  --samples <n>       Samples accumulated per frame (default: 64)
+     *  --format <png/exr>  Image format (default: png)
+     *  --size <w>x<h>      Resolution (default: the initial window size)
+     *  --ffmpeg            Also pipe the frames to ffmpeg, creating <dir>/animation.mp4
+     *  --bookmark <n>      Render from a saved camera bookmark
+     *  --projection <name> Camera projection: perspective, orthographic, fisheye or equirectangular
+     *  --stereo <mode>     Stereo layout: off, side-by-side or top-bottom. Each eye is also written to its own file
+     *  --ipd <d>           Interpupillary distance used in stereo
+     *  --convergence <d>   Distance at which the eyes converge in stereo
+     *  --shutter <deg>     Shutter angle for motion blur, 360 blurs across the whole frame (default: 0, no blur)
+     *  --exposure <ev>     Exposure adjustment in stops (default: 0)
+     *  --tonemap <name>    Tone mapping operator: none, reinhard, aces or agx (default: aces)
+     *  --temperature <t>   White balance temperature within [-100, 100] (default: 0)
+     *  --tint <t>          White balance tint within [-100, 100] (default: 0)
+     *  --denoise           Denoise the frames before writing them
+     *  --passes            Also write the other AOVs of each frame, as <frame>_<aov>.exr
+     *
+     * @param args The command line arguments, excluding the program name.
+     * @param default_size The resolution used if none is given.
+     *
+     * @return The export settings, None if exporting was not requested, or an error message if the arguments are invalid.
+     */
+    pub fn from_args( args: &[String], default_size: ( u32, u32 ) ) -> Result<Option<ExportSettings>, String> {
+        let mut settings = ExportSettings {
+            out_dir: PathBuf::new(),
+            frames: None,
+            fps: 24.0,
+            samples: 64,
+            format: ImageFormat::Png,
+            width: default_size.0,
+            height: default_size.1,
+            ffmpeg: false,
+            bookmark: None,
+            projection: None,
+            stereo_mode: None,
+            ipd: None,
+            convergence: None,
+            shutter_angle: 0.0,
+            post: PostSettings::new(),
+            denoise: false,
+            passes: false,
+        };
+        let mut export = false;
+
+        let mut args = args.iter();
+        while let Some( arg ) = args.next() {
+            let mut value = || args.next().ok_or( format!( "Missing value for '{arg}'" ) );
+            let invalid = |value: &str| format!( "Invalid value '{value}' for '{arg}'" );
+            match arg.as_str() {
+                "--export" => {
+                    export = true;
+                    settings.out_dir = PathBuf::from( value()? );
+                },
+                "--frames" => {
+                    let value = value()?;
+                    settings.frames = Some( value.parse().map_err( |_| invalid( value ) )? );
+                },
+                "--fps" => {
+                    let value = value()?;
+                    settings.fps = value.parse().map_err( |_| invalid( value ) )?;
+                },
+                "--samples" => {
+                    let value = value()?;
+                    settings.samples = value.parse().map_err( |_| invalid( value ) )?;
+                },
+                "--format" => {
+                    let value = value()?;
+                    settings.format = match value.as_str() {
+                        "png" => { ImageFormat::Png },
+                        "exr" => { ImageFormat::Exr },
+                        _ => { return Err( invalid( value ) ) },
+                    };
+                },
+                "--size" => {
+                    let value = value()?;
+                    let ( width, height ) = value.split_once( 'x' ).ok_or( invalid( value ) )?;
+                    settings.width = width.parse().map_err( |_| invalid( value ) )?;
+                    settings.height = height.parse().map_err( |_| invalid( value ) )?;
+                },
+                "--ffmpeg" => {
+                    settings.ffmpeg = true;
+                },
+                "--denoise" => {
+                    settings.denoise = true;
+                },
+                "--passes" => {
+                    settings.passes = true;
+                },
+                "--bookmark" => {
+                    let value = value()?;
+                    settings.bookmark = Some( value.parse().map_err( |_| invalid( value ) )? );
+                },
+                "--projection" => {
+                    let value = value()?;
+                    settings.projection = Some( Projection::from_name( value ).ok_or( invalid( value ) )? );
+                },
+                "--stereo" => {
+                    let value = value()?;
+                    settings.stereo_mode = Some( StereoMode::from_name( value ).ok_or( invalid( value ) )? );
+                },
+                "--ipd" => {
+                    let value = value()?;
+                    settings.ipd = Some( value.parse().map_err( |_| invalid( value ) )? );
+                },
+                "--convergence" => {
+                    let value = value()?;
+                    settings.convergence = Some( value.parse().map_err( |_| invalid( value ) )? );
+                },
+                "--shutter" => {
+                    let value = value()?;
+                    settings.shutter_angle = value.parse().map_err( |_| invalid( value ) )?;
+                },
+                "--exposure" => {
+                    let value = value()?;
+                    settings.post.exposure = value.parse().map_err( |_| invalid( value ) )?;
+                },
+                "--tonemap" => {
+                    let value = value()?;
+                    settings.post.tone_mapping = ToneMapping::from_name( value ).ok_or( invalid( value ) )?;
+                },
+                "--temperature" => {
+                    let value = value()?;
+                    settings.post.temperature = value.parse().map_err( |_| invalid( value ) )?;
+                },
+                "--tint" => {
+                    let value = value()?;
+                    settings.post.tint = value.parse().map_err( |_| invalid( value ) )?;
+                },
+                _ => { return Err( format!( "Unknown argument '{arg}'" ) ) },
+            }
+        }
+
+        if settings.fps <= 0.0 || settings.samples == 0 || settings.width == 0 || settings.height == 0 {
+            return Err( "The frame rate, sample count and resolution must be greater than zero".to_string() );
+        }
+        if !( 0.0..=360.0 ).contains( &settings.shutter_angle ) {
+            return Err( "The shutter angle must be within [0, 360] degrees".to_string() );
+        }
+
+        Ok( if export { Some( settings ) } else { None } )
+    }
+}
+
+/**
+ * Struct for writing rendered frames to disk, and optionally to ffmpeg.
+ */
+pub struct Exporter {
+    pub settings: ExportSettings,
+    pub frames: u32,
+    frame: u32,
+    ffmpeg: Option<Child>,
+}
+
+/**
+ * Exporter functions.
+ */
+impl Exporter {
+    /**
+     * Creates the output directory and starts ffmpeg if requested.
+     * If ffmpeg cannot be started, the frames are still written to disk.
+     *
+     * @param settings The export settings.
+     * @param frames The amount of frames to export.
+     */
+    pub fn new( settings: ExportSettings, frames: u32 ) -> Exporter {
+        std::fs::create_dir_all( &settings.out_dir )
+            .unwrap_or_else( |err| panic!( "ERROR::EXPORT::FAILED_TO_CREATE_DIRECTORY\n{}", err ) );
+
+        let ffmpeg = if settings.ffmpeg {
+            Command::new( "ffmpeg" )
+                .args( [
+                    "-y", "-loglevel", "error",
+                    "-f", "rawvideo",
+                    "-pix_fmt", "rgb24",
+                    "-s", &format!( "{}x{}", settings.width, settings.height ),
+                    "-r", &settings.fps.to_string(),
+                    "-i", "-",
+                    "-pix_fmt", "yuv420p",
+                ] )
+                .arg( settings.out_dir.join( "animation.mp4" ) )
+                .stdin( Stdio::piped() )
+                .spawn()
+                .map_err( |err| println!( "Could not start ffmpeg, only writing image files: {err}" ) )
+                .ok()
+        } else {
+            None
+        };
+
+        Exporter { settings, frames, frame: 0, ffmpeg }
+    }
+
+    /**
+     * Checks if all frames have been exported.
+     */
+    pub fn is_done( &self ) -> bool {
+        self.frame >= self.frames
+    }
+
+    /**
+     * Writes a frame to disk as a numbered image file, and to ffmpeg if it is running.
+     * When rendering in stereo, each eye is also written to its own file.
+     *
+     * @param linear The frame's pixels as linear RGBA floats, starting at the top left corner. Used for exr.
+     * @param display The frame's pixels after the post pass, as RGBA floats within [0, 1]. Used for png and ffmpeg.
+     * @param passes Other passes (AOVs) of the frame as named RGBA float buffers, which are written as separate exr files.
+     */
+    pub fn write_frame( &mut self, linear: &[f32], display: &[f32], passes: &[( &str, Vec<f32> )] ) {
+        let ( width, height ) = ( self.settings.width, self.settings.height );
+        let pixels = match self.settings.format {
+            ImageFormat::Png => display,
+            ImageFormat::Exr => linear,
+        };
+        let name = format!( "frame_{:05}", self.frame );
+        self.write_image( &name, pixels, width, height );
+
+        // Split the frame into the eyes
+        match self.settings.stereo_mode {
+            Some( StereoMode::SideBySide ) => {
+                let row_length = width as usize * 4;
+                let left_length = ( width as usize / 2 ) * 4;
+                let ( left, right ): ( Vec<f32>, Vec<f32> ) = (
+                    pixels.chunks_exact( row_length ).flat_map( |row| row[..left_length].to_vec() ).collect(),
+                    pixels.chunks_exact( row_length ).flat_map( |row| row[left_length..left_length * 2].to_vec() ).collect(),
+                );
+                self.write_image( &format!( "{name}_left" ), &left, width / 2, height );
+                self.write_image( &format!( "{name}_right" ), &right, width / 2, height );
+            },
+            Some( StereoMode::TopBottom ) => {
+                let eye_length = ( width * ( height / 2 ) * 4 ) as usize;
+                self.write_image( &format!( "{name}_left" ), &pixels[..eye_length], width, height / 2 );
+                self.write_image( &format!( "{name}_right" ), &pixels[eye_length..eye_length * 2], width, height / 2 );
+            },
+            _ => { },
+        }
+
+        // Write passes, which are always exr as they hold data rather than images
+        for ( pass_name, pixels ) in passes {
+            let path = self.settings.out_dir.join( format!( "{name}_{pass_name}.exr" ) );
+            if let Err( err ) = save_image( &path, pixels, width, height, ImageFormat::Exr ) {
+                println!( "Failed to write {}: {err}", path.display() );
+            }
+        }
+
+        // Pipe to ffmpeg, stopping it if the pipe breaks
+        if let Some( ffmpeg ) = &mut self.ffmpeg {
+            let written = ffmpeg.stdin.as_mut().map( |stdin| stdin.write_all( &to_rgb8( display ) ) );
+            if !matches!( written, Some( Ok( () ) ) ) {
+                println!( "ffmpeg stopped accepting frames, only writing image files" );
+                self.ffmpeg = None;
+            }
+        }
+
+        println!( "Exported frame {}/{}", self.frame + 1, self.frames );
+        self.frame += 1;
+    }
+
+    /**
+     * Writes an image file to the output directory, in the export's format.
+     *
+     * @param name The file name, without extension.
+     * @param pixels The pixels as linear RGBA floats, starting at the top left corner.
+     * @param width The width of the image, in pixels.
+     * @param height The height of the image, in pixels.
+     */
+    fn write_image( &self, name: &str, pixels: &[f32], width: u32, height: u32 ) {
+        let path = self.settings.out_dir.join( format!( "{name}.{}", match self.settings.format {
+            ImageFormat::Png => "png",
+            ImageFormat::Exr => "exr",
+        } ) );
+
+        if let Err( err ) = save_image( &path, pixels, width, height, self.settings.format ) {
+            println!( "Failed to write {}: {err}", path.display() );
+        }
+    }
+
+    /**
+     * Finishes the export, waiting for ffmpeg to finish encoding.
+     */
+    pub fn finish( mut self ) {
+        if let Some( mut ffmpeg ) = self.ffmpeg.take() {
+            // Closing stdin tells ffmpeg there are no more frames
+            drop( ffmpeg.stdin.take() );
+            if let Err( err ) = ffmpeg.wait() {
+                println!( "ffmpeg failed: {err}" );
+            }
+        }
+    }
+}
+
+/**
+ * Saves an image file.
+ *
+ * @param path The path of the file.
+ * @param pixels The pixels as linear RGBA floats, starting at the top left corner.
+ * @param width The width of the image, in pixels.
+ * @param height The height of the image, in pixels.
+ * @param format The image format.
+ *
+ * @return Ok if the image was saved, an error otherwise.
+ */
+fn save_image( path: &Path, pixels: &[f32], width: u32, height: u32, format: ImageFormat ) -> image::ImageResult<()> {
+    match format {
+        ImageFormat::Png => {
+            image::RgbImage::from_raw( width, height, to_rgb8( pixels ) )
+                .expect( "ERROR::EXPORT::INVALID_FRAME_SIZE" )
+                .save( path )
+        },
+        ImageFormat::Exr => {
+            let rgb32f: Vec<f32> = pixels.chunks_exact( 4 ).flat_map( |pixel| pixel[..3].to_vec() ).collect();
+            image::Rgb32FImage::from_raw( width, height, rgb32f )
+                .expect( "ERROR::EXPORT::INVALID_FRAME_SIZE" )
+                .save( path )
+        },
+    }
+}
+
+/**
+ * Converts RGBA floats to 8-bit RGB, clamped to [0, 1]. Used by both png and ffmpeg.
+ *
+ * @param pixels The pixels as RGBA floats.
+ * @return The pixels as RGB bytes.
+ */
+fn to_rgb8( pixels: &[f32] ) -> Vec<u8> {
+    pixels.chunks_exact( 4 )
+        .flat_map( |pixel| pixel[..3].iter().map( |&c| ( c.clamp( 0.0, 1.0 ) * 255.0 ).round() as u8 ) )
+        .collect()
+}
@@ -0,0 +1,209 @@
+/**
+ * Struct for an offscreen render target (framebuffer object with texture attachments).
+ */
+pub struct RenderTarget {
+    pub fbo: u32,
+    pub textures: Vec<u32>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/**
+ * Struct for a render target builder.
+ * @see RenderTarget
+ */
+pub struct RenderTargetBuilder {
+    width: u32,
+    height: u32,
+    formats: Vec<gl::types::GLenum>,
+}
+
+/**
+ * RenderTargetBuilder functions.
+ */
+impl RenderTargetBuilder {
+    /**
+     * Constructor.
+     *
+     * @param width The width of the render target, in pixels.
+     * @param height The height of the render target, in pixels.
+     */
+    pub fn new( width: u32, height: u32 ) -> RenderTargetBuilder {
+        RenderTargetBuilder { width, height, formats: Vec::new() }
+    }
+
+    /**
+     * Adds a color attachment to the render target.
+     * Attachments are numbered in the order they are added, matching the shader's output locations.
+     *
+     * @param internal_format The internal format of the attachment's texture, for example gl::RGBA32F.
+     */
+    pub fn add_color_attachment( mut self, internal_format: gl::types::GLenum ) -> RenderTargetBuilder {
+        self.formats.push( internal_format );
+        self
+    }
+
+    /**
+     * Creates the framebuffer and its textures.
+     *
+     * @return The finished render target.
+     */
+    #[must_use = "The render target must be used or it is useless."]
+    pub unsafe fn link( self ) -> RenderTarget {
+        // Generate & bind FBO
+        let mut fbo: gl::types::GLuint = 0;
+        gl::GenFramebuffers( 1, &mut fbo );
+        gl::BindFramebuffer( gl::FRAMEBUFFER, fbo );
+
+        // Create a texture for each attachment
+        let mut textures = Vec::new();
+        let mut draw_buffers = Vec::new();
+        for ( i, &format ) in self.formats.iter().enumerate() {
+            let mut texture: gl::types::GLuint = 0;
+            gl::GenTextures( 1, &mut texture );
+            gl::BindTexture( gl::TEXTURE_2D, texture );
+            gl::TexStorage2D( gl::TEXTURE_2D, 1, format, self.width as i32, self.height as i32 );
+            gl::TexParameteri( gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32 );
+            gl::TexParameteri( gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32 );
+            gl::TexParameteri( gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32 );
+            gl::TexParameteri( gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32 );
+            gl::FramebufferTexture2D( gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0 + i as u32, gl::TEXTURE_2D, texture, 0 );
+
+            textures.push( texture );
+            draw_buffers.push( gl::COLOR_ATTACHMENT0 + i as u32 );
+        }
+        gl::DrawBuffers( draw_buffers.len() as i32, draw_buffers.as_ptr() );
+
+        // Error handling
+        if gl::CheckFramebufferStatus( gl::FRAMEBUFFER ) != gl::FRAMEBUFFER_COMPLETE {
+            panic!( "ERROR::FRAMEBUFFER::INCOMPLETE" );
+        }
+
+        // Unbind and return
+        gl::BindTexture( gl::TEXTURE_2D, 0 );
+        gl::BindFramebuffer( gl::FRAMEBUFFER, 0 );
+
+        RenderTarget {
+            fbo,
+            textures,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+/**
+ * RenderTarget functions.
+ */
+impl RenderTarget {
+    /**
+     * Binds the render target for drawing, and sets the viewport to cover it.
+     */
+    pub unsafe fn bind( &self ) {
+        gl::BindFramebuffer( gl::FRAMEBUFFER, self.fbo );
+        gl::Viewport( 0, 0, self.width as i32, self.height as i32 );
+    }
+
+    /**
+     * Binds the default framebuffer (the window) for drawing.
+     *
+     * @param width The width of the window, in pixels.
+     * @param height The height of the window, in pixels.
+     */
+    pub unsafe fn bind_default( width: u32, height: u32 ) {
+        gl::BindFramebuffer( gl::FRAMEBUFFER, 0 );
+        gl::Viewport( 0, 0, width as i32, height as i32 );
+    }
+
+    /**
+     * Binds an attachment's texture to a texture unit.
+     *
+     * @param attachment The index of the attachment.
+     * @param unit The texture unit.
+     */
+    pub unsafe fn bind_texture( &self, attachment: usize, unit: u32 ) {
+        gl::ActiveTexture( gl::TEXTURE0 + unit );
+        gl::BindTexture( gl::TEXTURE_2D, self.textures[attachment] );
+    }
+
+    /**
+     * Reads back the pixels of an attachment as RGBA floats.
+     * The rows are flipped so that the first row is the top of the image.
+     *
+     * @param attachment The index of the attachment.
+     * @return The pixels, 4 floats per pixel.
+     */
+    pub unsafe fn read_pixels( &self, attachment: usize ) -> Vec<f32> {
+        let mut pixels = vec![0.0f32; ( self.width * self.height * 4 ) as usize];
+        gl::BindFramebuffer( gl::READ_FRAMEBUFFER, self.fbo );
+        gl::ReadBuffer( gl::COLOR_ATTACHMENT0 + attachment as u32 );
+        gl::ReadPixels(
+            0, 0,
+            self.width as i32, self.height as i32,
+            gl::RGBA,
+            gl::FLOAT,
+            pixels.as_mut_ptr() as *mut std::ffi::c_void
+        );
+        gl::BindFramebuffer( gl::READ_FRAMEBUFFER, 0 );
+
+        flip_rows( &pixels, self.width as usize * 4 )
+    }
+
+    /**
+     * Replaces the pixels of an attachment with RGBA floats.
+     * The rows are flipped, so that the first row is the top of the image, like in read_pixels().
+     *
+     * @param attachment The index of the attachment.
+     * @param pixels The pixels, 4 floats per pixel.
+     */
+    pub unsafe fn write_pixels( &self, attachment: usize, pixels: &[f32] ) {
+        let flipped = flip_rows( pixels, self.width as usize * 4 );
+        gl::BindTexture( gl::TEXTURE_2D, self.textures[attachment] );
+        gl::TexSubImage2D(
+            gl::TEXTURE_2D, 0,
+            0, 0,
+            self.width as i32, self.height as i32,
+            gl::RGBA,
+            gl::FLOAT,
+            flipped.as_ptr() as *const std::ffi::c_void
+        );
+        gl::BindTexture( gl::TEXTURE_2D, 0 );
+    }
+}
+
+/**
+ * Flips the rows of an image upside down.
+ * OpenGL's origin is in the bottom left corner, while image files start at the top left.
+ *
+ * @param pixels The pixels.
+ * @param row_length The amount of values in one row.
+ *
+ * @return The flipped pixels.
+ */
+pub fn flip_rows<T: Copy>( pixels: &[T], row_length: usize ) -> Vec<T> {
+    pixels.chunks_exact( row_length ).rev().flatten().copied().collect()
+}
+
+/**
+ * Reads back the pixels of the default framebuffer's back buffer (what is about to be shown on the screen).
+ * The rows are flipped so that the first row is the top of the image.
+ *
+ * @param width The width of the window, in pixels.
+ * @param height The height of the window, in pixels.
+ *
+ * @return The pixels, 4 bytes (RGBA) per pixel.
+ */
+pub unsafe fn read_default_pixels( width: u32, height: u32 ) -> Vec<u8> {
+    let mut pixels = vec![0u8; ( width * height * 4 ) as usize];
+    gl::BindFramebuffer( gl::READ_FRAMEBUFFER, 0 );
+    gl::ReadBuffer( gl::BACK );
+    gl::ReadPixels(
+        0, 0,
+        width as i32, height as i32,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixels.as_mut_ptr() as *mut std::ffi::c_void
+    );
+
+    flip_rows( &pixels, width as usize * 4 )
+}
@@ -0,0 +1,438 @@
+use crate::overlay::{TextGrid, TextStyle};
+
+// Width of the labels in front of widgets, in cells
+const LABEL_WIDTH: i32 = 12;
+
+/**
+ * Struct for the state of the mouse.
+ * It is written by the event loop on the main thread and read by the render thread, like the held keys.
+ */
+#[derive(Clone, Copy)]
+pub struct MouseState {
+    // Position in (logical) pixels from the top left corner of the window
+    pub position: glm::Vec2,
+    pub left_down: bool,
+    // Scrolled lines since the render thread last read the state, positive is up
+    pub scroll: f32,
+}
+
+/**
+ * MouseState functions.
+ */
+impl MouseState {
+    /**
+     * Creates a new mouse state, with the mouse in the top left corner and no buttons held.
+     */
+    pub fn new() -> MouseState {
+        MouseState { position: glm::Vec2::zeros(), left_down: false, scroll: 0.0 }
+    }
+}
+
+/**
+ * Struct for an immediate mode GUI, drawn as text.
+ * Every frame, the widgets are declared after begin(). Each one draws itself to a text grid
+ * and reports whether the user changed it, so there is no widget state to keep in sync with the values they edit.
+ * Widgets are identified by their cell, so the layout must stay the same while a widget is being dragged.
+ */
+pub struct Gui {
+    grid: TextGrid,
+    mouse: MouseState,
+    // Mouse position in cells (fractional) and movement in pixels since the previous frame
+    mouse_cell: glm::Vec2,
+    mouse_delta: glm::Vec2,
+    // Whether the left button was pressed this frame
+    pressed: bool,
+    // Cell of the widget being dragged, while the left button is held
+    active: Option<( i32, i32 )>,
+    // Layout of the current panel: its left column, the next row and its width
+    column: i32,
+    row: i32,
+    width: i32,
+    // Area of each panel drawn this frame, as (column, row, width, height)
+    panels: Vec<( i32, i32, i32, i32 )>,
+}
+
+/**
+ * Gui functions.
+ */
+impl Gui {
+    /**
+     * Creates a GUI drawing to a grid.
+     *
+     * @param grid The grid, usually created by TextOverlay::create_grid().
+     */
+    pub fn new( grid: TextGrid ) -> Gui {
+        Gui {
+            grid,
+            mouse: MouseState::new(),
+            mouse_cell: glm::Vec2::zeros(),
+            mouse_delta: glm::Vec2::zeros(),
+            pressed: false,
+            active: None,
+            column: 0,
+            row: 0,
+            width: 0,
+            panels: Vec::new(),
+        }
+    }
+
+    /**
+     * Starts a new frame, clearing the grid.
+     *
+     * @param mouse The current state of the mouse.
+     * @param mouse_cell The mouse position in cells, see TextOverlay::cell_at().
+     */
+    pub fn begin( &mut self, mouse: MouseState, mouse_cell: glm::Vec2 ) {
+        self.grid.clear();
+        self.panels.clear();
+
+        self.pressed = mouse.left_down && !self.mouse.left_down;
+        self.mouse_delta = mouse.position - self.mouse.position;
+        self.mouse_cell = mouse_cell;
+        self.mouse = mouse;
+        if !mouse.left_down {
+            self.active = None;
+        }
+    }
+
+    /**
+     * Checks if the mouse is used by the GUI, either by being over a panel or by dragging a widget.
+     */
+    pub fn wants_mouse( &self ) -> bool {
+        let ( column, row ) = ( self.mouse_cell.x.floor() as i32, self.mouse_cell.y.floor() as i32 );
+        self.active.is_some() || self.panels.iter().any( |&( panel_column, panel_row, width, height )|
+            column >= panel_column && column < panel_column + width && row >= panel_row && row < panel_row + height
+        )
+    }
+
+    /**
+     * Checks if the left mouse button was pressed this frame outside of the GUI, meaning the click belongs to the scene.
+     * Only the panels declared so far this frame are taken into account.
+     */
+    pub fn clicked_outside( &self ) -> bool {
+        self.pressed && !self.wants_mouse()
+    }
+
+    // --- Getters
+    pub fn grid( &self )            -> &TextGrid { &self.grid }
+    pub fn grid_mut( &mut self )    -> &mut TextGrid { &mut self.grid }
+
+    // --- Layout
+    /**
+     * Starts a panel. The following widgets are placed below each other within it.
+     *
+     * @param title The title of the panel.
+     * @param column The left column of the panel.
+     * @param row The top row of the panel.
+     * @param width The width of the panel, in cells.
+     */
+    pub fn panel( &mut self, title: &str, column: i32, row: i32, width: i32 ) {
+        ( self.column, self.row, self.width ) = ( column, row, width );
+        self.panels.push( ( column, row, width, 0 ) );
+        self.heading( title );
+    }
+
+    /**
+     * Moves to the next row of the current panel, filling it with the panel's background.
+     *
+     * @return The row.
+     */
+    fn next_row( &mut self ) -> i32 {
+        let row = self.row;
+        self.row += 1;
+        if let Some( panel ) = self.panels.last_mut() {
+            panel.3 = self.row - panel.1;
+        }
+        self.grid.write( self.column, row, &" ".repeat( self.width.max( 0 ) as usize ), TextStyle::Normal );
+        row
+    }
+
+    /**
+     * Writes the label in front of a widget.
+     *
+     * @param row The row of the widget.
+     * @param label The label, cut off at the label width.
+     */
+    fn write_label( &mut self, row: i32, label: &str ) {
+        let label: String = label.chars().take( LABEL_WIDTH as usize - 1 ).collect();
+        self.grid.write( self.column + 1, row, &label, TextStyle::Normal );
+    }
+
+    /**
+     * Gets the first column and the width of the area to the right of a widget's label.
+     */
+    fn content_area( &self ) -> ( i32, i32 ) {
+        ( self.column + 1 + LABEL_WIDTH, self.width - 2 - LABEL_WIDTH )
+    }
+
+    /**
+     * Checks if the mouse is over an area of a row.
+     */
+    fn hovered( &self, column: i32, row: i32, width: i32 ) -> bool {
+        let ( mouse_column, mouse_row ) = ( self.mouse_cell.x.floor() as i32, self.mouse_cell.y.floor() as i32 );
+        mouse_row == row && mouse_column >= column && mouse_column < column + width
+    }
+
+    /**
+     * Draws clickable text, highlighted while the mouse is over it.
+     *
+     * @return Whether the text was clicked this frame.
+     */
+    fn clickable( &mut self, column: i32, row: i32, text: &str, style: TextStyle ) -> bool {
+        let hovered = self.active.is_none() && self.hovered( column, row, text.chars().count() as i32 );
+        self.grid.write( column, row, text, if hovered { TextStyle::Highlight } else { style } );
+        hovered && self.pressed
+    }
+
+    // --- Widgets
+    /**
+     * Adds a line of text.
+     */
+    pub fn label( &mut self, text: &str ) {
+        let row = self.next_row();
+        let text: String = text.chars().take( self.width.max( 2 ) as usize - 2 ).collect();
+        self.grid.write( self.column + 1, row, &text, TextStyle::Normal );
+    }
+
+    /**
+     * Adds a line of highlighted text, for titles.
+     */
+    pub fn heading( &mut self, text: &str ) {
+        let row = self.next_row();
+        let text: String = text.chars().take( self.width.max( 2 ) as usize - 2 ).collect();
+        self.grid.write( self.column + 1, row, &text, TextStyle::Accent );
+    }
+
+    /**
+     * Adds a button.
+     *
+     * @return Whether the button was clicked.
+     */
+    pub fn button( &mut self, text: &str ) -> bool {
+        let row = self.next_row();
+        self.clickable( self.column + 1, row, &format!( "[ {text} ]" ), TextStyle::Normal )
+    }
+
+    /**
+     * Adds a row of tabs, of which one is selected.
+     *
+     * @param selected The index of the selected tab, changed when another one is clicked.
+     * @param names The names of the tabs.
+     *
+     * @return Whether the selected tab changed.
+     */
+    pub fn tabs( &mut self, selected: &mut usize, names: &[&str] ) -> bool {
+        let row = self.next_row();
+        let mut column = self.column + 1;
+        let mut changed = false;
+        for ( index, name ) in names.iter().enumerate() {
+            let style = if index == *selected { TextStyle::Accent } else { TextStyle::Normal };
+            let text = format!( "[{name}]" );
+            if self.clickable( column, row, &text, style ) && index != *selected {
+                *selected = index;
+                changed = true;
+            }
+            column += text.len() as i32 + 1;
+        }
+        changed
+    }
+
+    /**
+     * Adds an item of a list, which can be selected.
+     *
+     * @param text The text of the item.
+     * @param selected Whether the item is currently selected.
+     *
+     * @return Whether the item was clicked.
+     */
+    pub fn selectable( &mut self, text: &str, selected: bool ) -> bool {
+        let row = self.next_row();
+        let text = format!( "{:width$}", text, width = self.width.max( 2 ) as usize - 2 );
+        self.clickable( self.column + 1, row, &text, if selected { TextStyle::Accent } else { TextStyle::Normal } )
+    }
+
+    /**
+     * Adds a checkbox.
+     *
+     * @param label The label of the checkbox.
+     * @param value The value, toggled when the checkbox is clicked.
+     *
+     * @return Whether the value changed.
+     */
+    pub fn checkbox( &mut self, label: &str, value: &mut bool ) -> bool {
+        let row = self.next_row();
+        self.write_label( row, label );
+        let ( column, _ ) = self.content_area();
+        let clicked = self.clickable( column, row, if *value { "[x]" } else { "[ ]" }, TextStyle::Normal );
+        if clicked {
+            *value = !*value;
+        }
+        clicked
+    }
+
+    /**
+     * Adds a button showing the current value of a setting with a fixed set of options, such as an enum.
+     *
+     * @param label The label of the setting.
+     * @param value The name of the current value.
+     *
+     * @return Whether the button was clicked, in which case the caller should move to the next value.
+     */
+    pub fn choice( &mut self, label: &str, value: &str ) -> bool {
+        let row = self.next_row();
+        self.write_label( row, label );
+        let ( column, _ ) = self.content_area();
+        self.clickable( column, row, &format!( "< {value} >" ), TextStyle::Normal )
+    }
+
+    /**
+     * Adds a slider, which is set by dragging along it or by scrolling over it.
+     *
+     * @param label The label of the slider.
+     * @param value The value, kept within [min, max].
+     * @param min The value at the left end of the slider.
+     * @param max The value at the right end of the slider.
+     *
+     * @return Whether the value changed.
+     */
+    pub fn slider( &mut self, label: &str, value: &mut f32, min: f32, max: f32 ) -> bool {
+        self.slider_with_step( label, value, min, max, ( max - min ) / 100.0, 2 )
+    }
+
+    /**
+     * Adds a slider for whole numbers.
+     * @see slider()
+     */
+    pub fn slider_u32( &mut self, label: &str, value: &mut u32, min: u32, max: u32 ) -> bool {
+        let mut value_f32 = *value as f32;
+        self.slider_with_step( label, &mut value_f32, min as f32, max as f32, 1.0, 0 );
+
+        let rounded = value_f32.round() as u32;
+        let changed = rounded != *value;
+        *value = rounded;
+        changed
+    }
+
+    /**
+     * Adds a slider.
+     *
+     * @param scroll_step How much the value changes per scrolled line.
+     * @param decimals The amount of decimals shown.
+     * @see slider()
+     */
+    fn slider_with_step( &mut self, label: &str, value: &mut f32, min: f32, max: f32, scroll_step: f32, decimals: usize ) -> bool {
+        let row = self.next_row();
+        self.write_label( row, label );
+
+        // The bar is followed by the value
+        let ( column, width ) = self.content_area();
+        let bar_width = ( width - 8 ).max( 1 );
+        let hovered = self.hovered( column, row, bar_width );
+        if hovered && self.pressed {
+            self.active = Some( ( column, row ) );
+        }
+
+        let mut new_value = *value;
+        if self.active == Some( ( column, row ) ) {
+            let t = ( ( self.mouse_cell.x - column as f32 ) / bar_width as f32 ).clamp( 0.0, 1.0 );
+            new_value = min + t * ( max - min );
+        }
+        if hovered {
+            new_value += self.mouse.scroll * scroll_step;
+        }
+        new_value = new_value.clamp( min, max );
+
+        // Draw
+        let filled = ( ( new_value - min ) / ( max - min ).max( f32::EPSILON ) * bar_width as f32 ).round() as usize;
+        let bar = format!( "{}{}", "=".repeat( filled ), "-".repeat( bar_width as usize - filled.min( bar_width as usize ) ) );
+        let style = if hovered || self.active == Some( ( column, row ) ) { TextStyle::Highlight } else { TextStyle::Normal };
+        self.grid.write( column, row, &bar, style );
+        self.grid.write( column + bar_width + 1, row, &format!( "{new_value:>7.decimals$}" ), TextStyle::Normal );
+
+        let changed = new_value != *value;
+        *value = new_value;
+        changed
+    }
+
+    /**
+     * Adds a number field, which is changed by dragging the mouse sideways over it or by scrolling over it.
+     *
+     * @param label The label of the field.
+     * @param value The value.
+     * @param speed How much the value changes per pixel dragged. Each scrolled line counts as 10 pixels.
+     *
+     * @return Whether the value changed.
+     */
+    pub fn drag( &mut self, label: &str, value: &mut f32, speed: f32 ) -> bool {
+        let row = self.next_row();
+        self.write_label( row, label );
+        let ( column, width ) = self.content_area();
+        self.drag_field( column, row, width, value, speed, f32::MIN, f32::MAX )
+    }
+
+    /**
+     * Adds three number fields on one row, for vectors.
+     * @see drag()
+     */
+    pub fn drag3( &mut self, label: &str, value: &mut glm::Vec3, speed: f32 ) -> bool {
+        self.drag3_clamped( label, value, speed, f32::MIN, f32::MAX )
+    }
+
+    /**
+     * Adds three number fields on one row for a color, with each channel within [0, 1].
+     * @see drag()
+     */
+    pub fn color( &mut self, label: &str, value: &mut glm::Vec3 ) -> bool {
+        self.drag3_clamped( label, value, 0.005, 0.0, 1.0 )
+    }
+
+    /**
+     * Adds three number fields on one row, with each value kept within [min, max].
+     * @see drag()
+     */
+    fn drag3_clamped( &mut self, label: &str, value: &mut glm::Vec3, speed: f32, min: f32, max: f32 ) -> bool {
+        let row = self.next_row();
+        self.write_label( row, label );
+        let ( column, width ) = self.content_area();
+        let field_width = width / 3;
+
+        let mut changed = false;
+        for axis in 0..3 {
+            changed |= self.drag_field( column + axis as i32 * field_width, row, field_width - 1, &mut value[axis], speed, min, max );
+        }
+        changed
+    }
+
+    /**
+     * Draws a number field and handles dragging and scrolling over it.
+     *
+     * @return Whether the value changed.
+     */
+    #[allow(clippy::too_many_arguments)]
+    fn drag_field( &mut self, column: i32, row: i32, width: i32, value: &mut f32, speed: f32, min: f32, max: f32 ) -> bool {
+        let hovered = self.hovered( column, row, width );
+        if hovered && self.pressed {
+            self.active = Some( ( column, row ) );
+        }
+
+        let mut new_value = *value;
+        if self.active == Some( ( column, row ) ) {
+            new_value += self.mouse_delta.x * speed;
+        }
+        if hovered {
+            new_value += self.mouse.scroll * speed * 10.0;
+        }
+        new_value = new_value.clamp( min, max );
+
+        // Draw, with fewer decimals for large values so they fit
+        let inner_width = ( width - 2 ).max( 1 ) as usize;
+        let decimals = if new_value.abs() >= 100.0 { 1 } else { 3 };
+        let text: String = format!( "{new_value:>inner_width$.decimals$}" ).chars().take( inner_width ).collect();
+        let style = if hovered || self.active == Some( ( column, row ) ) { TextStyle::Highlight } else { TextStyle::Normal };
+        self.grid.write( column, row, &format!( "[{text}]" ), style );
+
+        let changed = new_value != *value;
+        *value = new_value;
+        changed
+    }
+}
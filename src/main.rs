@@ -2,9 +2,24 @@
 use std::{ thread, ptr };
 use std::sync::{Mutex, Arc, RwLock};
 
-use glutin::event::{Event, WindowEvent, KeyboardInput, ElementState::{Pressed, Released}, VirtualKeyCode::{self}};
+use glutin::event::{Event, WindowEvent, KeyboardInput, MouseButton, MouseScrollDelta, ElementState::{Pressed, Released}, VirtualKeyCode::{self}};
 use glutin::event_loop::ControlFlow;
-use raytracing::{RTSphere, RTMaterial, RTSettings, RTCamera};
+use raytracing::{RTMaterial, RTSettings, RTCamera, RTEnvironment, DebugMode};
+use aov::{Aov, AOVS};
+use scene::{Scene, Transform, Geometry};
+use material::MaterialLibrary;
+use raycast::RayHit;
+use animation::Animation;
+use clock::Clock;
+use export::{ExportSettings, Exporter};
+use framebuffer::{RenderTarget, RenderTargetBuilder};
+use screenshot::ScreenshotInfo;
+use bookmarks::{Bookmark, Bookmarks, CameraTransition};
+use postprocess::PostSettings;
+use denoise::{DenoiseSettings, Denoiser};
+use overlay::{TextOverlay, TextStyle, GpuTimer};
+use gui::{Gui, MouseState};
+use editor::{EDITOR_TABS, INSPECTOR_HEIGHT};
 
 extern crate nalgebra_glm as glm;
 
@@ -13,15 +28,51 @@ mod shader;
 mod camera;
 mod raytracing;
 mod mesh;
+mod ply;
+mod stl;
+mod scene;
+mod material;
+mod animation;
+mod clock;
+mod export;
+mod framebuffer;
+mod screenshot;
+mod bookmarks;
+mod raycast;
+mod postprocess;
+mod denoise;
+mod aov;
+mod font;
+mod overlay;
+mod gui;
+mod editor;
 
 // Initial window size
 const INITIAL_SCREEN_W: u32 = 720;
 const INITIAL_SCREEN_H: u32 = 400;
 
+// File the camera bookmarks are stored in
+const BOOKMARKS_PATH: &str = "resources/scene.bookmarks";
+
 /**
  * The main function.
  */
 fn main() {
+    // --- Parse command line arguments
+    // If an export is requested, the frames are rendered offline in a hidden window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let export_settings = ExportSettings::from_args( &args, ( INITIAL_SCREEN_W, INITIAL_SCREEN_H ) )
+        .unwrap_or_else( |err| {
+            println!( "{err}" );
+            std::process::exit( 1 );
+        } );
+
+    // TODO: Resize events
+    let ( screen_width, screen_height ) = match &export_settings {
+        Some( settings ) => ( settings.width, settings.height ),
+        None => ( INITIAL_SCREEN_W, INITIAL_SCREEN_H ),
+    };
+
     // --- Create contexted window
     // Create context builder
     let context_builder = glutin::ContextBuilder::new()
@@ -31,10 +82,13 @@ fn main() {
     let window_builder = glutin::window::WindowBuilder::new()
         .with_title ( "OpenGL Raytracing Engine" )
         .with_resizable ( true )
-        .with_inner_size ( glutin::dpi::LogicalSize::new(INITIAL_SCREEN_W, INITIAL_SCREEN_H) );
+        .with_visible ( export_settings.is_none() )
+        .with_inner_size ( glutin::dpi::LogicalSize::new(screen_width, screen_height) );
 
     // Create event loop
+    // (The proxy lets the render thread close the window once it is done)
     let event_loop = glutin::event_loop::EventLoop::new();
+    let event_loop_proxy = event_loop.create_proxy();
 
     // Assemble
     let context_pre = context_builder
@@ -43,6 +97,11 @@ fn main() {
     // --- Set up event listeners
     let arc_keys_mainthread = Arc::new( Mutex::new( Vec::<VirtualKeyCode>::with_capacity(10) ) );
     let arc_keys_renderthread = Arc::clone( &arc_keys_mainthread );
+    let arc_mouse_mainthread = Arc::new( Mutex::new( MouseState::new() ) );
+    let arc_mouse_renderthread = Arc::clone( &arc_mouse_mainthread );
+
+    // Cursor positions are given in physical pixels, while the screen size is in logical pixels
+    let mut scale_factor = context_pre.window().scale_factor();
     
     // --- Start render thread
     // Spawn thread
@@ -72,7 +131,7 @@ fn main() {
         camera.set_view_params(
             glm::zero(),
             glm::zero(),
-            90.0,
+            60.0,
             1.0,
             10.0,
         );
@@ -80,11 +139,33 @@ fn main() {
         let (
             camera_move_speed,
             camera_rotation_speed,
+            camera_transition_duration,
+            camera_aperture_speed,
+            exposure_speed,
         ) = (
             5.0,
             3.0,
+            1.0,
+            0.1,
+            2.0,
         );
 
+        // Load camera bookmarks
+        // When exporting, the camera can start at one of them.
+        let mut bookmarks = Bookmarks::load( std::path::Path::new( BOOKMARKS_PATH ) );
+        let mut camera_transition: Option<CameraTransition> = None;
+        if let Some( slot ) = export_settings.as_ref().and_then( |settings| settings.bookmark ) {
+            match bookmarks.get( slot ) {
+                Some( bookmark ) => bookmark.apply( &mut camera ),
+                None => println!( "Bookmark {slot} does not exist, using the default camera" ),
+            }
+        }
+        if let Some( settings ) = &export_settings {
+            camera.set_projection( settings.projection, None );
+            camera.set_stereo( settings.stereo_mode, settings.ipd, settings.convergence );
+            camera.set_shutter( Some( 0.0 ), Some( settings.shutter_angle / 360.0 / settings.fps ) );
+        }
+
         // --- Set up game objects
         // Set up screen quad
         let (vertices, indices) = util::create_billboard();
@@ -96,52 +177,235 @@ fn main() {
                 .link()
         };
 
+        // Set up the render target which frames are accumulated in, and the shader which draws it to the screen
+        // The accumulated frame is the average of all frames rendered since the camera or scene last changed.
+        // It holds linear, unbounded light, which the present shader exposes, tone maps and encodes for display,
+        // along with the other AOVs (albedo, normal, depth, ...) used by the denoiser and for compositing.
+        let accumulation_target = unsafe {
+            AOVS.iter()
+                .fold( RenderTargetBuilder::new( screen_width, screen_height ), |builder, _| builder.add_color_attachment( gl::RGBA32F ) )
+                .link()
+        };
+        let output_target = unsafe {
+            RenderTargetBuilder::new( screen_width, screen_height )
+                .add_color_attachment( gl::RGBA8 )
+                .link()
+        };
+        let present_shader = unsafe {
+            shader::ShaderBuilder::new()
+                .attach_shader("shaders/raytracing.vert")
+                .attach_shader("shaders/present.frag")
+                .link()
+        };
+
+        // Set shader settings
+        let mut settings = RTSettings {
+            max_bounces: 3,
+            rays_per_frag: 8,
+            diverge_strength: 0.07,
+            debug_mode: DebugMode::Off,
+        };
+        let mut prev_settings: Option<RTSettings> = None;
+
+        // Set environment
+        let mut environment = RTEnvironment::new();
+        let mut prev_environment: Option<RTEnvironment> = None;
+
+        // Set post settings
+        let mut post_settings = export_settings.as_ref().map_or( PostSettings::new(), |settings| settings.post );
+        let mut prev_post_settings: Option<PostSettings> = None;
+
+        // Set up denoiser
+        let denoiser = unsafe { Denoiser::new( screen_width, screen_height ) };
+        let denoise_settings = DenoiseSettings::new();
+        let mut denoise_enabled = false;
+
+        // The AOV shown on the screen
+        let mut displayed_aov = Aov::Color;
+
+        // Set up the stats overlay, and the GPU timers it shows
+        let text_overlay = unsafe { TextOverlay::new( screen_width, screen_height, 1 ) };
+        let mut overlay_enabled = true;
+        let ( mut trace_timer, mut display_timer ) = unsafe { ( GpuTimer::new(), GpuTimer::new() ) };
+        let mut smoothed_dt: Option<f32> = None;
+
+        // Set up the editor GUI, which shares the overlay's text grid
+        let mut gui = Gui::new( text_overlay.create_grid() );
+        let mut gui_enabled = false;
+        let mut editor_tab = 0;
+
+        // The node selected in the editor or by clicking on it, and the last picked point
+        let mut selected_node: Option<usize> = None;
+        let mut picked: Option<RayHit> = None;
+
+        // --- Set up materials
+        // (Materials animated by scene.anim are named like the node that uses them)
+        let mut material_library = MaterialLibrary::new();
+        let knight_material = material_library.add( "knight", RTMaterial {
+            color: glm::vec4(1.0, 0.0, 0.0, 1.0),
+            emission_color: glm::vec4(1.0, 0.0, 0.0, 0.5),
+            smoothness: 0.5,
+            ..RTMaterial::new()
+        } );
+        let orange_material = material_library.add( "orange_sphere", RTMaterial {
+            color: glm::vec4(1.0, 0.7, 0.3, 1.0),
+            emission_color: glm::vec4(1.0, 0.7, 0.3, 1.0),
+            specular_color: glm::vec4(1.0, 1.0, 1.0, 0.0),
+            smoothness: 0.5,
+            ..RTMaterial::new()
+        } );
+        let ground_material = material_library.add( "ground", RTMaterial {
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            emission_color: glm::vec4(1.0, 1.0, 1.0, 0.0),
+            specular_color: glm::vec4(1.0, 0.0, 0.0, 0.0),
+            smoothness: 0.3,
+            ..RTMaterial::new()
+        } );
+        let light_material = material_library.add( "blue_light", RTMaterial::emissive( glm::vec3(0.0, 0.0, 1.0), 1.0 ) );
+        let mirror_material = material_library.add( "mirror", RTMaterial {
+            color: glm::vec4(1.0, 1.0, 1.0, 1.0),
+            emission_color: glm::vec4(0.0, 0.0, 1.0, 0.0),
+            specular_color: glm::vec4(0.0, 1.0, 1.0, 1.0),
+            smoothness: 1.0,
+            ..RTMaterial::new()
+        } );
+        let gold_material = material_library.add( "gold", RTMaterial::pbr( glm::vec3(1.0, 0.78, 0.34), 0.3, 1.0 ) );
+
+        // --- Set up scene
+        let mut scene = Scene::new();
+
         // Load knight model
+        // (The model is large in file units, so it is scaled down and placed in front of the camera)
+        let knight_options = mesh::ImportOptions {
+            transform: Transform { scale: glm::vec3(1.0, 1.0, 1.0) / 80.0, ..Transform::from_translation( glm::vec3(-1.0, 1.0, 3.0) ) },
+            ..mesh::ImportOptions::new()
+        };
         let model_knight = mesh::Model::new()
-            .load_from_file("resources/knight.obj");
+            .load_from_file("resources/knight.obj", knight_options);
+
+        let ( knight_positions, knight_normals, knight_colors, knight_triangles, knight_meshes ) = model_knight.generate_raytracing_structs( knight_material );
+        scene.add_node(
+            "knight",
+            None,
+            Transform::new(),
+            Geometry::Mesh { positions: knight_positions, normals: knight_normals, colors: knight_colors, triangles: knight_triangles, meshes: knight_meshes }
+        );
 
-        let ( triangles, meshes ) = model_knight.generate_raytracing_structs();
-        let meshes_count = meshes.len();
+        // Add spheres
+        scene.add_node(
+            "orange_sphere",
+            None,
+            Transform::new(),
+            Geometry::Sphere {
+                radius: 50.0,
+                material_id: orange_material,
+            }
+        );
+        scene.add_node(
+            "ground",
+            None,
+            Transform::from_translation( glm::vec3(0.0, -150.0, 0.0) ),
+            Geometry::Sphere {
+                radius: 150.0,
+                material_id: ground_material,
+            }
+        );
+        scene.add_node( "props", None, Transform::new(), Geometry::Empty );
+        scene.add_node(
+            "blue_light",
+            Some( "props" ),
+            Transform::from_translation( glm::vec3(3.0, 1.25, 0.0) ),
+            Geometry::Light {
+                radius: 1.0,
+                material_id: light_material,
+            }
+        );
+        scene.add_node(
+            "mirror",
+            Some( "props" ),
+            Transform::from_translation( glm::vec3(2.5, -0.5, 2.5) ),
+            Geometry::Sphere {
+                radius: 2.0,
+                material_id: mirror_material,
+            }
+        );
+
+        scene.add_node(
+            "gold",
+            Some( "props" ),
+            Transform::from_translation( glm::vec3(-2.5, 1.5, 2.5) ),
+            Geometry::Sphere {
+                radius: 1.5,
+                material_id: gold_material,
+            }
+        );
+
+        // Load scene animation
+        let animation = Animation::load_from_file( "resources/animations/scene.anim" )
+            .expect( "Failed to load animation" );
+
+        // Create SSBOs for spheres/triangles/meshes/vertices from the initial state of the scene
+        // The SSBOs are updated in the gameloop whenever the scene changes.
+        // A copy of the flattened scene is kept for ray casting on the CPU.
+        let mut flat_scene = scene.flatten().expect( "ERROR::SCENE::FAILED_TO_FLATTEN" );
+
+        let mut ssbo_spheres = unsafe {
+            shader::SSBOBuilder::new()
+                .set_data( &flat_scene.spheres )
+                .set_shader_details( simple_shader.pid, 0, "SphereBuffer" )
+                .link()
+        };
 
-        // Create SSBOs for triangles/meshes
-        let triangles_ssbo = unsafe {
+        let mut ssbo_triangles = unsafe {
             shader::SSBOBuilder::new()
-                .set_data( triangles )
+                .set_data( &flat_scene.triangles )
                 .set_shader_details( simple_shader.pid, 1, "TriangleBuffer")
                 .link()
         };
 
-        let meshes_ssbo = unsafe {
+        let mut ssbo_meshes = unsafe {
             shader::SSBOBuilder::new()
-                .set_data( meshes )
+                .set_data( &flat_scene.meshes )
                 .set_shader_details( simple_shader.pid, 2, "MeshInfoBuffer")
                 .link()
         };
 
-        // Set shader settings
-        let settings = RTSettings {
-            max_bounces: 3,
-            rays_per_frag: 8,
-            diverge_strength: 0.07,
+        // (The motion matrices of moving meshes, see RTMeshInfo)
+        let mut ssbo_motion = unsafe {
+            shader::SSBOBuilder::new()
+                .set_data( &flat_scene.motion )
+                .set_shader_details( simple_shader.pid, 3, "MotionBuffer")
+                .link()
         };
 
-        unsafe {
-            settings.send_uniform( &simple_shader, "settings" );
-        }
+        // (Vertex positions and normals are sent as tightly packed floats, as an array of vec3 would be padded to vec4)
+        let mut ssbo_positions = unsafe {
+            shader::SSBOBuilder::new()
+                .set_data( &flat_scene.positions )
+                .set_shader_details( simple_shader.pid, 5, "PositionBuffer")
+                .link()
+        };
 
-        // Create SSBO for spheres
-        // For now the data is left blank, as it is immidiately overwritten in the gameloop.
-        // However, the amount of objects must be the same so the correct amount of space is reserved.
-        let spheres_count = 4;
-        let mut spheres = Vec::new();
-        for _ in 0..spheres_count {
-            spheres.push( RTSphere::new() )
-        }
+        let mut ssbo_normals = unsafe {
+            shader::SSBOBuilder::new()
+                .set_data( &flat_scene.normals )
+                .set_shader_details( simple_shader.pid, 6, "NormalBuffer")
+                .link()
+        };
 
-        let mut ssbo_spheres = unsafe {
+        // (Vertex colors are vec4s, which need no padding)
+        let mut ssbo_colors = unsafe {
             shader::SSBOBuilder::new()
-                .set_data( spheres )
-                .set_shader_details( simple_shader.pid, 0, "SphereBuffer" )
+                .set_data( &flat_scene.colors )
+                .set_shader_details( simple_shader.pid, 7, "ColorBuffer")
+                .link()
+        };
+
+        // Create the SSBO for the material table, which is updated whenever a material changes
+        let mut ssbo_materials = unsafe {
+            shader::SSBOBuilder::new()
+                .set_data( material_library.take_changed().unwrap_or_default() )
+                .set_shader_details( simple_shader.pid, 4, "MaterialBuffer")
                 .link()
         };
 
@@ -149,28 +413,38 @@ fn main() {
         // --------------- Gameloop ----------------- //
         // ------------------------------------------ //
 
-        // Start time
-        let ( time_start, mut time_prev ) = (
-            std::time::Instant::now(),
-            std::time::Instant::now()
-        );
-        
+        // Set up clock
+        // When exporting, the clock only advances once a frame has accumulated enough samples.
+        let mut clock = match &export_settings {
+            Some( settings ) => Clock::fixed( settings.fps ),
+            None => Clock::realtime(),
+        };
+        let mut exporter = export_settings.map( |settings| {
+            let frames = settings.frames.unwrap_or( ( ( animation.duration() * settings.fps ).ceil() as u32 ).max( 1 ) );
+            Exporter::new( settings, frames )
+        } );
+
+        // Frame counters, and the state used to detect when the accumulation must restart
+        let ( mut frame, mut accumulated_frames ) = ( 0u32, 0u32 );
+        let ( mut prev_rtcamera, mut animation_time ) = ( None, None );
+
+        // Keys held during the previous frame, used to detect key presses
+        let mut keys_prev = Vec::<VirtualKeyCode>::new();
+
         loop {
             // Elapsed and delta time
-            let time = std::time::Instant::now();
-            let ( time_elapsed, dt ) = (
-                time.duration_since( time_start ).as_secs_f32(),
-                time.duration_since(time_prev).as_secs_f32(),
-            );
-            time_prev = time;
-
-            // TODO: Resize events
-            let ( mut screen_width, mut screen_height ) = ( INITIAL_SCREEN_W, INITIAL_SCREEN_H );
+            let ( time_elapsed, dt ) = clock.tick();
+            smoothed_dt = Some( smoothed_dt.map_or( dt, |smoothed| smoothed * 0.95 + dt * 0.05 ) );
 
             // --- Key events
             let ( mut movement, mut rotation ) = ( glm::Vec3::zeros(), glm::Vec3::zeros() );
+            let mut keys_pressed = Vec::<VirtualKeyCode>::new();
 
             if let Ok( keys ) = arc_keys_renderthread.lock() {
+                // Keys which were pressed this frame
+                keys_pressed = keys.iter().filter( |key| !keys_prev.contains( key ) ).copied().collect();
+                keys_prev = keys.clone();
+
                 for key in keys.iter() { match key {
 
                     // Movement
@@ -211,88 +485,250 @@ fn main() {
                         }
                     }
 
+                    // Aperture
+                    VirtualKeyCode::Minus => {
+                        camera.set_lens( Some( camera.aperture() - dt * camera_aperture_speed ), None, None, None );
+                    }
+                    VirtualKeyCode::Equals => {
+                        camera.set_lens( Some( camera.aperture() + dt * camera_aperture_speed ), None, None, None );
+                    }
+
+                    // Exposure
+                    VirtualKeyCode::LBracket => {
+                        post_settings.exposure -= dt * exposure_speed;
+                    }
+                    VirtualKeyCode::RBracket => {
+                        post_settings.exposure += dt * exposure_speed;
+                    }
+
                     _ => { }
                 } }
             }
 
+            // Toggle autofocus
+            if keys_pressed.contains( &VirtualKeyCode::F ) {
+                camera.set_autofocus( !camera.autofocus() );
+                println!( "Autofocus {}", if camera.autofocus() { "enabled" } else { "disabled" } );
+            }
+
+            // Cycle camera projection
+            if keys_pressed.contains( &VirtualKeyCode::P ) {
+                camera.set_projection( Some( camera.projection().next() ), None );
+                println!( "Projection: {}", camera.projection().name() );
+            }
+
+            // Toggle denoising
+            if keys_pressed.contains( &VirtualKeyCode::N ) {
+                denoise_enabled = !denoise_enabled;
+                println!( "Denoising {}", if denoise_enabled { "enabled" } else { "disabled" } );
+            }
+
+            // Cycle displayed AOV
+            if keys_pressed.contains( &VirtualKeyCode::Tab ) {
+                displayed_aov = displayed_aov.next();
+                println!( "Displaying AOV: {}", displayed_aov.name() );
+            }
+
+            // Cycle tone mapping operator
+            if keys_pressed.contains( &VirtualKeyCode::T ) {
+                post_settings.tone_mapping = post_settings.tone_mapping.next();
+                println!( "Tone mapping: {}", post_settings.tone_mapping.name() );
+            }
+
+            // Toggle stats overlay
+            if keys_pressed.contains( &VirtualKeyCode::F1 ) {
+                overlay_enabled = !overlay_enabled;
+            }
+
+            // Toggle editor GUI
+            if keys_pressed.contains( &VirtualKeyCode::G ) {
+                gui_enabled = !gui_enabled;
+            }
+
+            // Cycle debug view mode
+            if keys_pressed.contains( &VirtualKeyCode::B ) {
+                settings.debug_mode = settings.debug_mode.next();
+                println!( "Debug view: {}", settings.debug_mode.name() );
+            }
+
+            // Cycle stereo mode
+            if keys_pressed.contains( &VirtualKeyCode::V ) {
+                camera.set_stereo( Some( camera.stereo_mode().next() ), None, None );
+                println!( "Stereo: {}", camera.stereo_mode().name() );
+            }
+
+            // Camera bookmarks
+            // Ctrl+digit saves the current viewpoint, digit moves the camera to a saved one.
+            let control_held = keys_prev.contains( &VirtualKeyCode::LControl ) || keys_prev.contains( &VirtualKeyCode::RControl );
+            for slot in keys_pressed.iter().filter_map( |&key| bookmarks::slot_for_key( key ) ) {
+                if control_held {
+                    match bookmarks.set( slot, Bookmark::from_camera( &camera ) ) {
+                        Ok( () ) => println!( "Saved camera bookmark {slot}" ),
+                        Err( err ) => println!( "{err}" ),
+                    }
+                } else if let Some( bookmark ) = bookmarks.get( slot ) {
+                    camera_transition = Some( CameraTransition::new( Bookmark::from_camera( &camera ), bookmark, camera_transition_duration ) );
+                }
+            }
+
+            // --- GUI
+            // (The scroll is only counted once)
+            let mouse = arc_mouse_renderthread.lock().map( |mut mouse| {
+                let state = *mouse;
+                mouse.scroll = 0.0;
+                state
+            } ).unwrap_or( MouseState::new() );
+            gui.begin( mouse, text_overlay.cell_at( mouse.position ) );
+            if gui_enabled {
+                let width = ( gui.grid().columns() as i32 ).min( 40 );
+                gui.panel( "Editor (G to hide)", gui.grid().columns() as i32 - width, 0, width );
+                gui.tabs( &mut editor_tab, &EDITOR_TABS );
+                match editor_tab {
+                    0 => editor::render_panel( &mut gui, &mut settings, &mut post_settings, &mut denoise_enabled, &mut displayed_aov ),
+                    1 => editor::camera_panel( &mut gui, &mut camera ),
+                    2 => editor::scene_panel( &mut gui, &mut scene, &mut material_library, &mut selected_node ),
+                    _ => editor::environment_panel( &mut gui, &mut environment ),
+                }
+            }
+
+            // Pick the object under the mouse when clicking the scene, selecting its node
+            // (This uses the previous frame's camera and scene, which are what is shown on the screen.)
+            if gui.clicked_outside() {
+                picked = camera.screen_ray( mouse.position, glm::vec2( screen_width as f32, screen_height as f32 ) )
+                    .and_then( |( origin, dir )| raycast::cast_ray( &origin, &dir, &flat_scene ) );
+                selected_node = picked.and_then( |hit| ( hit.object_id as usize ).checked_sub( 1 ) );
+                match &picked {
+                    Some( hit ) => println!( "Picked {:?} at distance {:.3}", hit.primitive, hit.dist ),
+                    None => println!( "Picked nothing" ),
+                }
+            }
+
+            // Inspect the picked point, as long as its node is still selected
+            if let Some( hit ) = picked.filter( |hit| ( hit.object_id as usize ).checked_sub( 1 ) == selected_node ) {
+                gui.panel( "Inspector", 0, gui.grid().rows() as i32 - INSPECTOR_HEIGHT, ( gui.grid().columns() as i32 ).min( 40 ) );
+                editor::inspector_panel( &mut gui, &hit, &scene, &material_library );
+            }
+
             // --- OpenGL
             unsafe {
-                // Clear color and depth buffers
-                gl::ClearColor(0.04, 0.05, 0.09, 1.0);
-                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
-
                 // Activate shader
                 simple_shader.activate();
 
-                // Update camera with player movement
-                camera.set_vars(
-                    Some( camera.pos() + movement ),
-                    Some( camera.ang() + rotation ),
-                    None,
-                    None,
-                    None
-                );
+                // Update camera with player movement, unless it is moving to a bookmark
+                if let Some( transition ) = &mut camera_transition {
+                    if transition.update( dt, &mut camera ) {
+                        camera_transition = None;
+                    }
+                } else {
+                    camera.set_vars(
+                        Some( camera.pos() + movement ),
+                        Some( camera.ang() + rotation ),
+                        None,
+                        None,
+                        None
+                    );
+                }
+
+                // Animate scene and camera
+                // (Only when the time has changed, so that a paused clock does not restart the accumulation)
+                if animation_time != Some( time_elapsed ) {
+                    animation.apply_to_camera( time_elapsed, &mut camera );
+
+                    // For motion blur, the scene is posed both when the shutter opens and when it closes
+                    if camera.shutter_close() > camera.shutter_open() {
+                        animation.apply_to_scene( time_elapsed + camera.shutter_open(), &mut scene );
+                        scene.store_shutter_open();
+                        animation.apply_to_scene( time_elapsed + camera.shutter_close(), &mut scene );
+                    } else {
+                        scene.clear_shutter_open();
+                        animation.apply_to_scene( time_elapsed, &mut scene );
+                    }
+                    animation.apply_to_materials( time_elapsed, &mut material_library );
+                    animation_time = Some( time_elapsed );
+                }
+
+                // Update the SSBOs if the scene has changed
+                if let Some( new_flat_scene ) = scene.flatten() {
+                    flat_scene = new_flat_scene;
+                    ssbo_spheres.update_data( &flat_scene.spheres );
+                    ssbo_triangles.update_data( &flat_scene.triangles );
+                    ssbo_meshes.update_data( &flat_scene.meshes );
+                    ssbo_positions.update_data( &flat_scene.positions );
+                    ssbo_normals.update_data( &flat_scene.normals );
+                    ssbo_colors.update_data( &flat_scene.colors );
+                    ssbo_motion.update_data( &flat_scene.motion );
+                    accumulated_frames = 0;
+                }
+
+                // Update the material SSBO if a material has changed
+                if let Some( materials ) = material_library.take_changed() {
+                    ssbo_materials.update_data( materials );
+                    accumulated_frames = 0;
+                }
+
+                // Autofocus on whatever is in the center of the screen
+                if camera.autofocus() {
+                    if let Some( hit ) = raycast::cast_ray( &camera.pos(), &camera.front(), &flat_scene ) {
+                        camera.set_lens( None, Some( hit.dist ), None, None );
+                    }
+                }
 
                 // Create RTCamera and pass to shader
                 // This camera is a lot like the normal Camera, but only carries the necessary variables for the shader to use
-                let rtcamera = RTCamera {
-                    screen_size: glm::vec2( screen_width as f32, screen_height as f32 ),
-                    fov: 60.0,
-                    focus_distance: 1.0,
-                    pos: camera.pos().into(),
-                    local_to_world: camera.rts(),
-                };
+                let rtcamera = RTCamera::from_camera( &camera, glm::vec2( screen_width as f32, screen_height as f32 ) );
+                if prev_rtcamera != Some( rtcamera ) {
+                    accumulated_frames = 0;
+                }
+                prev_rtcamera = Some( rtcamera );
                 rtcamera.send_uniform( &simple_shader, "camera" );
 
-                // Update sphere objects
-                ssbo_spheres.update_data(
-                    vec![
-                        RTSphere {
-                            radius: 50.0,
-                            center: glm::vec3((time_elapsed*0.5).sin() * 100.0 , time_elapsed.cos() * 100.0, 0.0).into(),
-                            material: RTMaterial {
-                                color: glm::vec4(1.0, 0.7, 0.3, 1.0),
-                                emission_color: glm::vec4(1.0, 0.7, 0.3, 1.0),
-                                specular_color: glm::vec4(1.0, 1.0, 1.0, 0.0),
-                                smoothness: 0.5,
-                            }
-                        },
-                        RTSphere {
-                            radius: 150.0,
-                            center: glm::vec3(0.0, -150.0, 0.0).into(),
-                            material: RTMaterial {
-                                color: glm::vec4(1.0, 1.0, 1.0, 1.0),
-                                emission_color: glm::vec4(1.0, 1.0, 1.0, 0.0),
-                                specular_color: glm::vec4(1.0, 0.0, 0.0, 0.0),
-                                smoothness: 0.3,
-                            }
-                        },
-                        RTSphere {
-                            radius: 1.0,
-                            center: glm::vec3(3.0, 1.25, 0.0).into(),
-                            material: RTMaterial {
-                                color: glm::vec4(0.0, 0.0, 1.0, 1.0),
-                                emission_color: glm::vec4(0.0, 0.0, 1.0, 1.0),
-                                specular_color: glm::vec4(0.0, 1.0, 1.0, 0.0),
-                                smoothness: 0.3,
-                            }
-                        },
-                        RTSphere {
-                            radius: 2.0,
-                            center: glm::vec3(2.5, -0.5, 2.5).into(),
-                            material: RTMaterial {
-                                color: glm::vec4(1.0, 1.0, 1.0, 1.0),
-                                emission_color: glm::vec4(0.0, 0.0, 1.0, 0.0),
-                                specular_color: glm::vec4(0.0, 1.0, 1.0, 1.0),
-                                smoothness: 1.0,
-                            }
-                        },
-                    ]
+                if prev_settings != Some( settings ) {
+                    settings.send_uniform( &simple_shader, "settings" );
+                    accumulated_frames = 0;
+                }
+                prev_settings = Some( settings );
+
+                if prev_environment != Some( environment ) {
+                    environment.send_uniform( &simple_shader, "environment" );
+                    accumulated_frames = 0;
+                }
+                prev_environment = Some( environment );
+
+                gl::Uniform1i( simple_shader.get_uniform_location( "spheresCount" ), flat_scene.spheres.len() as i32);
+                gl::Uniform1i( simple_shader.get_uniform_location( "meshesCount" ), flat_scene.meshes.len() as i32);
+                gl::Uniform1ui( simple_shader.get_uniform_location( "frame" ), frame);
+                gl::Uniform1ui( simple_shader.get_uniform_location( "accumulatedFrames" ), accumulated_frames);
+
+                // Draw into the accumulation target
+                // (Ids cannot be averaged, so they are not blended)
+                accumulation_target.bind();
+                gl::Disablei( gl::BLEND, Aov::Id.attachment() as u32 );
+                trace_timer.begin();
+                gl::BindVertexArray(my_vao);
+                gl::DrawElements(
+                    gl::TRIANGLES, 
+                    indices.len() as gl::types::GLint,
+                    gl::UNSIGNED_INT,
+                    ptr::null()
                 );
-                gl::Uniform1i( simple_shader.get_uniform_location( "spheresCount" ), spheres_count as i32);
-                gl::Uniform1i( simple_shader.get_uniform_location( "meshesCount" ), meshes_count as i32);
+                trace_timer.end();
+                gl::Enable( gl::BLEND );
+                frame += 1;
+                accumulated_frames += 1;
+            }
+
+            // Post pass, drawing an image (an attachment of the source, visualized like an AOV) to the currently bound framebuffer
+            // The object with the highlighted id (0 for none) is outlined, using the accumulation target's id pass.
+            let draw_post = |source: &RenderTarget, attachment: usize, aov: Aov, highlight: u32| unsafe {
+                present_shader.activate();
+                source.bind_texture( attachment, 0 );
+                accumulation_target.bind_texture( Aov::Id.attachment(), 1 );
+                gl::ActiveTexture( gl::TEXTURE0 );
+                gl::Uniform1i( present_shader.get_uniform_location( "accumulatedFrame" ), 0);
+                gl::Uniform1i( present_shader.get_uniform_location( "idTexture" ), 1);
+                gl::Uniform1ui( present_shader.get_uniform_location( "view" ), aov.view());
+                gl::Uniform1ui( present_shader.get_uniform_location( "selectedId" ), highlight);
 
-                // Draw
                 gl::BindVertexArray(my_vao);
                 gl::DrawElements(
                     gl::TRIANGLES, 
@@ -300,11 +736,115 @@ fn main() {
                     gl::UNSIGNED_INT,
                     ptr::null()
                 );
+            };
+            if prev_post_settings != Some( post_settings ) {
+                unsafe { post_settings.send_uniform( &present_shader, "post" ); }
+                prev_post_settings = Some( post_settings );
             }
 
+            // When exporting, write the frame once it has accumulated enough samples and advance the clock
+            // The window is hidden, so the post pass is drawn to an offscreen target instead of the screen.
+            if let Some( exporter ) = &mut exporter {
+                if accumulated_frames >= exporter.settings.samples {
+                    let ( linear, display ) = unsafe {
+                        let mut linear = accumulation_target.read_pixels( Aov::Color.attachment() );
+                        let mut source = &accumulation_target;
+                        if exporter.settings.denoise {
+                            linear = denoise::denoise_cpu(
+                                &linear,
+                                &accumulation_target.read_pixels( Aov::Albedo.attachment() ),
+                                &accumulation_target.read_pixels( Aov::Normal.attachment() ),
+                                &accumulation_target.read_pixels( Aov::Depth.attachment() ),
+                                screen_width as usize,
+                                screen_height as usize,
+                                &denoise_settings
+                            );
+                            source = denoiser.upload( &linear );
+                        }
+
+                        output_target.bind();
+                        draw_post( source, 0, Aov::Color, 0 );
+                        ( linear, output_target.read_pixels( 0 ) )
+                    };
+
+                    // The other AOVs are written as separate layers
+                    let passes: Vec<( &str, Vec<f32> )> = if exporter.settings.passes {
+                        AOVS[1..].iter().map( |aov| ( aov.name(), unsafe { accumulation_target.read_pixels( aov.attachment() ) } ) ).collect()
+                    } else {
+                        Vec::new()
+                    };
+                    exporter.write_frame( &linear, &display, &passes );
+                    if exporter.is_done() {
+                        break;
+                    }
+                    clock.advance_frame();
+                }
+                continue;
+            }
+
+            // Draw the accumulated (and possibly denoised) frame, or another AOV, to the screen
+            // Debug views only write colors already within [0, 1] to the color output, so they are shown like the albedo.
+            let debugging = settings.debug_mode != DebugMode::Off;
+            unsafe { display_timer.begin(); }
+            let ( source, attachment, view_aov ) = if debugging {
+                ( &accumulation_target, Aov::Color.attachment(), Aov::Albedo )
+            } else if denoise_enabled && displayed_aov == Aov::Color {
+                ( unsafe { denoiser.run( &accumulation_target, &denoise_settings, my_vao, indices.len() as i32 ) }, 0, displayed_aov )
+            } else {
+                ( &accumulation_target, displayed_aov.attachment(), displayed_aov )
+            };
+            unsafe {
+                RenderTarget::bind_default( screen_width, screen_height );
+                gl::ClearColor(0.04, 0.05, 0.09, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            }
+            draw_post( source, attachment, view_aov, selected_node.map_or( 0, |index| index as u32 + 1 ) );
+            unsafe { display_timer.end(); }
+
+            // Save a screenshot of the accumulated frame
+            if keys_pressed.contains( &VirtualKeyCode::F12 ) {
+                let pixels = unsafe { framebuffer::read_default_pixels( screen_width, screen_height ) };
+                let info = ScreenshotInfo { camera: &camera, settings: &settings, post: &post_settings, samples: accumulated_frames, time: time_elapsed };
+                match screenshot::save_screenshot( std::path::Path::new( "screenshots" ), pixels, screen_width, screen_height, &info ) {
+                    Ok( path ) => println!( "Saved screenshot to {}", path.display() ),
+                    Err( err ) => println!( "Failed to save screenshot: {err}" ),
+                }
+            }
+
+            // Draw the stats overlay and the GUI on top of everything else (after the screenshot, so they are not saved)
+            if overlay_enabled {
+                let frame_ms = smoothed_dt.unwrap_or( dt ) * 1000.0;
+                let format_ms = |timer: &GpuTimer| timer.elapsed_ms().map_or( "-".to_string(), |ms| format!( "{ms:.2} ms" ) );
+
+                // Estimate the camera rays traced per second, from the GPU time if known
+                let rays_per_frame = ( screen_width * screen_height * settings.rays_per_frag ) as f32;
+                let trace_seconds = trace_timer.elapsed_ms().unwrap_or( frame_ms ) / 1000.0;
+
+                let lines = [
+                    format!( "{:.1} fps, {:.2} ms/frame", 1000.0 / frame_ms, frame_ms ),
+                    format!( "GPU: trace {}, display {}", format_ms( &trace_timer ), format_ms( &display_timer ) ),
+                    format!( "Samples: {} frames, {} rays/px", accumulated_frames, accumulated_frames * settings.rays_per_frag ),
+                    format!( "Camera rays: {:.1} M/s", rays_per_frame / trace_seconds / 1_000_000.0 ),
+                    format!( "Resolution: {}x{}", screen_width, screen_height ),
+                    format!( "Camera: ({:.2}, {:.2}, {:.2}), fov {:.1}, {}", camera.pos().x, camera.pos().y, camera.pos().z, camera.fov(), camera.projection().name() ),
+                    format!( "Lens: aperture {:.3}, focus {:.2}", camera.aperture(), camera.focus_distance() ),
+                    format!( "Settings: {} bounces, {} rays/frag, diverge {:.2}", settings.max_bounces, settings.rays_per_frag, settings.diverge_strength ),
+                    format!( "View: {}, debug {}, denoise {}", displayed_aov.name(), settings.debug_mode.name(), if denoise_enabled { "on" } else { "off" } ),
+                    format!( "Post: exposure {:+.1}, {}", post_settings.exposure, post_settings.tone_mapping.name() ),
+                ];
+                gui.grid_mut().write_lines( 0, 0, &lines, TextStyle::Normal );
+            }
+            unsafe { text_overlay.draw( gui.grid(), my_vao, indices.len() as i32 ); }
+
             // "Flip" screen
             context.swap_buffers().unwrap(); // we use "double buffering" to avoid artifacts
         }
+
+        // Finish export and close the window
+        if let Some( exporter ) = exporter {
+            exporter.finish();
+        }
+        event_loop_proxy.send_event( () ).ok();
     } );
 
     // Spawn another thread for error handling
@@ -338,6 +878,36 @@ fn main() {
                 *control_flow = ControlFlow::Exit;
             }
 
+            //render thread is done
+            Event::UserEvent( () ) => {
+                *control_flow = ControlFlow::Exit;
+            }
+
+            //mouse input
+            Event::WindowEvent { event: WindowEvent::ScaleFactorChanged { scale_factor: new_scale_factor, .. }, .. } => {
+                scale_factor = new_scale_factor;
+            }
+            Event::WindowEvent { event: WindowEvent::CursorMoved { position, .. }, .. } => {
+                if let Ok( mut mouse ) = arc_mouse_mainthread.lock() {
+                    let position = position.to_logical::<f32>( scale_factor );
+                    mouse.position = glm::vec2( position.x, position.y );
+                }
+            }
+            Event::WindowEvent { event: WindowEvent::MouseInput { state, button: MouseButton::Left, .. }, .. } => {
+                if let Ok( mut mouse ) = arc_mouse_mainthread.lock() {
+                    mouse.left_down = state == Pressed;
+                }
+            }
+            Event::WindowEvent { event: WindowEvent::MouseWheel { delta, .. }, .. } => {
+                if let Ok( mut mouse ) = arc_mouse_mainthread.lock() {
+                    mouse.scroll += match delta {
+                        MouseScrollDelta::LineDelta( _, lines ) => lines,
+                        // (Roughly 20 pixels per line)
+                        MouseScrollDelta::PixelDelta( pixels ) => pixels.y as f32 / 20.0,
+                    };
+                }
+            }
+
             //keyboard input
             Event::WindowEvent { event: WindowEvent::KeyboardInput {
                 input: KeyboardInput { state: key_state, virtual_keycode: Some(key_code), .. }, .. 
@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+
+use crate::raytracing::RTMaterial;
+
+/**
+ * Struct for the table of materials used by the scene, which is sent to the GPU as its own buffer.
+ * Spheres and triangles refer to their material by its id (its index in the table), so a material is stored once
+ * no matter how many primitives use it, and editing it only updates the material buffer.
+ * Materials are looked up by id, or by their (unique) name.
+ */
+pub struct MaterialLibrary {
+    materials: Vec<RTMaterial>,
+    names: Vec<String>,
+    ids: HashMap<String, u32>,
+    dirty: bool,
+}
+
+/**
+ * MaterialLibrary functions.
+ */
+#[allow(dead_code)]
+impl MaterialLibrary {
+    /**
+     * Creates a new, empty, material library.
+     */
+    pub fn new() -> MaterialLibrary {
+        MaterialLibrary { materials: Vec::new(), names: Vec::new(), ids: HashMap::new(), dirty: true }
+    }
+
+    /**
+     * Adds a material to the library.
+     *
+     * @param name The name of the material. Must be unique within the library.
+     * @param material The material.
+     *
+     * @return The id of the new material.
+     */
+    pub fn add( &mut self, name: &str, material: RTMaterial ) -> u32 {
+        if self.ids.contains_key( name ) {
+            panic!( "ERROR::MATERIAL::DUPLICATE_MATERIAL_NAME\n{}", name );
+        }
+
+        let id = self.materials.len() as u32;
+        self.materials.push( material );
+        self.names.push( name.to_string() );
+        self.ids.insert( name.to_string(), id );
+
+        self.dirty = true;
+        id
+    }
+
+    /**
+     * Gets the id of a material.
+     *
+     * @param name The name of the material.
+     * @return The id of the material, or None if it does not exist.
+     */
+    pub fn id_of( &self, name: &str ) -> Option<u32> {
+        self.ids.get( name ).copied()
+    }
+
+    /**
+     * Gets a material by id.
+     *
+     * @param id The id of the material.
+     * @return The material, or None if it does not exist.
+     */
+    pub fn get( &self, id: u32 ) -> Option<&RTMaterial> {
+        self.materials.get( id as usize )
+    }
+
+    /**
+     * Gets a mutable material by id.
+     * Since the material may be changed, the library is marked as dirty.
+     *
+     * @param id The id of the material.
+     * @return The material, or None if it does not exist.
+     */
+    pub fn get_mut( &mut self, id: u32 ) -> Option<&mut RTMaterial> {
+        let material = self.materials.get_mut( id as usize )?;
+        self.dirty = true;
+        Some( material )
+    }
+
+    /**
+     * Gets a mutable material by name.
+     * Since the material may be changed, the library is marked as dirty.
+     *
+     * @param name The name of the material.
+     * @return The material, or None if it does not exist.
+     */
+    pub fn by_name_mut( &mut self, name: &str ) -> Option<&mut RTMaterial> {
+        let id = self.id_of( name )?;
+        self.get_mut( id )
+    }
+
+    /**
+     * Gets the name of a material.
+     *
+     * @param id The id of the material.
+     * @return The name, or None if the material does not exist.
+     */
+    pub fn name( &self, id: u32 ) -> Option<&str> {
+        self.names.get( id as usize ).map( String::as_str )
+    }
+
+    /**
+     * Gets the amount of materials in the library.
+     */
+    pub fn len( &self ) -> usize {
+        self.materials.len()
+    }
+
+    /**
+     * Gets all materials, ordered by id.
+     */
+    pub fn materials( &self ) -> &[RTMaterial] {
+        &self.materials
+    }
+
+    /**
+     * Gets the materials to send to the GPU buffer, if the library has changed since the last time.
+     *
+     * @return The materials, ordered by id, or None if the library was not dirty.
+     */
+    pub fn take_changed( &mut self ) -> Option<&[RTMaterial]> {
+        if !self.dirty {
+            return None
+        }
+        self.dirty = false;
+        Some( &self.materials )
+    }
+}
@@ -1,4 +1,106 @@
-use crate::raytracing::{RTTriangle, RTMeshInfo, RTMaterial};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::raytracing::{RTTriangle, RTMeshInfo, NO_MOTION};
+use crate::scene::Transform;
+use crate::{ply, stl};
+
+// Color of the vertices of files without vertex colors
+pub const DEFAULT_COLOR: [f32; 4] = [1.0, 1.0, 1.0, 1.0];
+
+/**
+ * Type for the raytracing structs of a model: vertex positions, vertex normals, vertex colors, triangles and meshes.
+ * See Model::generate_raytracing_structs().
+ */
+pub type RaytracingStructs = ( Vec<glm::Vec3>, Vec<glm::Vec3>, Vec<glm::Vec4>, Vec<RTTriangle>, Vec<RTMeshInfo> );
+
+/**
+ * Enum for how normals are generated for meshes which do not have any.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[allow(dead_code)]
+pub enum NormalGeneration {
+    // Vertex normals averaged over the faces around the vertex, except across edges sharper than the crease angle
+    Smooth,
+    // The normal of each face, for faceted shading
+    Flat,
+}
+
+/**
+ * Enum for the axis which points up in a model file.
+ * Models are converted to the Y-up space of the renderer when imported.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[allow(dead_code)]
+pub enum UpAxis {
+    Y,
+    Z,
+}
+
+/**
+ * Struct for the options used when importing a model.
+ * The model is first converted to Y-up (and mirrored, if the handedness is flipped), then centered and fitted,
+ * and lastly transformed by the import transform.
+ */
+#[derive(Clone, Copy)]
+pub struct ImportOptions {
+    // How normals are generated for parts of the model without normals
+    pub normal_generation: NormalGeneration,
+    // Largest angle between two faces (in degrees) for which smooth normals are averaged across their edge
+    pub crease_angle: f32,
+    pub up_axis: UpAxis,
+    // Mirrors the model along the Z axis, for files made with a left handed coordinate system
+    pub flip_handedness: bool,
+    // Moves the center of the model's bounding box to the origin
+    pub center: bool,
+    // Scales the model uniformly so the longest side of its bounding box is 1
+    pub fit_to_unit_box: bool,
+    pub transform: Transform,
+    // Welds vertices within this distance of each other (see Mesh::weld()), or None to keep them as they are in the file
+    pub weld_epsilon: Option<f32>,
+    // Fraction of each part's triangles to keep when decimating (see Mesh::decimate()), 1 to keep all of them
+    pub decimate_ratio: f32,
+    // Amount of times each part is subdivided (see Mesh::subdivide_loop()), each step giving four times as many triangles
+    pub subdivisions: u32,
+}
+
+/**
+ * ImportOptions functions.
+ */
+impl ImportOptions {
+    /**
+     * Creates the default import options, which keep the model as it is in the file.
+     */
+    pub fn new() -> ImportOptions {
+        ImportOptions {
+            normal_generation: NormalGeneration::Smooth,
+            crease_angle: 60.0,
+            up_axis: UpAxis::Y,
+            flip_handedness: false,
+            center: false,
+            fit_to_unit_box: false,
+            transform: Transform::new(),
+            weld_epsilon: None,
+            decimate_ratio: 1.0,
+            subdivisions: 0,
+        }
+    }
+
+    /**
+     * Calculates the matrix converting a model file's space to the renderer's: the up axis conversion and the handedness flip.
+     *
+     * @return The conversion matrix.
+     */
+    fn conversion_matrix( &self ) -> glm::Mat4 {
+        let up_axis = match self.up_axis {
+            UpAxis::Y => glm::Mat4::identity(),
+            // Rotates +Z onto +Y, and +Y onto -Z
+            UpAxis::Z => glm::rotation( -std::f32::consts::FRAC_PI_2, &glm::vec3(1.0, 0.0, 0.0) ),
+        };
+        let handedness = if self.flip_handedness { glm::scaling( &glm::vec3(1.0, 1.0, -1.0) ) } else { glm::Mat4::identity() };
+        handedness * up_axis
+    }
+}
 
 /**
  * Struct for holding a mesh.
@@ -6,11 +108,550 @@ use crate::raytracing::{RTTriangle, RTMeshInfo, RTMaterial};
 pub struct Mesh {
     pub vertices: Vec<f32>,
     pub normals: Vec<f32>,
+    // Per-vertex colors (RGBA), which tint the color of the triangles' material
     pub colors: Vec<f32>,
     pub indices: Vec<u32>,
     pub index_count: i32,
 }
 
+/**
+ * Mesh functions.
+ */
+impl Mesh {
+    /**
+     * Generates vertex normals for the mesh, replacing any it had.
+     * Smooth normals are weighted by the angle of each face at the vertex, so they do not depend on how faces are triangulated.
+     * Vertices are split where the normals of their faces differ (at creases, or everywhere for flat normals),
+     * and vertices of the mesh which no face uses are removed.
+     *
+     * @param generation How the normals are generated.
+     * @param crease_angle Largest angle between two faces (in degrees) for which smooth normals are averaged across their edge.
+     */
+    pub fn generate_normals( &mut self, generation: NormalGeneration, crease_angle: f32 ) {
+        let position = |vertex: u32| glm::vec3(
+            self.vertices[vertex as usize * 3],
+            self.vertices[vertex as usize * 3 + 1],
+            self.vertices[vertex as usize * 3 + 2],
+        );
+        let triangle_count = self.index_count as usize / 3;
+
+        // Calculate the normal of each face, and its angle at each of its corners
+        let ( mut face_normals, mut corner_angles ) = ( Vec::with_capacity( triangle_count ), Vec::with_capacity( triangle_count * 3 ) );
+        for triangle in self.indices[..triangle_count * 3].chunks( 3 ) {
+            let points = [ position( triangle[0] ), position( triangle[1] ), position( triangle[2] ) ];
+            let normal = ( points[1] - points[0] ).cross( &( points[2] - points[0] ) );
+            face_normals.push( if normal.norm() > f32::EPSILON { normal.normalize() } else { glm::Vec3::zeros() } );
+            for corner in 0..3 {
+                let ( a, b ) = ( points[( corner + 1 ) % 3] - points[corner], points[( corner + 2 ) % 3] - points[corner] );
+                corner_angles.push( if a.norm() > f32::EPSILON && b.norm() > f32::EPSILON { glm::angle( &a, &b ) } else { 0.0 } );
+            }
+        }
+
+        // Find the faces around each vertex
+        let mut vertex_faces = vec![ Vec::<usize>::new(); self.vertices.len() / 3 ];
+        for ( index, &vertex ) in self.indices[..triangle_count * 3].iter().enumerate() {
+            vertex_faces[vertex as usize].push( index );
+        }
+
+        // Calculate the normal of each corner, and create a vertex for each distinct (vertex, normal) pair
+        let crease_cos = crease_angle.to_radians().cos();
+        let ( mut vertices, mut normals, mut colors, mut indices ) = ( Vec::new(), Vec::new(), Vec::new(), Vec::with_capacity( triangle_count * 3 ) );
+        let mut split_vertices = HashMap::<( u32, [u32; 3] ), u32>::new();
+        for ( index, &vertex ) in self.indices[..triangle_count * 3].iter().enumerate() {
+            let face_normal = face_normals[index / 3];
+            let mut normal = match generation {
+                NormalGeneration::Flat => face_normal,
+                NormalGeneration::Smooth => vertex_faces[vertex as usize].iter()
+                    .filter( |&&other| face_normals[other / 3].dot( &face_normal ) >= crease_cos )
+                    .fold( glm::Vec3::zeros(), |sum, &other| sum + face_normals[other / 3] * corner_angles[other] ),
+            };
+            normal = if normal.norm() > f32::EPSILON { normal.normalize() } else if face_normal != glm::Vec3::zeros() { face_normal } else { glm::vec3( 0.0, 1.0, 0.0 ) };
+
+            let key = ( vertex, [ normal.x.to_bits(), normal.y.to_bits(), normal.z.to_bits() ] );
+            let new_vertex = *split_vertices.entry( key ).or_insert_with( || {
+                let vertex = vertex as usize;
+                vertices.extend_from_slice( &self.vertices[vertex * 3..vertex * 3 + 3] );
+                normals.extend_from_slice( &[ normal.x, normal.y, normal.z ] );
+                if let Some( color ) = self.colors.get( vertex * 4..vertex * 4 + 4 ) {
+                    colors.extend_from_slice( color );
+                }
+                ( vertices.len() / 3 - 1 ) as u32
+            } );
+            indices.push( new_vertex );
+        }
+
+        ( self.vertices, self.normals, self.colors, self.indices ) = ( vertices, normals, colors, indices );
+        self.index_count = self.indices.len() as i32;
+    }
+
+    /**
+     * Transforms the vertices and normals of the mesh.
+     * If the matrix mirrors the mesh, the winding of its triangles is reversed, so they keep facing outwards.
+     *
+     * @param matrix The transformation matrix.
+     */
+    pub fn transform( &mut self, matrix: &glm::Mat4 ) {
+        // Normals are transformed by the inverse transpose to stay perpendicular under non-uniform scaling
+        let normal_matrix = glm::transpose( &glm::inverse( &glm::mat4_to_mat3( matrix ) ) );
+
+        for vertex in self.vertices.chunks_exact_mut( 3 ) {
+            let position = ( matrix * glm::vec4( vertex[0], vertex[1], vertex[2], 1.0 ) ).xyz();
+            vertex.copy_from_slice( position.as_slice() );
+        }
+        for normal in self.normals.chunks_exact_mut( 3 ) {
+            let transformed = normal_matrix * glm::vec3( normal[0], normal[1], normal[2] );
+            let transformed = if transformed.norm() > f32::EPSILON { transformed.normalize() } else { transformed };
+            normal.copy_from_slice( transformed.as_slice() );
+        }
+
+        if glm::determinant( &glm::mat4_to_mat3( matrix ) ) < 0.0 {
+            for triangle in self.indices.chunks_exact_mut( 3 ) {
+                triangle.swap( 1, 2 );
+            }
+        }
+    }
+
+    /**
+     * Calculates the bounding box of the mesh's vertices.
+     *
+     * @return The bottom left and top right corners of the box, or None if the mesh has no vertices.
+     */
+    pub fn bounding_box( &self ) -> Option<( glm::Vec3, glm::Vec3 )> {
+        self.vertices.chunks_exact( 3 )
+            .map( |vertex| glm::vec3( vertex[0], vertex[1], vertex[2] ) )
+            .fold( None, |bounds, point| match bounds {
+                None => Some( ( point, point ) ),
+                Some( ( min, max ) ) => Some( ( glm::min2( &min, &point ), glm::max2( &max, &point ) ) ),
+            } )
+    }
+}
+
+// Weight of the planes which keep the boundary of an open mesh in place when decimating, relative to the faces' planes
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/**
+ * Type for an error quadric, the symmetric 4x4 matrix of the sum of squared distances to a set of planes.
+ * Only the upper triangle is stored: [aa, ab, ac, ad, bb, bc, bd, cc, cd, dd].
+ */
+type Quadric = [f64; 10];
+
+/**
+ * Creates the quadric of a plane (ax + by + cz + d = 0).
+ *
+ * @param normal The normal of the plane (a, b, c), normalized.
+ * @param point A point on the plane.
+ * @param weight The weight of the plane.
+ *
+ * @return The quadric.
+ */
+fn plane_quadric( normal: &glm::DVec3, point: &glm::DVec3, weight: f64 ) -> Quadric {
+    let ( a, b, c, d ) = ( normal.x, normal.y, normal.z, -normal.dot( point ) );
+    [a*a, a*b, a*c, a*d, b*b, b*c, b*d, c*c, c*d, d*d].map( |value| value * weight )
+}
+
+/**
+ * Adds two quadrics.
+ */
+fn add_quadrics( a: &Quadric, b: &Quadric ) -> Quadric {
+    std::array::from_fn( |i| a[i] + b[i] )
+}
+
+/**
+ * Calculates the error of a point: its weighted sum of squared distances to the planes of a quadric.
+ */
+fn quadric_error( q: &Quadric, p: &glm::DVec3 ) -> f64 {
+    let ( x, y, z ) = ( p.x, p.y, p.z );
+    q[0]*x*x + 2.0*q[1]*x*y + 2.0*q[2]*x*z + 2.0*q[3]*x + q[4]*y*y + 2.0*q[5]*y*z + 2.0*q[6]*y + q[7]*z*z + 2.0*q[8]*z + q[9]
+}
+
+/**
+ * Finds the point with the smallest error of a quadric.
+ *
+ * @param q The quadric.
+ * @return The point, or None if it is not unique (e.g. when all planes are parallel).
+ */
+fn optimal_point( q: &Quadric ) -> Option<glm::DVec3> {
+    let matrix = glm::DMat3::new(
+        q[0], q[1], q[2],
+        q[1], q[4], q[5],
+        q[2], q[5], q[7],
+    );
+    if matrix.determinant().abs() < 1e-12 {
+        return None
+    }
+    matrix.try_inverse().map( |inverse| -( inverse * glm::DVec3::new( q[3], q[6], q[8] ) ) )
+}
+
+/**
+ * Mesh processing functions, for preparing imported assets (see ImportOptions).
+ * Normals and colors are carried along with the vertices when the mesh has them.
+ */
+impl Mesh {
+    /**
+     * Gets the position of a vertex.
+     */
+    fn position( &self, vertex: usize ) -> glm::Vec3 {
+        glm::vec3( self.vertices[vertex * 3], self.vertices[vertex * 3 + 1], self.vertices[vertex * 3 + 2] )
+    }
+
+    /**
+     * Checks if the mesh has a normal and a color for every vertex.
+     *
+     * @return Whether the mesh has normals, and whether it has colors.
+     */
+    fn has_attributes( &self ) -> ( bool, bool ) {
+        let vertex_count = self.vertices.len() / 3;
+        ( self.normals.len() == vertex_count * 3, self.colors.len() == vertex_count * 4 )
+    }
+
+    /**
+     * Welds vertices which are within a distance of each other into one, like the duplicated vertices of STL files.
+     * The welded vertex keeps the position of the first vertex, and averages their normals and colors.
+     * Triangles which become degenerate (with two corners at the same vertex) are removed.
+     *
+     * @param tolerance The largest distance between two vertices which are welded. At 0, only identical vertices are.
+     * @return The amount of vertices removed.
+     */
+    pub fn weld( &mut self, tolerance: f32 ) -> usize {
+        let ( vertex_count, ( has_normals, has_colors ) ) = ( self.vertices.len() / 3, self.has_attributes() );
+
+        // Vertices are bucketed in a grid of the tolerance, so only the neighbouring cells need to be searched
+        let cell_size = if tolerance > 0.0 { tolerance } else { 1.0 };
+        let cell_of = |point: &glm::Vec3| [ point.x, point.y, point.z ].map( |value| ( value / cell_size ).floor() as i64 );
+        let mut grid = HashMap::<[i64; 3], Vec<u32>>::new();
+
+        let ( mut vertices, mut normals, mut colors, mut remap ) = ( Vec::new(), Vec::new(), Vec::new(), Vec::with_capacity( vertex_count ) );
+        let mut weld_counts = Vec::<f32>::new();
+        for vertex in 0..vertex_count {
+            let point = self.position( vertex );
+            let cell = cell_of( &point );
+            let welded = ( -1..=1 ).flat_map( |x| ( -1..=1 ).flat_map( move |y| ( -1..=1 ).map( move |z| [ cell[0] + x, cell[1] + y, cell[2] + z ] ) ) )
+                .filter_map( |neighbour| grid.get( &neighbour ) )
+                .flatten()
+                .copied()
+                .find( |&welded| ( glm::vec3( vertices[welded as usize * 3], vertices[welded as usize * 3 + 1], vertices[welded as usize * 3 + 2] ) - point ).norm() <= tolerance );
+
+            let welded = welded.unwrap_or_else( || {
+                let welded = weld_counts.len() as u32;
+                vertices.extend_from_slice( point.as_slice() );
+                normals.extend_from_slice( &[ 0.0; 3 ] );
+                colors.extend_from_slice( &[ 0.0; 4 ] );
+                weld_counts.push( 0.0 );
+                grid.entry( cell ).or_default().push( welded );
+                welded
+            } );
+
+            // Sum the attributes, to be averaged once every vertex has been welded
+            let index = welded as usize;
+            weld_counts[index] += 1.0;
+            if has_normals {
+                ( 0..3 ).for_each( |i| normals[index * 3 + i] += self.normals[vertex * 3 + i] );
+            }
+            if has_colors {
+                ( 0..4 ).for_each( |i| colors[index * 4 + i] += self.colors[vertex * 4 + i] );
+            }
+            remap.push( welded );
+        }
+
+        // Average the attributes
+        for ( index, count ) in weld_counts.iter().enumerate() {
+            let normal = glm::vec3( normals[index * 3], normals[index * 3 + 1], normals[index * 3 + 2] );
+            if normal.norm() > f32::EPSILON {
+                normals[index * 3..index * 3 + 3].copy_from_slice( normal.normalize().as_slice() );
+            }
+            colors[index * 4..index * 4 + 4].iter_mut().for_each( |channel| *channel /= count );
+        }
+
+        // Remap the triangles, dropping the degenerate ones
+        let mut indices = Vec::with_capacity( self.indices.len() );
+        for triangle in self.indices[..self.index_count as usize / 3 * 3].chunks( 3 ) {
+            let [ a, b, c ] = [ 0, 1, 2 ].map( |corner| remap[triangle[corner] as usize] );
+            if a != b && b != c && c != a {
+                indices.extend( [ a, b, c ] );
+            }
+        }
+
+        let removed = vertex_count - weld_counts.len();
+        self.vertices = vertices;
+        self.normals = if has_normals { normals } else { Vec::new() };
+        self.colors = if has_colors { colors } else { Vec::new() };
+        self.index_count = indices.len() as i32;
+        self.indices = indices;
+        removed
+    }
+
+    /**
+     * Simplifies the mesh by collapsing edges until it has a target amount of triangles, using quadric error metrics
+     * (Garland & Heckbert, "Surface Simplification Using Quadric Error Metrics", 1997).
+     * Each vertex keeps the (area weighted) planes of its original faces, and the edge whose collapsed vertex is the closest
+     * to its planes is collapsed first. Open boundaries are kept in place by planes perpendicular to them, and collapses
+     * which would flip a face or make the mesh non-manifold are skipped, so the target may not be reached.
+     * Vertices should be welded first, as the edges of split vertices are treated as boundaries.
+     *
+     * @param target_triangles The amount of triangles to simplify the mesh to.
+     */
+    pub fn decimate( &mut self, target_triangles: usize ) {
+        let ( vertex_count, ( has_normals, has_colors ) ) = ( self.vertices.len() / 3, self.has_attributes() );
+        let mut positions: Vec<glm::DVec3> = ( 0..vertex_count ).map( |vertex| glm::convert( self.position( vertex ) ) ).collect();
+        let mut faces: Vec<[u32; 3]> = self.indices[..self.index_count as usize / 3 * 3].chunks( 3 ).map( |triangle| [ triangle[0], triangle[1], triangle[2] ] ).collect();
+        let mut face_count = faces.len();
+        if face_count <= target_triangles {
+            return;
+        }
+
+        // Find the faces around each vertex, and the faces of each edge
+        let mut vertex_faces = vec![ Vec::<usize>::new(); vertex_count ];
+        let mut edge_faces = HashMap::<( u32, u32 ), Vec<usize>>::new();
+        for ( face, triangle ) in faces.iter().enumerate() {
+            for corner in 0..3 {
+                let ( a, b ) = ( triangle[corner], triangle[( corner + 1 ) % 3] );
+                vertex_faces[a as usize].push( face );
+                edge_faces.entry( ( a.min( b ), a.max( b ) ) ).or_default().push( face );
+            }
+        }
+        let face_normal = |positions: &[glm::DVec3], triangle: &[u32; 3]| {
+            let [ p0, p1, p2 ] = triangle.map( |vertex| positions[vertex as usize] );
+            ( p1 - p0 ).cross( &( p2 - p0 ) )
+        };
+
+        // Sum the quadrics of the faces' planes, and of the planes along boundary edges
+        let mut quadrics = vec![ [0.0; 10]; vertex_count ];
+        for triangle in &faces {
+            let normal = face_normal( &positions, triangle );
+            if normal.norm() > f64::EPSILON {
+                let quadric = plane_quadric( &normal.normalize(), &positions[triangle[0] as usize], normal.norm() * 0.5 );
+                triangle.iter().for_each( |&vertex| quadrics[vertex as usize] = add_quadrics( &quadrics[vertex as usize], &quadric ) );
+            }
+        }
+        for ( &( a, b ), edge_face ) in &edge_faces {
+            if edge_face.len() != 1 {
+                continue;
+            }
+            let edge = positions[b as usize] - positions[a as usize];
+            let boundary_normal = edge.cross( &face_normal( &positions, &faces[edge_face[0]] ) );
+            if boundary_normal.norm() > f64::EPSILON {
+                let quadric = plane_quadric( &boundary_normal.normalize(), &positions[a as usize], BOUNDARY_WEIGHT * edge.norm_squared() );
+                for vertex in [ a, b ] {
+                    quadrics[vertex as usize] = add_quadrics( &quadrics[vertex as usize], &quadric );
+                }
+            }
+        }
+
+        // Finds where an edge collapses to (the optimal point, or else the best of its ends and middle), and the error there
+        let evaluate = |quadrics: &[Quadric], positions: &[glm::DVec3], a: u32, b: u32| -> ( f64, glm::DVec3 ) {
+            let quadric = add_quadrics( &quadrics[a as usize], &quadrics[b as usize] );
+            let ( pa, pb ) = ( positions[a as usize], positions[b as usize] );
+            let candidates = optimal_point( &quadric ).map_or( vec![ pa, pb, ( pa + pb ) * 0.5 ], |point| vec![ point ] );
+            candidates.into_iter()
+                .map( |point| ( quadric_error( &quadric, &point ).max( 0.0 ), point ) )
+                .min_by( |( a, _ ), ( b, _ )| a.total_cmp( b ) )
+                .unwrap()
+        };
+
+        // Queue the edges by their error, cheapest first
+        // Entries are invalidated by changing the version of their vertices, instead of being removed from the queue
+        // (Non-negative floats order the same way as their bits)
+        let mut versions = vec![ 0u32; vertex_count ];
+        let mut queue = BinaryHeap::new();
+        for &( a, b ) in edge_faces.keys() {
+            let ( error, _ ) = evaluate( &quadrics, &positions, a, b );
+            queue.push( Reverse( ( error.to_bits(), a, b, 0u32, 0u32 ) ) );
+        }
+
+        let neighbours = |vertex_faces: &[Vec<usize>], faces: &[[u32; 3]], vertex: u32| -> Vec<u32> {
+            let mut neighbours: Vec<u32> = vertex_faces[vertex as usize].iter().flat_map( |&face| faces[face] ).filter( |&other| other != vertex ).collect();
+            neighbours.sort_unstable();
+            neighbours.dedup();
+            neighbours
+        };
+
+        let mut removed = vec![ false; vertex_count ];
+        while face_count > target_triangles {
+            let Some( Reverse( ( _, a, b, version_a, version_b ) ) ) = queue.pop() else { break };
+            if removed[a as usize] || removed[b as usize] || versions[a as usize] != version_a || versions[b as usize] != version_b {
+                continue;
+            }
+            let ( _, point ) = evaluate( &quadrics, &positions, a, b );
+
+            // Check the link condition: the ends may only share the neighbours across the edge's faces, or the mesh would pinch
+            let shared_faces: Vec<usize> = vertex_faces[a as usize].iter().copied().filter( |&face| faces[face].contains( &b ) ).collect();
+            let ( neighbours_a, neighbours_b ) = ( neighbours( &vertex_faces, &faces, a ), neighbours( &vertex_faces, &faces, b ) );
+            let shared_neighbours = neighbours_a.iter().filter( |vertex| neighbours_b.contains( vertex ) ).count();
+            if shared_faces.is_empty() || shared_neighbours != shared_faces.len() {
+                continue;
+            }
+
+            // Check that no remaining face around the edge flips
+            let flips = vertex_faces[a as usize].iter().chain( &vertex_faces[b as usize] )
+                .filter( |face| !shared_faces.contains( face ) )
+                .any( |&face| {
+                    let [ p0, p1, p2 ] = faces[face].map( |vertex| if vertex == a || vertex == b { point } else { positions[vertex as usize] } );
+                    ( p1 - p0 ).cross( &( p2 - p0 ) ).dot( &face_normal( &positions, &faces[face] ) ) <= 0.0
+                } );
+            if flips {
+                continue;
+            }
+
+            // Collapse b into a, interpolating the attributes by where the point lies along the edge
+            let ( pa, pb ) = ( positions[a as usize], positions[b as usize] );
+            let t = if ( pb - pa ).norm_squared() > 0.0 { ( ( point - pa ).dot( &( pb - pa ) ) / ( pb - pa ).norm_squared() ).clamp( 0.0, 1.0 ) as f32 } else { 0.0 };
+            let ( a_index, b_index ) = ( a as usize, b as usize );
+            if has_normals {
+                let normal = glm::lerp( &glm::make_vec3( &self.normals[a_index * 3..a_index * 3 + 3] ), &glm::make_vec3( &self.normals[b_index * 3..b_index * 3 + 3] ), t );
+                if normal.norm() > f32::EPSILON {
+                    self.normals[a_index * 3..a_index * 3 + 3].copy_from_slice( normal.normalize().as_slice() );
+                }
+            }
+            if has_colors {
+                for channel in 0..4 {
+                    self.colors[a_index * 4 + channel] += ( self.colors[b_index * 4 + channel] - self.colors[a_index * 4 + channel] ) * t;
+                }
+            }
+            positions[a_index] = point;
+            quadrics[a_index] = add_quadrics( &quadrics[a_index], &quadrics[b_index] );
+
+            for face in std::mem::take( &mut vertex_faces[b_index] ) {
+                if shared_faces.contains( &face ) {
+                    // Remove the faces of the edge from their third vertex
+                    for vertex in faces[face] {
+                        vertex_faces[vertex as usize].retain( |&other| other != face );
+                    }
+                    face_count -= 1;
+                } else {
+                    faces[face].iter_mut().filter( |vertex| **vertex == b ).for_each( |vertex| *vertex = a );
+                    vertex_faces[a_index].push( face );
+                }
+            }
+            removed[b_index] = true;
+            versions[a_index] += 1;
+
+            // Queue the edges of the collapsed vertex again with their new errors
+            for neighbour in neighbours( &vertex_faces, &faces, a ) {
+                let ( error, _ ) = evaluate( &quadrics, &positions, a, neighbour );
+                queue.push( Reverse( ( error.to_bits(), a, neighbour, versions[a_index], versions[neighbour as usize] ) ) );
+            }
+        }
+
+        // Rebuild the mesh from the remaining faces, dropping unused vertices
+        let mut face_alive = vec![ false; faces.len() ];
+        vertex_faces.iter().flatten().for_each( |&face| face_alive[face] = true );
+        let mut remap = vec![ u32::MAX; vertex_count ];
+        let ( mut vertices, mut normals, mut colors, mut indices ) = ( Vec::new(), Vec::new(), Vec::new(), Vec::new() );
+        for ( _, triangle ) in faces.iter().enumerate().filter( |( face, _ )| face_alive[*face] ) {
+            for &vertex in triangle {
+                let vertex = vertex as usize;
+                if remap[vertex] == u32::MAX {
+                    remap[vertex] = ( vertices.len() / 3 ) as u32;
+                    let position: glm::Vec3 = glm::convert( positions[vertex] );
+                    vertices.extend_from_slice( position.as_slice() );
+                    if has_normals {
+                        normals.extend_from_slice( &self.normals[vertex * 3..vertex * 3 + 3] );
+                    }
+                    if has_colors {
+                        colors.extend_from_slice( &self.colors[vertex * 4..vertex * 4 + 4] );
+                    }
+                }
+                indices.push( remap[vertex] );
+            }
+        }
+
+        ( self.vertices, self.normals, self.colors ) = ( vertices, normals, colors );
+        self.index_count = indices.len() as i32;
+        self.indices = indices;
+    }
+
+    /**
+     * Subdivides every triangle of the mesh into four, using Loop subdivision (Loop, "Smooth Subdivision Surfaces Based on Triangles", 1987).
+     * New vertices are added at the middle of each edge, and both old and new vertices are moved by averaging their neighbours,
+     * with Warren's weights for the old ones. Boundary edges (and split vertices' edges) only use their boundary neighbours,
+     * so the boundary stays a curve and split vertices do not crack apart. Normals and colors are interpolated.
+     */
+    pub fn subdivide_loop( &mut self ) {
+        let ( vertex_count, ( has_normals, has_colors ) ) = ( self.vertices.len() / 3, self.has_attributes() );
+        let triangles: Vec<[u32; 3]> = self.indices[..self.index_count as usize / 3 * 3].chunks( 3 ).map( |triangle| [ triangle[0], triangle[1], triangle[2] ] ).collect();
+
+        // Find the vertices opposite of each edge, and create the edge's new vertex in the order the edges are first found
+        let mut edge_opposites = HashMap::<( u32, u32 ), Vec<u32>>::new();
+        let mut edge_vertices = HashMap::<( u32, u32 ), u32>::new();
+        let mut edges = Vec::<( u32, u32 )>::new();
+        for triangle in &triangles {
+            for corner in 0..3 {
+                let ( a, b, opposite ) = ( triangle[corner], triangle[( corner + 1 ) % 3], triangle[( corner + 2 ) % 3] );
+                let edge = ( a.min( b ), a.max( b ) );
+                edge_opposites.entry( edge ).or_default().push( opposite );
+                edge_vertices.entry( edge ).or_insert_with( || {
+                    edges.push( edge );
+                    ( vertex_count + edges.len() - 1 ) as u32
+                } );
+            }
+        }
+
+        // Find the neighbours of each vertex, and its neighbours along boundaries
+        let ( mut neighbours, mut boundary_neighbours ) = ( vec![ Vec::<u32>::new(); vertex_count ], vec![ Vec::<u32>::new(); vertex_count ] );
+        for &( a, b ) in &edges {
+            neighbours[a as usize].push( b );
+            neighbours[b as usize].push( a );
+            if edge_opposites[&( a, b )].len() != 2 {
+                boundary_neighbours[a as usize].push( b );
+                boundary_neighbours[b as usize].push( a );
+            }
+        }
+
+        // Move the old vertices
+        let ( mut vertices, mut normals, mut colors ) = ( Vec::new(), Vec::new(), Vec::new() );
+        for vertex in 0..vertex_count {
+            let ( point, count ) = ( self.position( vertex ), neighbours[vertex].len() );
+            let sum = |vertices: &[u32]| vertices.iter().fold( glm::Vec3::zeros(), |sum, &other| sum + self.position( other as usize ) );
+            let moved = match boundary_neighbours[vertex].len() {
+                0 if count >= 3 => {
+                    let beta = if count == 3 { 3.0 / 16.0 } else { 3.0 / ( 8.0 * count as f32 ) };
+                    point * ( 1.0 - count as f32 * beta ) + sum( &neighbours[vertex] ) * beta
+                },
+                2 => point * 0.75 + sum( &boundary_neighbours[vertex] ) * 0.125,
+                // (Corners, where more than two boundaries meet, and unused vertices stay in place)
+                _ => point,
+            };
+            vertices.extend_from_slice( moved.as_slice() );
+        }
+        if has_normals {
+            normals.extend_from_slice( &self.normals );
+        }
+        if has_colors {
+            colors.extend_from_slice( &self.colors );
+        }
+
+        // Add the new vertices of the edges
+        for edge in &edges {
+            let ( a, b ) = ( edge.0 as usize, edge.1 as usize );
+            let middle = match edge_opposites[edge][..] {
+                [ c, d ] => ( self.position( a ) + self.position( b ) ) * 0.375 + ( self.position( c as usize ) + self.position( d as usize ) ) * 0.125,
+                _ => ( self.position( a ) + self.position( b ) ) * 0.5,
+            };
+            vertices.extend_from_slice( middle.as_slice() );
+            if has_normals {
+                let normal = glm::make_vec3( &self.normals[a * 3..a * 3 + 3] ) + glm::make_vec3( &self.normals[b * 3..b * 3 + 3] );
+                let normal = if normal.norm() > f32::EPSILON { normal.normalize() } else { glm::make_vec3( &self.normals[a * 3..a * 3 + 3] ) };
+                normals.extend_from_slice( normal.as_slice() );
+            }
+            if has_colors {
+                colors.extend( ( 0..4 ).map( |channel| ( self.colors[a * 4 + channel] + self.colors[b * 4 + channel] ) * 0.5 ) );
+            }
+        }
+
+        // Split each triangle into four, keeping their winding
+        let mut indices = Vec::with_capacity( triangles.len() * 12 );
+        for &[ a, b, c ] in &triangles {
+            let edge_vertex = |x: u32, y: u32| edge_vertices[&( x.min( y ), x.max( y ) )];
+            let ( ab, bc, ca ) = ( edge_vertex( a, b ), edge_vertex( b, c ), edge_vertex( c, a ) );
+            indices.extend( [ a, ab, ca, ab, b, bc, ca, bc, c, ab, bc, ca ] );
+        }
+
+        ( self.vertices, self.normals, self.colors ) = ( vertices, normals, colors );
+        self.index_count = indices.len() as i32;
+        self.indices = indices;
+    }
+}
+
 /**
  * Struct for holding a model.
  */
@@ -30,11 +671,54 @@ impl Model {
     }
 
     /**
-     * Loads a .obj file into the model.
+     * Loads a model file into the model, choosing the loader by the file's extension (.obj, .ply or .stl).
+     * The parts are welded, decimated and subdivided as the import options ask, normals are generated for the parts without normals,
+     * and the model is transformed by the import options.
      * 
+     * @param path The path for the model file.
+     * @param options The import options.
+     */
+    pub fn load_from_file( mut self, path: &str, options: ImportOptions ) -> Model {
+        let extension = std::path::Path::new( path ).extension().and_then( |extension| extension.to_str() ).map( str::to_lowercase );
+        let meshes = match extension.as_deref() {
+            Some( "obj" ) => Model::load_obj( path ),
+            Some( "ply" ) => ply::load( path ).map( |mesh| vec![ mesh ] ),
+            Some( "stl" ) => stl::load( path ),
+            _ => Err( format!( "Unsupported model format '{path}'" ) ),
+        }.unwrap_or_else( |err| panic!( "ERROR::MESH::FAILED_TO_LOAD_MODEL\n{}", err ) );
+
+        for mut mesh in meshes {
+            // Process the mesh before generating normals, so they match its final shape
+            if let Some( epsilon ) = options.weld_epsilon {
+                mesh.weld( epsilon );
+                // (Welding averages the normals across hard edges, so they are generated again)
+                mesh.normals.clear();
+            }
+            if options.decimate_ratio < 1.0 {
+                let triangle_count = mesh.index_count as usize / 3;
+                mesh.decimate( ( triangle_count as f32 * options.decimate_ratio.max( 0.0 ) ).round() as usize );
+            }
+            for _ in 0..options.subdivisions {
+                mesh.subdivide_loop();
+            }
+
+            if mesh.normals.len() != mesh.vertices.len() {
+                mesh.generate_normals( options.normal_generation, options.crease_angle );
+            }
+            self.meshes.push( mesh );
+        }
+
+        self.apply_import_options( &options );
+        self
+    }
+
+    /**
+     * Loads the parts of a .obj file as meshes.
+     *
      * @param path The path for the .obj file.
+     * @return The meshes, or an error message if the file could not be loaded.
      */
-    pub fn load_from_file( mut self, path: &str ) -> Model {
+    fn load_obj( path: &str ) -> Result<Vec<Mesh>, String> {
         let (parts, _materials)
         = tobj::load_obj(path,
             &tobj::LoadOptions{
@@ -42,46 +726,102 @@ impl Model {
                 single_index: true,
                 ..Default::default()
             }
-        ).expect("Failed to load model");
+        ).map_err( |err| format!( "Failed to load OBJ file '{path}': {err}" ) )?;
 
+        let mut meshes = Vec::new();
         for part in parts {
             let ( positions, indices ) = ( part.mesh.positions, part.mesh.indices );
-            let ( positions_len, indices_len ) = ( positions.len(), indices.len() );
-            self.meshes.push( 
+            let indices_len = indices.len();
+
+            // (Vertex colors are RGB in OBJ files)
+            let colors = if part.mesh.vertex_color.len() == positions.len() {
+                part.mesh.vertex_color.chunks( 3 ).flat_map( |color| [ color[0], color[1], color[2], 1.0 ] ).collect()
+            } else {
+                DEFAULT_COLOR.iter().cloned().cycle().take( positions.len()/3*4 ).collect()
+            };
+            meshes.push(
                 Mesh {
                     vertices: positions,
                     normals: part.mesh.normals,
-                    indices: indices,
-                    colors: [1.0, 0.0, 0.0, 1.0].iter().cloned().cycle().take(positions_len*4).collect(),
+                    indices,
+                    colors,
                     index_count: indices_len as i32,
                 }
             );
         }
 
-        self
+        Ok( meshes )
+    }
+
+    /**
+     * Transforms the model as described by the import options.
+     *
+     * @param options The import options.
+     */
+    fn apply_import_options( &mut self, options: &ImportOptions ) {
+        let conversion = options.conversion_matrix();
+        self.meshes.iter_mut().for_each( |mesh| mesh.transform( &conversion ) );
+
+        // Center and fit the model by its bounding box, after it has been converted
+        let mut fit = glm::Mat4::identity();
+        if let Some( ( min, max ) ) = self.bounding_box() {
+            if options.center {
+                fit = glm::translation( &( -( min + max ) * 0.5 ) );
+            }
+            let longest_side = ( max - min ).max();
+            if options.fit_to_unit_box && longest_side > f32::EPSILON {
+                fit = glm::scaling( &glm::vec3( 1.0, 1.0, 1.0 ).scale( 1.0 / longest_side ) ) * fit;
+            }
+        }
+
+        let matrix = options.transform.matrix() * fit;
+        self.meshes.iter_mut().for_each( |mesh| mesh.transform( &matrix ) );
+    }
+
+    /**
+     * Calculates the bounding box of all meshes in the model.
+     *
+     * @return The bottom left and top right corners of the box, or None if the model has no vertices.
+     */
+    pub fn bounding_box( &self ) -> Option<( glm::Vec3, glm::Vec3 )> {
+        self.meshes.iter()
+            .filter_map( Mesh::bounding_box )
+            .reduce( |( min, max ), ( mesh_min, mesh_max )| ( glm::min2( &min, &mesh_min ), glm::max2( &max, &mesh_max ) ) )
     }
 
     /**
      * Generates the necessary raytracing structs to render the model.
-     * Each part of the model becomes its own mesh, and triangles are dumped into a global triangle vector.
+     * Each part of the model becomes its own mesh. Vertices are dumped into global position and normal vectors,
+     * and triangles into a global triangle vector, referring to their vertices by index.
+     * Parts without triangles are skipped, and parts without normals (see Mesh::generate_normals()) get zero normals, so the normals stay aligned with the positions.
+     * Likewise, parts without vertex colors get DEFAULT_COLOR, which leaves the material's color as it is.
      * 
-     * @return Two vectors containing raytracing triangles and meshes, respectively.
+     * @param material_id The id of the material used by all triangles, in the MaterialLibrary.
+     *
+     * @return Vectors containing the vertex positions, vertex normals, vertex colors, raytracing triangles and meshes, respectively.
      */
-    pub fn generate_raytracing_structs( self ) -> ( Vec<RTTriangle>, Vec<RTMeshInfo> ) {
+    pub fn generate_raytracing_structs( self, material_id: u32 ) -> RaytracingStructs {
         // Set up buffers and counters
-        let ( mut triangles, mut meshes, mut start_index ) = (
+        let ( mut positions, mut normals, mut colors, mut triangles, mut meshes ) = (
+            Vec::<glm::Vec3>::new(),
+            Vec::<glm::Vec3>::new(),
+            Vec::<glm::Vec4>::new(),
             Vec::<RTTriangle>::new(),
             Vec::<RTMeshInfo>::new(),
-            0,
         );
 
         // Iterate parts, adding each as its own mesh in `meshes`
         for part in self.meshes {
-            // Set up buffers required for each individual mesh
-            let ( mut vertices_vec3, mut boundingbox_min, mut boundingbox_max ) = (
-                Vec::<glm::Vec3>::new(),
-                glm::Vec3::zeros(),
-                glm::Vec3::zeros(),
+            if part.index_count < 3 {
+                continue;
+            }
+
+            // Set up the bounding box, and the indices of the part's first vertex and triangle in the global vectors
+            let ( vertex_offset, start_index, mut boundingbox_min, mut boundingbox_max ) = (
+                positions.len() as u32,
+                triangles.len() as u32,
+                glm::vec3( f32::MAX, f32::MAX, f32::MAX ),
+                glm::vec3( f32::MIN, f32::MIN, f32::MIN ),
             );
 
             // Iterate vertices of part, creating glm::vec3 for each and noting down the min/max point
@@ -90,66 +830,578 @@ impl Model {
                     part.vertices[i*3],
                     part.vertices[i*3+1],
                     part.vertices[i*3+2]
-                ) / 80.0 + glm::vec3(-1.0, 1.0, 3.0);
+                );
 
-                vertices_vec3.push( vec );
-                if i == 0 {
-                    boundingbox_min = vec; boundingbox_max = vec;
-                }
+                positions.push( vec );
                 boundingbox_min = glm::min2( &vec, &boundingbox_min );
                 boundingbox_max = glm::max2( &vec, &boundingbox_max );
             }
 
             // Iterate normals, creating glm::vec3 for each
-            let mut normals_vec3 = Vec::<glm::Vec3>::new();
             for i in 0..part.normals.len()/3 {
-                normals_vec3.push( glm::vec3(part.normals[i*3], part.normals[i*3+1], part.normals[i*3+2]) );
+                normals.push( glm::vec3(part.normals[i*3], part.normals[i*3+1], part.normals[i*3+2]) );
             }
+            normals.resize( positions.len(), glm::Vec3::zeros() );
 
             // Iterate colors, creating glm::vec4 for each
-            let mut colors_vec4 = Vec::<glm::Vec4>::new();
             for i in 0..part.colors.len()/4 {
-                colors_vec4.push( glm::vec4(part.colors[i*4], part.colors[i*4+1], part.colors[i*4+2], part.colors[i*4+3]) );
+                colors.push( glm::vec4(part.colors[i*4], part.colors[i*4+1], part.colors[i*4+2], part.colors[i*4+3]) );
             }
+            colors.resize( positions.len(), glm::Vec4::from( DEFAULT_COLOR ) );
 
             // Iterate triangles of part, creating raytracing triangles and adding them to `triangles` vector
             for i in 0..part.index_count/3 {
-                //if i > 10 { break }
-                let ( i0, i1, i2 ) = (
-                    part.indices[(i*3) as usize],
-                    part.indices[(i*3+1) as usize],
-                    part.indices[(i*3+2) as usize],
-                );
                 let triangle = RTTriangle {
-                    p0: vertices_vec3[i0 as usize].into(),
-                    p1: vertices_vec3[i1 as usize].into(),
-                    p2: vertices_vec3[i2 as usize].into(),
-                    normal0: normals_vec3[i0 as usize].into(),
-                    normal1: normals_vec3[i1 as usize].into(),
-                    normal2: normals_vec3[i2 as usize].into(),
-                    material: RTMaterial {
-                        color: colors_vec4[i0 as usize],
-                        emission_color: glm::vec4(colors_vec4[i0 as usize].x, colors_vec4[i0 as usize].y, colors_vec4[i0 as usize].z, 0.5),
-                        specular_color: glm::Vec4::zeros(),
-                        smoothness: 0.5,
-                    }
+                    vertices: [
+                        vertex_offset + part.indices[(i*3) as usize],
+                        vertex_offset + part.indices[(i*3+1) as usize],
+                        vertex_offset + part.indices[(i*3+2) as usize],
+                    ],
+                    material_id,
                 };
                 triangles.push( triangle );
             }
 
             // Create and push raytracing mesh to `meshes`
             meshes.push( RTMeshInfo {
-                start_index: start_index,
+                start_index,
                 count: triangles.len() as u32 - start_index,
+                object_id: 0,
+                motion_index: NO_MOTION,
                 boundingbox_min: boundingbox_min.into(),
                 boundingbox_max: boundingbox_max.into(),
             } );
+        }
+
+        // Return vertices, triangles and meshes
+        ( positions, normals, colors, triangles, meshes )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::{Geometry, Scene};
 
-            // Set start index for next part
-            start_index = triangles.len() as u32 - 1;
+    /**
+     * Creates a mesh from positions (without normals) and triangle indices.
+     */
+    fn mesh( vertices: &[f32], indices: &[u32] ) -> Mesh {
+        Mesh {
+            vertices: vertices.to_vec(),
+            normals: Vec::new(),
+            colors: Vec::new(),
+            indices: indices.to_vec(),
+            index_count: indices.len() as i32,
         }
+    }
 
-        // Return triangles and meshes
-        ( triangles, meshes ) 
+    /**
+     * Asserts that two vectors are (almost) equal.
+     */
+    fn assert_vec3_eq( actual: glm::Vec3, expected: glm::Vec3 ) {
+        assert!( ( actual - expected ).norm() < 1e-5, "expected {expected:?}, got {actual:?}" );
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn loads_every_object_as_a_part() {
+        let model = Model::new().load_from_file( "resources/tests/two_objects.obj", ImportOptions::new() );
+        assert_eq!( model.meshes.len(), 2 );
+        assert_eq!( model.meshes[0].index_count, 6 );
+        assert_eq!( model.meshes[1].index_count, 3 );
+    }
+
+    #[test]
+    fn mesh_ranges_are_contiguous_and_do_not_overlap() {
+        let model = Model::new().load_from_file( "resources/tests/three_objects.obj", ImportOptions::new() );
+        let ( _, _, _, triangles, meshes ) = model.generate_raytracing_structs( 0 );
+
+        let ranges: Vec<( u32, u32 )> = meshes.iter().map( |mesh| ( mesh.start_index, mesh.count ) ).collect();
+        assert_eq!( ranges, vec![ ( 0, 1 ), ( 1, 2 ), ( 3, 3 ) ] );
+        assert_eq!( triangles.len(), 6 );
+    }
+
+    #[test]
+    fn triangles_index_the_vertices_of_their_own_part() {
+        let model = Model::new().load_from_file( "resources/tests/three_objects.obj", ImportOptions::new() );
+        let ( positions, normals, _, triangles, meshes ) = model.generate_raytracing_structs( 0 );
+        assert_eq!( positions.len(), 12 );
+        assert_eq!( normals.len(), positions.len() );
+
+        // The parts have 3, 4 and 5 vertices, stored one after another
+        let vertex_ranges = [ 0..3, 3..7, 7..12 ];
+        for ( mesh, vertex_range ) in meshes.iter().zip( vertex_ranges ) {
+            for triangle in &triangles[mesh.start_index as usize..( mesh.start_index + mesh.count ) as usize] {
+                for vertex in triangle.vertices {
+                    assert!( vertex_range.contains( &vertex ), "vertex {vertex} outside of {vertex_range:?}" );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bounding_boxes_enclose_their_part() {
+        let model = Model::new().load_from_file( "resources/tests/two_objects.obj", ImportOptions::new() );
+        let ( positions, _, _, triangles, meshes ) = model.generate_raytracing_structs( 0 );
+
+        assert_vec3_eq( meshes[0].boundingbox_min.into(), glm::vec3( 0.0, 0.0, 0.0 ) );
+        assert_vec3_eq( meshes[0].boundingbox_max.into(), glm::vec3( 80.0, 80.0, 0.0 ) );
+        assert_vec3_eq( meshes[1].boundingbox_min.into(), glm::vec3( -160.0, 0.0, -160.0 ) );
+        assert_vec3_eq( meshes[1].boundingbox_max.into(), glm::vec3( -80.0, 40.0, -80.0 ) );
+
+        for mesh in &meshes {
+            let ( min, max ): ( glm::Vec3, glm::Vec3 ) = ( mesh.boundingbox_min.into(), mesh.boundingbox_max.into() );
+            for triangle in &triangles[mesh.start_index as usize..( mesh.start_index + mesh.count ) as usize] {
+                for vertex in triangle.vertices {
+                    let position = positions[vertex as usize];
+                    assert!( position >= min && position <= max );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn bounding_boxes_do_not_include_the_origin() {
+        // All vertices are far from the origin, in both the fixture and after importing
+        let model = Model::new().load_from_file( "resources/tests/three_objects.obj", ImportOptions::new() );
+        let ( _, _, _, _, meshes ) = model.generate_raytracing_structs( 0 );
+
+        assert_vec3_eq( meshes[0].boundingbox_min.into(), glm::vec3( 160.0, 160.0, 160.0 ) );
+        assert_vec3_eq( meshes[1].boundingbox_min.into(), glm::vec3( 160.0, 160.0, 240.0 ) );
+        assert_vec3_eq( meshes[2].boundingbox_min.into(), glm::vec3( 320.0, 160.0, 160.0 ) );
+        assert_vec3_eq( meshes[2].boundingbox_max.into(), glm::vec3( 400.0, 320.0, 160.0 ) );
+    }
+
+    #[test]
+    fn empty_parts_are_skipped() {
+        let model = Model {
+            meshes: vec![
+                mesh( &[], &[] ),
+                mesh( &[ 0.0, 0.0, 0.0, 80.0, 0.0, 0.0, 0.0, 80.0, 0.0 ], &[ 0, 1, 2 ] ),
+                mesh( &[ 0.0, 0.0, 0.0 ], &[] ),
+                mesh( &[ 0.0, 0.0, 80.0, 80.0, 0.0, 80.0, 0.0, 80.0, 80.0 ], &[ 0, 1, 2 ] ),
+            ],
+        };
+        let ( positions, normals, _, triangles, meshes ) = model.generate_raytracing_structs( 0 );
+
+        let ranges: Vec<( u32, u32 )> = meshes.iter().map( |mesh| ( mesh.start_index, mesh.count ) ).collect();
+        assert_eq!( ranges, vec![ ( 0, 1 ), ( 1, 1 ) ] );
+        assert_eq!( ( positions.len(), normals.len() ), ( 6, 6 ) );
+        assert_eq!( triangles[1].vertices, [ 3, 4, 5 ] );
+    }
+
+    #[test]
+    fn empty_model_has_no_meshes() {
+        let ( positions, normals, _, triangles, meshes ) = Model::new().generate_raytracing_structs( 0 );
+        assert!( positions.is_empty() && normals.is_empty() && triangles.is_empty() && meshes.is_empty() );
+    }
+
+    #[test]
+    fn missing_normals_keep_the_vertices_aligned() {
+        let model = Model { meshes: vec![ mesh( &[ 0.0, 0.0, 0.0, 80.0, 0.0, 0.0, 0.0, 80.0, 0.0 ], &[ 0, 1, 2 ] ) ] };
+        let ( positions, normals, _, triangles, _ ) = model.generate_raytracing_structs( 0 );
+        assert_eq!( positions.len(), 3 );
+        assert_eq!( normals.len(), positions.len() );
+        assert_eq!( triangles.len(), 1 );
+    }
+
+    #[test]
+    fn missing_normals_are_generated_on_load() {
+        let model = Model::new().load_from_file( "resources/tests/no_normals.obj", ImportOptions::new() );
+        let part = &model.meshes[0];
+        assert_eq!( part.normals.len(), part.vertices.len() );
+        assert_eq!( part.colors.len(), part.vertices.len() / 3 * 4 );
+        for normal in part.normals.chunks( 3 ) {
+            assert_vec3_eq( glm::vec3( normal[0], normal[1], normal[2] ), glm::vec3( 0.0, 0.0, 1.0 ) );
+        }
+    }
+
+    #[test]
+    fn smooth_normals_are_split_at_creases() {
+        // The faces of a cube meet at 90 degrees, above the default crease angle
+        let model = Model::new().load_from_file( "resources/tests/cube.obj", ImportOptions::new() );
+        let part = &model.meshes[0];
+        assert_eq!( part.vertices.len() / 3, 24 );
+        assert_eq!( part.index_count, 36 );
+        for normal in part.normals.chunks( 3 ) {
+            let normal = glm::vec3( normal[0], normal[1], normal[2] );
+            assert!( ( normal.abs().max() - 1.0 ).abs() < 1e-5, "{normal:?} is not axis aligned" );
+        }
+    }
+
+    #[test]
+    fn smooth_normals_are_averaged_below_the_crease_angle() {
+        let options = ImportOptions { crease_angle: 100.0, ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/cube.obj", options );
+        let part = &model.meshes[0];
+        assert_eq!( part.vertices.len() / 3, 8 );
+
+        // Angle weighting makes every corner's normal point away from the center, whichever way the quads were split
+        for ( position, normal ) in part.vertices.chunks( 3 ).zip( part.normals.chunks( 3 ) ) {
+            let ( position, normal ) = ( glm::vec3( position[0], position[1], position[2] ), glm::vec3( normal[0], normal[1], normal[2] ) );
+            assert_vec3_eq( normal, position.normalize() );
+        }
+    }
+
+    #[test]
+    fn flat_normals_match_their_face() {
+        let options = ImportOptions { normal_generation: NormalGeneration::Flat, crease_angle: 180.0, ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/cube.obj", options );
+        let part = &model.meshes[0];
+        assert_eq!( part.vertices.len() / 3, 24 );
+
+        let position = |vertex: u32| glm::vec3( part.vertices[vertex as usize * 3], part.vertices[vertex as usize * 3 + 1], part.vertices[vertex as usize * 3 + 2] );
+        for triangle in part.indices.chunks( 3 ) {
+            let face_normal = ( position( triangle[1] ) - position( triangle[0] ) ).cross( &( position( triangle[2] ) - position( triangle[0] ) ) ).normalize();
+            for &vertex in triangle {
+                let normal = &part.normals[vertex as usize * 3..vertex as usize * 3 + 3];
+                assert_vec3_eq( glm::vec3( normal[0], normal[1], normal[2] ), face_normal );
+            }
+        }
+    }
+
+    #[test]
+    fn degenerate_triangles_get_a_unit_normal() {
+        let mut part = mesh( &[ 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 2.0, 0.0, 0.0 ], &[ 0, 1, 2 ] );
+        part.generate_normals( NormalGeneration::Smooth, 60.0 );
+        for normal in part.normals.chunks( 3 ) {
+            assert!( ( glm::vec3( normal[0], normal[1], normal[2] ).norm() - 1.0 ).abs() < 1e-5 );
+        }
+    }
+
+    #[test]
+    fn normals_are_copied_per_vertex() {
+        let model = Model::new().load_from_file( "resources/tests/two_objects.obj", ImportOptions::new() );
+        let ( _, normals, _, triangles, _ ) = model.generate_raytracing_structs( 0 );
+
+        for vertex in triangles[0].vertices {
+            assert_vec3_eq( normals[vertex as usize], glm::vec3( 0.0, 0.0, 1.0 ) );
+        }
+        for vertex in triangles[2].vertices {
+            assert_vec3_eq( normals[vertex as usize], glm::vec3( 0.0, 1.0, 0.0 ) );
+        }
+    }
+
+    #[test]
+    fn triangles_use_the_given_material() {
+        let model = Model::new().load_from_file( "resources/tests/two_objects.obj", ImportOptions::new() );
+        let ( _, _, _, triangles, _ ) = model.generate_raytracing_structs( 7 );
+        assert!( triangles.iter().all( |triangle| triangle.material_id == 7 ) );
+    }
+
+    /**
+     * Gets the vertex positions of a part.
+     */
+    fn positions( part: &Mesh ) -> Vec<glm::Vec3> {
+        part.vertices.chunks( 3 ).map( |vertex| glm::vec3( vertex[0], vertex[1], vertex[2] ) ).collect()
+    }
+
+    #[test]
+    fn default_import_keeps_the_file_coordinates() {
+        let model = Model::new().load_from_file( "resources/tests/no_normals.obj", ImportOptions::new() );
+        assert_eq!( positions( &model.meshes[0] ), vec![ glm::vec3( 0.0, 0.0, 0.0 ), glm::vec3( 80.0, 0.0, 0.0 ), glm::vec3( 0.0, 80.0, 0.0 ) ] );
+    }
+
+    #[test]
+    fn import_transform_scales_then_translates() {
+        let options = ImportOptions {
+            transform: Transform { scale: glm::vec3( 1.0, 1.0, 1.0 ) / 80.0, ..Transform::from_translation( glm::vec3( -1.0, 1.0, 3.0 ) ) },
+            ..ImportOptions::new()
+        };
+        let model = Model::new().load_from_file( "resources/tests/no_normals.obj", options );
+        let positions = positions( &model.meshes[0] );
+        assert_vec3_eq( positions[0], glm::vec3( -1.0, 1.0, 3.0 ) );
+        assert_vec3_eq( positions[1], glm::vec3( 0.0, 1.0, 3.0 ) );
+        assert_vec3_eq( positions[2], glm::vec3( -1.0, 2.0, 3.0 ) );
+    }
+
+    #[test]
+    fn import_rotation_rotates_normals() {
+        let options = ImportOptions {
+            transform: Transform { rotation: glm::vec3( 0.0, std::f32::consts::FRAC_PI_2, 0.0 ), ..Transform::new() },
+            ..ImportOptions::new()
+        };
+        let model = Model::new().load_from_file( "resources/tests/no_normals.obj", options );
+        let part = &model.meshes[0];
+        assert_vec3_eq( positions( part )[1], glm::vec3( 0.0, 0.0, -80.0 ) );
+        assert_vec3_eq( glm::vec3( part.normals[0], part.normals[1], part.normals[2] ), glm::vec3( 1.0, 0.0, 0.0 ) );
+    }
+
+    #[test]
+    fn z_up_is_converted_to_y_up() {
+        let options = ImportOptions { up_axis: UpAxis::Z, ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/no_normals.obj", options );
+        let part = &model.meshes[0];
+
+        // The triangle lies in the XY plane facing +Z (up), so it ends up in the XZ plane facing +Y
+        let positions = positions( part );
+        assert_vec3_eq( positions[1], glm::vec3( 80.0, 0.0, 0.0 ) );
+        assert_vec3_eq( positions[2], glm::vec3( 0.0, 0.0, -80.0 ) );
+        assert_vec3_eq( glm::vec3( part.normals[0], part.normals[1], part.normals[2] ), glm::vec3( 0.0, 1.0, 0.0 ) );
+    }
+
+    #[test]
+    fn flipping_handedness_mirrors_and_keeps_faces_outwards() {
+        let options = ImportOptions { flip_handedness: true, ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/cube.obj", options );
+        let part = &model.meshes[0];
+        let positions = positions( part );
+
+        // Every face's winding must still agree with its (mirrored) normal
+        for triangle in part.indices.chunks( 3 ) {
+            let [ p0, p1, p2 ] = [ 0, 1, 2 ].map( |corner| positions[triangle[corner] as usize] );
+            let face_normal = ( p1 - p0 ).cross( &( p2 - p0 ) ).normalize();
+            let vertex = triangle[0] as usize;
+            assert_vec3_eq( glm::vec3( part.normals[vertex * 3], part.normals[vertex * 3 + 1], part.normals[vertex * 3 + 2] ), face_normal );
+            assert!( face_normal.dot( &p0 ) > 0.0, "face at {p0:?} points inwards" );
+        }
+    }
+
+    #[test]
+    fn center_and_fit_place_the_model_in_a_unit_box() {
+        let options = ImportOptions { center: true, fit_to_unit_box: true, ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/two_objects.obj", options );
+
+        // The model spans 240 x 80 x 160 units in the file
+        let ( min, max ) = model.bounding_box().unwrap();
+        assert_vec3_eq( min, glm::vec3( -0.5, -1.0 / 6.0, -1.0 / 3.0 ) );
+        assert_vec3_eq( max, glm::vec3( 0.5, 1.0 / 6.0, 1.0 / 3.0 ) );
+    }
+
+    #[test]
+    fn center_is_applied_before_the_transform() {
+        let options = ImportOptions { center: true, transform: Transform::from_translation( glm::vec3( 0.0, 5.0, 0.0 ) ), ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/cube.obj", options );
+        let ( min, max ) = model.bounding_box().unwrap();
+        assert_vec3_eq( ( min + max ) * 0.5, glm::vec3( 0.0, 5.0, 0.0 ) );
+    }
+
+    #[test]
+    fn loader_is_chosen_by_extension() {
+        let ply = Model::new().load_from_file( "resources/tests/colored_square.ply", ImportOptions::new() );
+        assert_eq!( ply.meshes.len(), 1 );
+        assert_eq!( ply.meshes[0].index_count, 6 );
+        assert_eq!( ply.meshes[0].colors[..4], [ 1.0, 0.0, 0.0, 1.0 ] );
+        // (The file has no normals, so they are generated)
+        assert_eq!( ply.meshes[0].normals.len(), ply.meshes[0].vertices.len() );
+
+        let stl = Model::new().load_from_file( "resources/tests/two_solids.stl", ImportOptions::new() );
+        assert_eq!( stl.meshes.len(), 2 );
+
+        let obj = Model::new().load_from_file( "resources/tests/no_normals.obj", ImportOptions::new() );
+        assert_eq!( obj.meshes[0].colors, DEFAULT_COLOR.repeat( 3 ) );
+    }
+
+    #[test]
+    fn import_welds_and_regenerates_normals() {
+        let options = ImportOptions { weld_epsilon: Some( 1e-5 ), ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/square_binary.stl", options );
+        let part = &model.meshes[0];
+        assert_eq!( part.vertices.len(), 4 * 3 );
+        assert_eq!( part.normals.len(), 4 * 3 );
+        for normal in part.normals.chunks( 3 ) {
+            assert_vec3_eq( glm::vec3( normal[0], normal[1], normal[2] ), glm::vec3( 0.0, 0.0, 1.0 ) );
+        }
+    }
+
+    #[test]
+    fn import_decimates_to_the_ratio() {
+        let options = ImportOptions { decimate_ratio: 0.5, ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/grid.obj", options );
+        let part = &model.meshes[0];
+        assert!( part.index_count / 3 <= 16, "{} triangles left", part.index_count / 3 );
+        assert_eq!( part.normals.len(), part.vertices.len() );
+
+        let ( min, max ) = part.bounding_box().unwrap();
+        assert_vec3_eq( min, glm::vec3( 0.0, 0.0, 0.0 ) );
+        assert_vec3_eq( max, glm::vec3( 1.0, 0.0, 1.0 ) );
+    }
+
+    #[test]
+    fn import_subdivides_before_generating_normals() {
+        let options = ImportOptions { subdivisions: 2, crease_angle: 180.0, ..ImportOptions::new() };
+        let model = Model::new().load_from_file( "resources/tests/cube.obj", options );
+        let part = &model.meshes[0];
+        assert_eq!( part.index_count, 12 * 16 * 3 );
+        assert_eq!( part.normals.len(), part.vertices.len() );
+
+        // The smoothed cube's normals point away from its center
+        for ( position, normal ) in part.vertices.chunks( 3 ).zip( part.normals.chunks( 3 ) ) {
+            let ( position, normal ) = ( glm::vec3( position[0], position[1], position[2] ), glm::vec3( normal[0], normal[1], normal[2] ) );
+            assert!( normal.dot( &position ) > 0.0 );
+        }
+    }
+
+    #[test]
+    fn vertex_colors_reach_the_flattened_scene() {
+        let ply = Model::new().load_from_file( "resources/tests/colored_square.ply", ImportOptions::new() );
+        let ( positions, normals, colors, triangles, meshes ) = ply.generate_raytracing_structs( 0 );
+        assert_eq!( colors.len(), positions.len() );
+
+        let mut scene = Scene::new();
+        scene.add_node( "square", None, Transform::from_translation( glm::vec3( 0.0, 0.0, 5.0 ) ), Geometry::Mesh { positions, normals, colors, triangles, meshes } );
+        let flat = scene.flatten().unwrap();
+
+        // Each corner of the square keeps the color given to it in the file
+        let expected = [
+            ( glm::vec3( 0.0, 0.0, 5.0 ), glm::vec4( 1.0, 0.0, 0.0, 1.0 ) ),
+            ( glm::vec3( 1.0, 0.0, 5.0 ), glm::vec4( 0.0, 1.0, 0.0, 1.0 ) ),
+            ( glm::vec3( 1.0, 1.0, 5.0 ), glm::vec4( 0.0, 0.0, 1.0, 1.0 ) ),
+            ( glm::vec3( 0.0, 1.0, 5.0 ), glm::vec4( 1.0, 1.0, 1.0, 1.0 ) ),
+        ];
+        assert_eq!( flat.colors.len(), flat.positions.len() );
+        for triangle in &flat.triangles {
+            for vertex in triangle.vertices {
+                let ( position, color ) = ( flat.positions[vertex as usize], flat.colors[vertex as usize] );
+                let ( _, expected_color ) = expected.iter().find( |( corner, _ )| ( corner - position ).norm() < 1e-5 )
+                    .unwrap_or_else( || panic!( "{position:?} is not a corner of the square" ) );
+                assert_eq!( color, *expected_color );
+            }
+        }
+    }
+
+    #[test]
+    fn meshes_without_vertex_colors_are_white() {
+        let mut part = mesh( &[ 0.0, 0.0, 0.0,  1.0, 0.0, 0.0,  0.0, 1.0, 0.0 ], &[ 0, 1, 2 ] );
+        part.normals = [ 0.0, 0.0, 1.0 ].repeat( 3 );
+        let ( _, _, colors, _, _ ) = Model { meshes: vec![ part ] }.generate_raytracing_structs( 0 );
+        assert_eq!( colors, vec![ glm::Vec4::from( DEFAULT_COLOR ); 3 ] );
+    }
+
+    #[test]
+    #[should_panic( expected = "ERROR::MESH::FAILED_TO_LOAD_MODEL" )]
+    fn unknown_extensions_panic() {
+        Model::new().load_from_file( "resources/tests/cube.fbx", ImportOptions::new() );
+    }
+
+    /**
+     * Creates a square plane on XZ from 0 to 1, split into n by n quads of two triangles each, facing up.
+     */
+    fn grid( n: u32 ) -> Mesh {
+        let vertices: Vec<f32> = ( 0..=n ).flat_map( |z| ( 0..=n ).flat_map( move |x| [ x as f32 / n as f32, 0.0, z as f32 / n as f32 ] ) ).collect();
+        let indices: Vec<u32> = ( 0..n ).flat_map( |z| ( 0..n ).flat_map( move |x| {
+            let corner = z * ( n + 1 ) + x;
+            [ corner, corner + n + 1, corner + 1, corner + 1, corner + n + 1, corner + n + 2 ]
+        } ) ).collect();
+        mesh( &vertices, &indices )
+    }
+
+    /**
+     * Gets the normals of the triangles of a mesh (scaled by twice their area).
+     */
+    fn face_normals( part: &Mesh ) -> Vec<glm::Vec3> {
+        let positions = positions( part );
+        part.indices.chunks( 3 ).map( |triangle| {
+            let [ p0, p1, p2 ] = [ 0, 1, 2 ].map( |corner| positions[triangle[corner] as usize] );
+            ( p1 - p0 ).cross( &( p2 - p0 ) )
+        } ).collect()
+    }
+
+    #[test]
+    fn weld_merges_duplicated_vertices() {
+        let mut part = stl::load( "resources/tests/square_binary.stl" ).unwrap().remove( 0 );
+        assert_eq!( part.weld( 0.0 ), 2 );
+        assert_eq!( part.vertices.len(), 4 * 3 );
+        assert_eq!( part.normals.len(), 4 * 3 );
+        assert_eq!( part.colors.len(), 4 * 4 );
+        assert_eq!( part.index_count, 6 );
+        assert!( part.indices.iter().all( |&vertex| vertex < 4 ) );
+        for normal in face_normals( &part ) {
+            assert_vec3_eq( normal.normalize(), glm::vec3( 0.0, 0.0, 1.0 ) );
+        }
+    }
+
+    #[test]
+    fn weld_only_merges_vertices_within_the_tolerance() {
+        let vertices = [ 0.0, 0.0, 0.0,  1.0, 0.0, 0.0,  0.0, 1.0, 0.0,  1.005, 0.0, 0.0,  1.0, 1.0, 0.0 ];
+        let indices = [ 0, 1, 2, 3, 4, 2 ];
+
+        let mut part = mesh( &vertices, &indices );
+        assert_eq!( part.weld( 0.001 ), 0 );
+        assert_eq!( part.indices, indices );
+
+        let mut part = mesh( &vertices, &indices );
+        assert_eq!( part.weld( 0.01 ), 1 );
+        assert_eq!( part.indices, vec![ 0, 1, 2, 1, 3, 2 ] );
+        assert!( part.normals.is_empty() && part.colors.is_empty() );
+    }
+
+    #[test]
+    fn weld_removes_degenerate_triangles() {
+        let vertices = [ 0.0, 0.0, 0.0,  1.0, 0.0, 0.0,  0.0, 1.0, 0.0,  0.0, 0.0, 0.001 ];
+        let mut part = mesh( &vertices, &[ 0, 1, 2, 0, 3, 1 ] );
+        assert_eq!( part.weld( 0.01 ), 1 );
+        assert_eq!( part.indices, vec![ 0, 1, 2 ] );
+        assert_eq!( part.index_count, 3 );
+    }
+
+    #[test]
+    fn decimate_keeps_a_plane_and_its_boundary() {
+        let mut part = grid( 8 );
+        part.decimate( 16 );
+        assert!( part.index_count / 3 <= 16, "{} triangles left", part.index_count / 3 );
+        assert!( part.vertices.len() / 3 < 81 );
+
+        let ( min, max ) = part.bounding_box().unwrap();
+        assert_vec3_eq( min, glm::vec3( 0.0, 0.0, 0.0 ) );
+        assert_vec3_eq( max, glm::vec3( 1.0, 0.0, 1.0 ) );
+
+        // Every face still faces up, and together they still cover the square
+        let normals = face_normals( &part );
+        assert!( normals.iter().all( |normal| normal.y > 0.0 ) );
+        let area: f32 = normals.iter().map( |normal| normal.norm() * 0.5 ).sum();
+        assert!( ( area - 1.0 ).abs() < 1e-4, "area is {area}" );
+    }
+
+    #[test]
+    fn decimate_to_more_triangles_changes_nothing() {
+        let mut part = grid( 2 );
+        let ( vertices, indices ) = ( part.vertices.clone(), part.indices.clone() );
+        part.decimate( 8 );
+        assert_eq!( part.vertices, vertices );
+        assert_eq!( part.indices, indices );
+    }
+
+    #[test]
+    fn subdivide_splits_every_triangle_into_four() {
+        let options = ImportOptions { crease_angle: 180.0, ..ImportOptions::new() };
+        let mut part = Model::new().load_from_file( "resources/tests/cube.obj", options ).meshes.remove( 0 );
+        let ( old_min, old_max ) = part.bounding_box().unwrap();
+        part.subdivide_loop();
+
+        // 8 corners and 18 edges, and 12 triangles become 48
+        assert_eq!( part.vertices.len() / 3, 26 );
+        assert_eq!( part.normals.len(), 26 * 3 );
+        assert_eq!( part.index_count, 48 * 3 );
+
+        // The mesh stays closed: every edge has two triangles
+        let mut edges = HashMap::<( u32, u32 ), u32>::new();
+        for triangle in part.indices.chunks( 3 ) {
+            for corner in 0..3 {
+                let ( a, b ) = ( triangle[corner], triangle[( corner + 1 ) % 3] );
+                *edges.entry( ( a.min( b ), a.max( b ) ) ).or_default() += 1;
+            }
+        }
+        assert!( edges.values().all( |&count| count == 2 ) );
+
+        // Smoothing pulls the corners in, and keeps the new vertices inside the cube
+        let positions = positions( &part );
+        assert!( positions[..8].iter().all( |position| position.norm() < 3.0f32.sqrt() - 0.1 ) );
+        assert!( positions.iter().all( |position| glm::all( &glm::greater_than_equal( position, &old_min ) ) && glm::all( &glm::less_than_equal( position, &old_max ) ) ) );
+        assert!( face_normals( &part ).iter().all( |normal| normal.norm() > 0.0 ) );
+    }
+
+    #[test]
+    fn subdivide_keeps_a_plane_flat() {
+        let mut part = grid( 2 );
+        part.subdivide_loop();
+        assert_eq!( part.index_count, 8 * 4 * 3 );
+        assert!( positions( &part ).iter().all( |position| position.y.abs() < 1e-6 ) );
+        assert!( face_normals( &part ).iter().all( |normal| normal.y > 0.0 ) );
+
+        // The boundary's corners stay in place
+        let ( min, max ) = part.bounding_box().unwrap();
+        assert_vec3_eq( min, glm::vec3( 0.0, 0.0, 0.0 ) );
+        assert_vec3_eq( max, glm::vec3( 1.0, 0.0, 1.0 ) );
+    }
+}
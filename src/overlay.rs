@@ -0,0 +1,280 @@
+use std::ptr;
+
+use crate::font::{GLYPHS, GLYPH_WIDTH, GLYPH_HEIGHT, FIRST_CHAR};
+use crate::shader::{Shader, ShaderBuilder};
+
+/**
+ * Struct for measuring how long the GPU spends on a set of commands, using GL_TIME_ELAPSED queries.
+ * The queries are double buffered, so each frame reads the previous frame's result instead of waiting for the GPU.
+ */
+pub struct GpuTimer {
+    queries: [u32; 2],
+    issued: [bool; 2],
+    current: usize,
+    elapsed_ms: Option<f32>,
+}
+
+/**
+ * GpuTimer functions.
+ */
+impl GpuTimer {
+    /**
+     * Creates the timer's queries.
+     */
+    pub unsafe fn new() -> GpuTimer {
+        let mut queries = [0u32; 2];
+        gl::GenQueries( 2, queries.as_mut_ptr() );
+        GpuTimer { queries, issued: [false; 2], current: 0, elapsed_ms: None }
+    }
+
+    /**
+     * Starts timing. Only one timer may be running at a time.
+     */
+    pub unsafe fn begin( &self ) {
+        gl::BeginQuery( gl::TIME_ELAPSED, self.queries[self.current] );
+    }
+
+    /**
+     * Stops timing, and collects the result of the previous frame's query if the GPU has finished it.
+     */
+    pub unsafe fn end( &mut self ) {
+        gl::EndQuery( gl::TIME_ELAPSED );
+        self.issued[self.current] = true;
+        self.current = 1 - self.current;
+
+        // The other query was issued a frame ago
+        if self.issued[self.current] {
+            let mut available: gl::types::GLint = 0;
+            gl::GetQueryObjectiv( self.queries[self.current], gl::QUERY_RESULT_AVAILABLE, &mut available );
+            if available != 0 {
+                let mut elapsed_ns: gl::types::GLuint64 = 0;
+                gl::GetQueryObjectui64v( self.queries[self.current], gl::QUERY_RESULT, &mut elapsed_ns );
+                self.elapsed_ms = Some( elapsed_ns as f32 / 1_000_000.0 );
+            }
+        }
+    }
+
+    // --- Getters
+    /**
+     * Gets the last measured time, in milliseconds, or None if nothing has been measured yet.
+     */
+    pub fn elapsed_ms( &self ) -> Option<f32> {
+        self.elapsed_ms
+    }
+}
+
+/**
+ * Enum for the ways text can be drawn.
+ * The values match the STYLE_ constants in the text shader.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TextStyle {
+    // Light text on a dark background
+    Normal = 0,
+    // Dark text on a light background, for hovered or selected items
+    Highlight = 1,
+    // Colored text on a dark background, for titles and active items
+    Accent = 2,
+}
+
+/**
+ * Struct for a grid of characters covering the screen, starting at the top left.
+ * Each cell holds a character (0 for an empty cell, which is not drawn) and a style.
+ */
+pub struct TextGrid {
+    columns: u32,
+    rows: u32,
+    cells: Vec<[u8; 2]>,
+}
+
+/**
+ * TextGrid functions.
+ */
+impl TextGrid {
+    /**
+     * Creates an empty grid.
+     *
+     * @param columns The amount of columns.
+     * @param rows The amount of rows.
+     */
+    pub fn new( columns: u32, rows: u32 ) -> TextGrid {
+        TextGrid { columns, rows, cells: vec![[0, 0]; ( columns * rows ) as usize] }
+    }
+
+    /**
+     * Empties all cells.
+     */
+    pub fn clear( &mut self ) {
+        self.cells.fill( [0, 0] );
+    }
+
+    /**
+     * Writes text to the grid, starting at a cell and continuing to the right.
+     * Text outside of the grid is cut off, and characters outside of printable ASCII are written as '?'.
+     *
+     * @param column The column of the first character.
+     * @param row The row of the text.
+     * @param text The text.
+     * @param style The style of the text.
+     */
+    pub fn write( &mut self, column: i32, row: i32, text: &str, style: TextStyle ) {
+        if row < 0 || row >= self.rows as i32 {
+            return;
+        }
+        for ( offset, c ) in text.chars().enumerate() {
+            let cell_column = column + offset as i32;
+            if cell_column < 0 || cell_column >= self.columns as i32 {
+                continue;
+            }
+            let character = if c.is_ascii() && !c.is_ascii_control() { c as u8 } else { b'?' };
+            self.cells[( row as u32 * self.columns + cell_column as u32 ) as usize] = [character, style as u8];
+        }
+    }
+
+    /**
+     * Writes lines of text below each other, padding them with spaces so the background forms a box.
+     *
+     * @param column The column of the lines' first character.
+     * @param row The row of the first line.
+     * @param lines The lines of text.
+     * @param style The style of the text.
+     */
+    pub fn write_lines( &mut self, column: i32, row: i32, lines: &[String], style: TextStyle ) {
+        let width = lines.iter().map( |line| line.chars().count() ).max().unwrap_or( 0 );
+        for ( offset, line ) in lines.iter().enumerate() {
+            self.write( column, row + offset as i32, &format!( "{line:width$}" ), style );
+        }
+    }
+
+    // --- Getters
+    pub fn columns( &self ) -> u32 { self.columns }
+    pub fn rows( &self )    -> u32 { self.rows }
+}
+
+/**
+ * Struct for drawing a text grid on top of the screen, using the bitmap font.
+ * The grid is uploaded to a texture (one texel per cell) which the text shader looks up for every pixel,
+ * so the whole grid is drawn with the same screen quad as the other passes.
+ */
+pub struct TextOverlay {
+    shader: Shader,
+    font_texture: u32,
+    text_texture: u32,
+    columns: u32,
+    rows: u32,
+    scale: u32,
+    screen_height: u32,
+}
+
+/**
+ * TextOverlay functions.
+ */
+impl TextOverlay {
+    /**
+     * Creates the text shader and uploads the font.
+     *
+     * @param width The width of the screen, in pixels.
+     * @param height The height of the screen, in pixels.
+     * @param scale How many screen pixels each font pixel covers in each direction.
+     */
+    pub unsafe fn new( width: u32, height: u32, scale: u32 ) -> TextOverlay {
+        let scale = scale.max( 1 );
+        let ( columns, rows ) = ( ( width / ( GLYPH_WIDTH * scale ) ).max( 1 ), ( height / ( GLYPH_HEIGHT * scale ) ).max( 1 ) );
+
+        // The font is stored with one glyph per texel row, and one glyph row per texel
+        let font_texture = create_texture( gl::R8UI, gl::RED_INTEGER, GLYPH_HEIGHT, GLYPHS.len() as u32, GLYPHS.concat().as_slice() );
+        let text_texture = create_texture( gl::RG8UI, gl::RG_INTEGER, columns, rows, &vec![0u8; ( columns * rows * 2 ) as usize] );
+
+        TextOverlay {
+            shader: ShaderBuilder::new()
+                .attach_shader( "shaders/raytracing.vert" )
+                .attach_shader( "shaders/text.frag" )
+                .link(),
+            font_texture,
+            text_texture,
+            columns,
+            rows,
+            scale,
+            screen_height: height,
+        }
+    }
+
+    /**
+     * Creates an empty grid covering the screen.
+     */
+    pub fn create_grid( &self ) -> TextGrid {
+        TextGrid::new( self.columns, self.rows )
+    }
+
+    /**
+     * Gets the cell under a point on the screen.
+     *
+     * @param position The point, in pixels from the top left corner of the screen.
+     * @return The column (x) and row (y) of the cell, with the fraction giving the position within it. These may be outside of the grid.
+     */
+    pub fn cell_at( &self, position: glm::Vec2 ) -> glm::Vec2 {
+        glm::vec2( position.x / ( GLYPH_WIDTH * self.scale ) as f32, position.y / ( GLYPH_HEIGHT * self.scale ) as f32 )
+    }
+
+    /**
+     * Draws a text grid on top of the currently bound framebuffer.
+     *
+     * @param grid The grid, created by create_grid().
+     * @param vao The vertex array of the screen quad.
+     * @param index_count The amount of indices in the screen quad.
+     */
+    pub unsafe fn draw( &self, grid: &TextGrid, vao: u32, index_count: i32 ) {
+        if ( grid.columns, grid.rows ) != ( self.columns, self.rows ) {
+            panic!( "ERROR::OVERLAY::GRID_SIZE_MISMATCH" );
+        }
+
+        // Upload the grid
+        gl::BindTexture( gl::TEXTURE_2D, self.text_texture );
+        gl::PixelStorei( gl::UNPACK_ALIGNMENT, 1 );
+        gl::TexSubImage2D( gl::TEXTURE_2D, 0, 0, 0, self.columns as i32, self.rows as i32, gl::RG_INTEGER, gl::UNSIGNED_BYTE, grid.cells.as_ptr() as *const _ );
+        gl::PixelStorei( gl::UNPACK_ALIGNMENT, 4 );
+
+        // Draw
+        self.shader.activate();
+        gl::ActiveTexture( gl::TEXTURE0 );
+        gl::BindTexture( gl::TEXTURE_2D, self.font_texture );
+        gl::ActiveTexture( gl::TEXTURE1 );
+        gl::BindTexture( gl::TEXTURE_2D, self.text_texture );
+        gl::Uniform1i( self.shader.get_uniform_location( "font" ), 0 );
+        gl::Uniform1i( self.shader.get_uniform_location( "text" ), 1 );
+        gl::Uniform1ui( self.shader.get_uniform_location( "firstChar" ), FIRST_CHAR as u32 );
+        gl::Uniform1i( self.shader.get_uniform_location( "scale" ), self.scale as i32 );
+        gl::Uniform1i( self.shader.get_uniform_location( "screenHeight" ), self.screen_height as i32 );
+
+        gl::BindVertexArray( vao );
+        gl::DrawElements( gl::TRIANGLES, index_count, gl::UNSIGNED_INT, ptr::null() );
+        gl::ActiveTexture( gl::TEXTURE0 );
+    }
+}
+
+/**
+ * Creates an unsigned integer texture with one byte per channel.
+ *
+ * @param internal_format The internal format of the texture, for example gl::R8UI.
+ * @param format The format of the data, for example gl::RED_INTEGER.
+ * @param width The width of the texture, in texels.
+ * @param height The height of the texture, in texels.
+ * @param data The texels, row by row.
+ *
+ * @return The id of the texture.
+ */
+unsafe fn create_texture( internal_format: gl::types::GLenum, format: gl::types::GLenum, width: u32, height: u32, data: &[u8] ) -> u32 {
+    let mut texture: gl::types::GLuint = 0;
+    gl::GenTextures( 1, &mut texture );
+    gl::BindTexture( gl::TEXTURE_2D, texture );
+    gl::TexStorage2D( gl::TEXTURE_2D, 1, internal_format, width as i32, height as i32 );
+    gl::TexParameteri( gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32 );
+    gl::TexParameteri( gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32 );
+
+    gl::PixelStorei( gl::UNPACK_ALIGNMENT, 1 );
+    gl::TexSubImage2D( gl::TEXTURE_2D, 0, 0, 0, width as i32, height as i32, format, gl::UNSIGNED_BYTE, data.as_ptr() as *const _ );
+    gl::PixelStorei( gl::UNPACK_ALIGNMENT, 4 );
+
+    gl::BindTexture( gl::TEXTURE_2D, 0 );
+    texture
+}
@@ -0,0 +1,370 @@
+use crate::mesh::{Mesh, DEFAULT_COLOR};
+
+/**
+ * Enum for the encodings of a PLY file's body.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/**
+ * Enum for the types of PLY properties.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PropertyType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+/**
+ * PropertyType functions.
+ */
+impl PropertyType {
+    /**
+     * Gets a property type from its name in the header, including the sized aliases (e.g. "uint8" for "uchar").
+     *
+     * @param name The name of the type.
+     * @return The type, or an error message if the name is unknown.
+     */
+    fn from_str( name: &str ) -> Result<PropertyType, String> {
+        match name {
+            "char" | "int8"     => Ok( PropertyType::Int8 ),
+            "uchar" | "uint8"   => Ok( PropertyType::UInt8 ),
+            "short" | "int16"   => Ok( PropertyType::Int16 ),
+            "ushort" | "uint16" => Ok( PropertyType::UInt16 ),
+            "int" | "int32"     => Ok( PropertyType::Int32 ),
+            "uint" | "uint32"   => Ok( PropertyType::UInt32 ),
+            "float" | "float32" => Ok( PropertyType::Float32 ),
+            "double" | "float64" => Ok( PropertyType::Float64 ),
+            e => { Err(format!("Unknown property type '{e}'")) },
+        }
+    }
+
+    /**
+     * Gets the size of a value of the type, in bytes.
+     */
+    fn size( self ) -> usize {
+        match self {
+            PropertyType::Int8 | PropertyType::UInt8 => 1,
+            PropertyType::Int16 | PropertyType::UInt16 => 2,
+            PropertyType::Int32 | PropertyType::UInt32 | PropertyType::Float32 => 4,
+            PropertyType::Float64 => 8,
+        }
+    }
+
+    /**
+     * Gets the value which color components of the type are divided by, to bring them within [0, 1].
+     * Integer colors use their whole range, while float colors already are within [0, 1].
+     */
+    fn color_range( self ) -> f32 {
+        match self {
+            PropertyType::UInt8 => u8::MAX as f32,
+            PropertyType::UInt16 => u16::MAX as f32,
+            PropertyType::UInt32 => u32::MAX as f32,
+            PropertyType::Int8 => i8::MAX as f32,
+            PropertyType::Int16 => i16::MAX as f32,
+            PropertyType::Int32 => i32::MAX as f32,
+            PropertyType::Float32 | PropertyType::Float64 => 1.0,
+        }
+    }
+}
+
+/**
+ * Struct for a property of a PLY element.
+ * List properties (like the vertex indices of a face) have the type of their length as well.
+ */
+struct Property {
+    name: String,
+    value_type: PropertyType,
+    count_type: Option<PropertyType>,
+}
+
+/**
+ * Struct for an element declared in the header of a PLY file, like the vertices or faces.
+ */
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/**
+ * Struct for reading the values of a PLY file's body one by one, whatever its format.
+ */
+struct BodyReader<'a> {
+    format: Format,
+    data: &'a [u8],
+    position: usize,
+    words: std::str::SplitAsciiWhitespace<'a>,
+}
+
+/**
+ * BodyReader functions.
+ */
+impl<'a> BodyReader<'a> {
+    /**
+     * Creates a reader for the body of a PLY file.
+     *
+     * @param format The format of the body.
+     * @param data The body, following the header.
+     *
+     * @return The reader, or an error message if an ASCII body is not valid text.
+     */
+    fn new( format: Format, data: &'a [u8] ) -> Result<BodyReader<'a>, String> {
+        let text = if format == Format::Ascii {
+            std::str::from_utf8( data ).map_err( |_| "The body is not valid ASCII".to_string() )?
+        } else {
+            ""
+        };
+        Ok( BodyReader { format, data, position: 0, words: text.split_ascii_whitespace() } )
+    }
+
+    /**
+     * Reads the next value.
+     *
+     * @param value_type The type of the value.
+     * @return The value, or an error message if the body ended or the value is invalid.
+     */
+    fn read( &mut self, value_type: PropertyType ) -> Result<f64, String> {
+        if self.format == Format::Ascii {
+            let word = self.words.next().ok_or( "Unexpected end of file" )?;
+            return word.parse::<f64>().map_err( |_| format!( "Invalid number '{word}'" ) )
+        }
+
+        let bytes = self.data.get( self.position..self.position + value_type.size() ).ok_or( "Unexpected end of file" )?;
+        self.position += value_type.size();
+
+        // Read the bytes in the file's byte order
+        let mut buffer = [0u8; 8];
+        buffer[..bytes.len()].copy_from_slice( bytes );
+        if self.format == Format::BinaryBigEndian {
+            buffer[..bytes.len()].reverse();
+        }
+        let ( b2, b4, b8 ) = (
+            [buffer[0], buffer[1]],
+            [buffer[0], buffer[1], buffer[2], buffer[3]],
+            buffer,
+        );
+        Ok( match value_type {
+            PropertyType::Int8 => buffer[0] as i8 as f64,
+            PropertyType::UInt8 => buffer[0] as f64,
+            PropertyType::Int16 => i16::from_le_bytes( b2 ) as f64,
+            PropertyType::UInt16 => u16::from_le_bytes( b2 ) as f64,
+            PropertyType::Int32 => i32::from_le_bytes( b4 ) as f64,
+            PropertyType::UInt32 => u32::from_le_bytes( b4 ) as f64,
+            PropertyType::Float32 => f32::from_le_bytes( b4 ) as f64,
+            PropertyType::Float64 => f64::from_le_bytes( b8 ),
+        } )
+    }
+}
+
+/**
+ * Loads a .ply file as a mesh.
+ *
+ * @param path The path to the .ply file.
+ * @return The mesh, or an error message if the file could not be read or parsed.
+ */
+pub fn load( path: &str ) -> Result<Mesh, String> {
+    let data = std::fs::read( path ).map_err( |err| format!( "Failed to read PLY file '{path}': {err}" ) )?;
+    parse( &data ).map_err( |err| format!( "{path}: {err}" ) )
+}
+
+/**
+ * Parses the contents of a PLY file as a mesh, in the ASCII or either binary format.
+ * The vertices must have x, y and z properties, and may have normals (nx, ny, nz) and colors (red, green, blue and alpha).
+ * Faces are read from their vertex_indices (or vertex_index) list, and polygons are triangulated as fans.
+ * Any other elements and properties are skipped.
+ *
+ * @param data The contents of the file.
+ * @return The mesh, or an error message if the file could not be parsed.
+ */
+pub fn parse( data: &[u8] ) -> Result<Mesh, String> {
+    // Find the end of the header, which is followed by the body
+    let header_end = data.windows( 10 ).position( |window| window == b"end_header" ).ok_or( "Missing end_header" )?;
+    let body_start = data[header_end..].iter().position( |&byte| byte == b'\n' ).map_or( data.len(), |newline| header_end + newline + 1 );
+    let header = std::str::from_utf8( &data[..header_end] ).map_err( |_| "The header is not valid ASCII" )?;
+
+    // Parse the header
+    let mut lines = header.lines().map( str::trim );
+    if lines.next() != Some( "ply" ) {
+        return Err( "Not a PLY file".to_string() )
+    }
+    let ( mut format, mut elements ) = ( None, Vec::<Element>::new() );
+    for line in lines {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        match words.as_slice() {
+            [] => { },
+            ["comment" | "obj_info", ..] => { },
+            ["format", name, _version] => {
+                format = Some( match *name {
+                    "ascii" => Format::Ascii,
+                    "binary_little_endian" => Format::BinaryLittleEndian,
+                    "binary_big_endian" => Format::BinaryBigEndian,
+                    e => { return Err( format!( "Unknown format '{e}'" ) ) },
+                } );
+            },
+            ["element", name, count] => {
+                let count = count.parse().map_err( |_| format!( "Invalid element count '{count}'" ) )?;
+                elements.push( Element { name: name.to_string(), count, properties: Vec::new() } );
+            },
+            ["property", "list", count_type, value_type, name] => {
+                let element = elements.last_mut().ok_or( "Property declared before any element" )?;
+                element.properties.push( Property {
+                    name: name.to_string(),
+                    value_type: PropertyType::from_str( value_type )?,
+                    count_type: Some( PropertyType::from_str( count_type )? ),
+                } );
+            },
+            ["property", value_type, name] => {
+                let element = elements.last_mut().ok_or( "Property declared before any element" )?;
+                element.properties.push( Property { name: name.to_string(), value_type: PropertyType::from_str( value_type )?, count_type: None } );
+            },
+            _ => { return Err( format!( "Could not parse header line '{line}'" ) ) },
+        }
+    }
+    let format = format.ok_or( "Missing format" )?;
+
+    // Read the elements in the order they were declared
+    let mut reader = BodyReader::new( format, &data[body_start..] )?;
+    let mut mesh = Mesh { vertices: Vec::new(), normals: Vec::new(), colors: Vec::new(), indices: Vec::new(), index_count: 0 };
+    let mut vertex_count = 0;
+    for element in &elements {
+        let index_of = |name: &str| element.properties.iter().position( |property| property.name == name && property.count_type.is_none() );
+        let ( position, normal ) = (
+            [ index_of( "x" ), index_of( "y" ), index_of( "z" ) ],
+            [ index_of( "nx" ), index_of( "ny" ), index_of( "nz" ) ],
+        );
+        let color = [ index_of( "red" ), index_of( "green" ), index_of( "blue" ), index_of( "alpha" ) ];
+        let face_indices = element.properties.iter()
+            .position( |property| ( property.name == "vertex_indices" || property.name == "vertex_index" ) && property.count_type.is_some() );
+
+        let mut values = vec![ 0.0; element.properties.len() ];
+        let mut list = Vec::<u32>::new();
+        for _ in 0..element.count {
+            // Read every property of the element, keeping the values of scalars and the items of the face list
+            for ( index, property ) in element.properties.iter().enumerate() {
+                match property.count_type {
+                    None => values[index] = reader.read( property.value_type )?,
+                    Some( count_type ) => {
+                        let count = reader.read( count_type )? as usize;
+                        let is_face_list = Some( index ) == face_indices;
+                        if is_face_list {
+                            list.clear();
+                        }
+                        for _ in 0..count {
+                            let value = reader.read( property.value_type )?;
+                            if is_face_list {
+                                if value < 0.0 || value.fract() != 0.0 {
+                                    return Err( format!( "Face refers to invalid vertex index {value}" ) )
+                                }
+                                list.push( value as u32 );
+                            }
+                        }
+                    },
+                }
+            }
+
+            if element.name == "vertex" {
+                let [ Some( x ), Some( y ), Some( z ) ] = position else {
+                    return Err( "Vertices must have x, y and z properties".to_string() )
+                };
+                mesh.vertices.extend( [ x, y, z ].map( |index| values[index] as f32 ) );
+                if let [ Some( x ), Some( y ), Some( z ) ] = normal {
+                    mesh.normals.extend( [ x, y, z ].map( |index| values[index] as f32 ) );
+                }
+                for ( channel, index ) in color.iter().enumerate() {
+                    mesh.colors.push( index.map_or( DEFAULT_COLOR[channel], |index| {
+                        values[index] as f32 / element.properties[index].value_type.color_range()
+                    } ) );
+                }
+            } else if element.name == "face" && face_indices.is_some() {
+                if let Some( &index ) = list.iter().find( |&&index| index as usize >= vertex_count ) {
+                    return Err( format!( "Face refers to vertex {index}, but there are only {vertex_count} vertices" ) )
+                }
+                for corner in 1..list.len().saturating_sub( 1 ) {
+                    mesh.indices.extend( [ list[0], list[corner], list[corner + 1] ] );
+                }
+            }
+        }
+        if element.name == "vertex" {
+            vertex_count = element.count;
+        }
+    }
+
+    mesh.index_count = mesh.indices.len() as i32;
+    Ok( mesh )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Asserts that two float slices are (almost) equal.
+     */
+    fn assert_floats_eq( actual: &[f32], expected: &[f32] ) {
+        assert_eq!( actual.len(), expected.len(), "expected {expected:?}, got {actual:?}" );
+        assert!( actual.iter().zip( expected ).all( |( a, b )| ( a - b ).abs() < 1e-5 ), "expected {expected:?}, got {actual:?}" );
+    }
+
+    #[test]
+    fn ascii_quads_are_triangulated_with_colors() {
+        let mesh = load( "resources/tests/colored_square.ply" ).unwrap();
+        assert_floats_eq( &mesh.vertices, &[ 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 0.0 ] );
+        assert_eq!( mesh.indices, vec![ 0, 1, 2, 0, 2, 3 ] );
+        assert_eq!( mesh.index_count, 6 );
+        assert!( mesh.normals.is_empty() );
+
+        // Colors are scaled from [0, 255], and get an opaque alpha
+        assert_floats_eq( &mesh.colors, &[ 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0 ] );
+    }
+
+    #[test]
+    fn binary_files_are_read_in_either_byte_order() {
+        for path in [ "resources/tests/triangle_binary_little_endian.ply", "resources/tests/triangle_binary_big_endian.ply" ] {
+            let mesh = load( path ).unwrap();
+            assert_floats_eq( &mesh.vertices, &[ 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 3.0, 0.0 ] );
+            assert_floats_eq( &mesh.normals, &[ 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0 ] );
+            assert_floats_eq( &mesh.colors, &[ 1.0, 0.0, 0.0, 1.0, 0.0, 0.5, 0.0, 1.0, 0.0, 0.0, 1.0, 0.25 ] );
+            assert_eq!( mesh.indices, vec![ 0, 1, 2 ] );
+        }
+    }
+
+    #[test]
+    fn missing_colors_use_the_default_color() {
+        let data = b"ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_index\nend_header\n0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+        let mesh = parse( data ).unwrap();
+        assert_eq!( mesh.colors, DEFAULT_COLOR.repeat( 3 ) );
+        assert_eq!( mesh.indices, vec![ 0, 1, 2 ] );
+    }
+
+    #[test]
+    fn invalid_files_are_errors() {
+        let header = "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nproperty float y\nproperty float z\nelement face 1\nproperty list uchar int vertex_indices\nend_header\n";
+        let cases: [( String, &str ); 9] = [
+            ( "solid cube\n".to_string(), "end_header" ),
+            ( "plx\nformat ascii 1.0\nend_header\n".to_string(), "Not a PLY file" ),
+            ( "ply\nformat utf16 1.0\nend_header\n".to_string(), "Unknown format" ),
+            ( "ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n".to_string(), "Unknown property type" ),
+            ( format!( "{header}0 0 0\n3 0 1 2\n" ), "only 1 vertices" ),
+            ( format!( "{header}0 0 0\n3 0 -1 0\n" ), "invalid vertex index -1" ),
+            ( format!( "{header}0 0 0\n3 0 0.5 0\n" ), "invalid vertex index 0.5" ),
+            ( format!( "{header}0 0 zero\n" ), "Invalid number" ),
+            ( format!( "{header}0 0\n" ), "Unexpected end of file" ),
+        ];
+        for ( data, message ) in cases {
+            let error = parse( data.as_bytes() ).err().unwrap_or_else( || panic!( "{data:?} was parsed" ) );
+            assert!( error.contains( message ), "'{error}' does not mention '{message}'" );
+        }
+        assert!( load( "resources/tests/missing.ply" ).is_err() );
+    }
+}
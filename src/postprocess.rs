@@ -0,0 +1,133 @@
+use crate::shader::Shader;
+
+/**
+ * Enum for the tone mapping operators, which map the unbounded light values to the [0, 1] range of the screen.
+ * The values match the TONE_MAPPING_ constants in the present shader.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ToneMapping {
+    // Values above 1 are clipped
+    None = 0,
+    // Simple x / (1 + x) curve, per channel
+    Reinhard = 1,
+    // Fitted ACES filmic curve
+    Aces = 2,
+    // AgX, which desaturates bright colors instead of skewing their hue
+    AgX = 3,
+}
+
+/**
+ * ToneMapping functions.
+ */
+impl ToneMapping {
+    /**
+     * Gets the next operator, for cycling through them.
+     */
+    pub fn next( self ) -> ToneMapping {
+        match self {
+            ToneMapping::None => ToneMapping::Reinhard,
+            ToneMapping::Reinhard => ToneMapping::Aces,
+            ToneMapping::Aces => ToneMapping::AgX,
+            ToneMapping::AgX => ToneMapping::None,
+        }
+    }
+
+    /**
+     * Gets the name of the operator, as used on the command line.
+     */
+    pub fn name( self ) -> &'static str {
+        match self {
+            ToneMapping::None => "none",
+            ToneMapping::Reinhard => "reinhard",
+            ToneMapping::Aces => "aces",
+            ToneMapping::AgX => "agx",
+        }
+    }
+
+    /**
+     * Parses an operator from its name.
+     *
+     * @param name The name, see name().
+     * @return The operator, or None if the name is unknown.
+     */
+    pub fn from_name( name: &str ) -> Option<ToneMapping> {
+        [ToneMapping::None, ToneMapping::Reinhard, ToneMapping::Aces, ToneMapping::AgX]
+            .into_iter()
+            .find( |tone_mapping| tone_mapping.name() == name )
+    }
+}
+
+/**
+ * Struct for the settings of the post pass, which turns the accumulated (linear) light into the displayed image.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub struct PostSettings {
+    // Exposure adjustment in stops, 0 leaves the image unchanged
+    pub exposure: f32,
+    pub tone_mapping: ToneMapping,
+    // White balance within [-100, 100], 0 is neutral. Positive temperature is warmer, positive tint is more magenta.
+    pub temperature: f32,
+    pub tint: f32,
+    // Encode the output with the sRGB transfer function, for displays and 8-bit image files
+    pub srgb: bool,
+}
+
+/**
+ * PostSettings functions.
+ */
+impl PostSettings {
+    /**
+     * Creates the default post settings: ACES tone mapping and sRGB output, with no exposure or white balance adjustment.
+     */
+    pub fn new() -> PostSettings {
+        PostSettings { exposure: 0.0, tone_mapping: ToneMapping::Aces, temperature: 0.0, tint: 0.0, srgb: true }
+    }
+
+    /**
+     * Calculates the white balance as scaling factors in LMS space.
+     * The temperature and tint move the white point along and across the daylight locus, see:
+     * https://docs.unity3d.com/Packages/com.unity.render-pipelines.core@latest (ColorUtils.ColorBalanceToLMSCoeffs)
+     *
+     * @return The L, M and S scaling factors.
+     */
+    pub fn white_balance_coefficients( &self ) -> glm::Vec3 {
+        let ( t1, t2 ) = ( self.temperature / 60.0, self.tint / 60.0 );
+
+        // Get the CIE xy chromaticity of the white point
+        let x = 0.31271 - t1 * if t1 < 0.0 { 0.1 } else { 0.05 };
+        let standard_illuminant_y = 2.87 * x - 3.0 * x * x - 0.275_095_07;
+        let y = standard_illuminant_y + t2 * 0.05;
+
+        // Convert to LMS, and scale the D65 white point to it
+        let ( big_x, big_y, big_z ) = ( x / y, 1.0, ( 1.0 - x - y ) / y );
+        let lms = glm::vec3(
+             0.7328 * big_x + 0.4296 * big_y - 0.1624 * big_z,
+            -0.7036 * big_x + 1.6975 * big_y + 0.0061 * big_z,
+             0.0030 * big_x + 0.0136 * big_y + 0.9834 * big_z,
+        );
+        glm::vec3( 0.949_237, 1.035_42, 1.087_28 ).component_div( &lms )
+    }
+
+    /**
+     * Sends the PostSettings' data to a uniform variable in a given shader.
+     *
+     * @param shader The shader.
+     * @param uniform_name The name of the uniform variable in the shader.
+     */
+    pub unsafe fn send_uniform( self, shader: &Shader, uniform_name: &str ) {
+        // Temporarily switch to the shader we're setting uniforms for
+        let mut prev_pid: gl::types::GLint = 0;
+        gl::GetIntegerv(gl::CURRENT_PROGRAM,&mut prev_pid);
+        shader.activate();
+
+        // Set uniforms
+        let white_balance = self.white_balance_coefficients();
+        gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.exposure").as_str() ), self.exposure);
+        gl::Uniform1ui( shader.get_uniform_location( format!("{uniform_name}.toneMapping").as_str() ), self.tone_mapping as u32);
+        gl::Uniform3f( shader.get_uniform_location( format!("{uniform_name}.whiteBalance").as_str() ), white_balance.x, white_balance.y, white_balance.z);
+        gl::Uniform1i( shader.get_uniform_location( format!("{uniform_name}.srgb").as_str() ), self.srgb as i32);
+
+        // Switch back and return
+        gl::UseProgram( prev_pid as u32 );
+    }
+}
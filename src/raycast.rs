@@ -0,0 +1,281 @@
+use crate::raytracing::RTSphere;
+use crate::scene::FlatScene;
+
+// Same epsilon and culling as the shader, so the CPU and GPU agree on what is hit
+const EPSILON: f32 = 0.001;
+const CULL_FACE: bool = true;
+
+/**
+ * Enum for the kind of primitive a ray hit.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Primitive {
+    Sphere { index: usize },
+    Triangle { mesh: usize, index: usize },
+}
+
+/**
+ * Struct for the hit information of a ray cast on the CPU.
+ */
+#[derive(Clone, Copy, Debug)]
+pub struct RayHit {
+    pub dist: f32,
+    pub pos: glm::Vec3,
+    pub normal: glm::Vec3,
+    pub primitive: Primitive,
+    // The scene node's index + 1, like in the id pass
+    pub object_id: u32,
+    // The id of the material in the MaterialLibrary
+    pub material_id: u32,
+}
+
+/**
+ * Checks for an intersection between a ray and a sphere.
+ * Mirrors RaySphere() in the raytracing shader.
+ *
+ * @param origin The origin of the ray.
+ * @param dir The direction of the ray.
+ * @param sphere The sphere.
+ *
+ * @return The distance to the intersection, or None if there is none.
+ */
+pub fn ray_sphere( origin: &glm::Vec3, dir: &glm::Vec3, sphere: &RTSphere ) -> Option<f32> {
+    let offset_origin = origin - glm::Vec3::from( sphere.center );
+
+    // Solve for distance with a quadratic equation
+    let a = dir.dot( dir );
+    let b = 2.0 * offset_origin.dot( dir );
+    let c = offset_origin.dot( &offset_origin ) - sphere.radius * sphere.radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None
+    }
+
+    let dist = ( -b - discriminant.sqrt() ) / ( 2.0 * a );
+    if dist >= 0.0 { Some( dist ) } else { None }
+}
+
+/**
+ * Checks for an intersection between a ray and a triangle, using the Möller-Trumbore algorithm.
+ * Mirrors RayTriangle() in the raytracing shader.
+ *
+ * @param origin The origin of the ray.
+ * @param dir The direction of the ray.
+ * @param positions The positions of the triangle's vertices.
+ *
+ * @return The distance to the intersection and the barycentric coordinates (u, v) of it, or None if there is none.
+ */
+pub fn ray_triangle( origin: &glm::Vec3, dir: &glm::Vec3, positions: &[glm::Vec3; 3] ) -> Option<( f32, f32, f32 )> {
+    let [ p0, p1, p2 ] = *positions;
+    let ( v0, v1 ) = ( p1 - p0, p2 - p0 );
+    let v0v1c = v0.cross( &v1 );
+
+    // Check culling and if the ray is parallel with the triangle
+    let determinant = -dir.dot( &v0v1c );
+    if ( CULL_FACE && determinant < EPSILON ) || determinant.abs() < EPSILON {
+        return None
+    }
+    let inv_determinant = 1.0 / determinant;
+
+    // Calculate distance and barycentric coordinates
+    let v3 = origin - p0;
+    let v3dirc = v3.cross( dir );
+    let ( dist, u, v ) = (
+        v3.dot( &v0v1c ) * inv_determinant,
+        v1.dot( &v3dirc ) * inv_determinant,
+        -v0.dot( &v3dirc ) * inv_determinant,
+    );
+
+    if dist >= 0.0 && u >= 0.0 && v >= 0.0 && u + v <= 1.0 { Some( ( dist, u, v ) ) } else { None }
+}
+
+/**
+ * Checks for an intersection between a ray and a bounding box.
+ * Mirrors RayBoundingBox() in the raytracing shader.
+ *
+ * @param origin The origin of the ray.
+ * @param dir The direction of the ray.
+ * @param box_min The bottom left corner of the box.
+ * @param box_max The top right corner of the box.
+ *
+ * @return If the ray intersects the box at all.
+ */
+pub fn ray_bounding_box( origin: &glm::Vec3, dir: &glm::Vec3, box_min: &glm::Vec3, box_max: &glm::Vec3 ) -> bool {
+    let dir_inverted = glm::vec3( 1.0 / dir.x, 1.0 / dir.y, 1.0 / dir.z );
+    let ( box_min_relative, box_max_relative ) = (
+        ( box_min - origin ).component_mul( &dir_inverted ),
+        ( box_max - origin ).component_mul( &dir_inverted ),
+    );
+    let ( box_min_new, box_max_new ) = (
+        glm::min2( &box_min_relative, &box_max_relative ),
+        glm::max2( &box_min_relative, &box_max_relative ),
+    );
+
+    box_min_new.max() <= box_max_new.min()
+}
+
+/**
+ * Casts a ray against the flattened scene, finding the closest intersection.
+ * Mirrors CalculateRayCollision() in the raytracing shader, at the time the shutter opens (motion is ignored).
+ *
+ * @param origin The origin of the ray.
+ * @param dir The direction of the ray. Does not need to be normalized, but the distance is measured in its length.
+ * @param scene The flattened scene.
+ *
+ * @return The closest hit, or None if the ray hit nothing.
+ */
+pub fn cast_ray( origin: &glm::Vec3, dir: &glm::Vec3, scene: &FlatScene ) -> Option<RayHit> {
+    let mut closest: Option<RayHit> = None;
+    let is_closer = |closest: &Option<RayHit>, dist: f32| closest.is_none_or( |hit| dist < hit.dist );
+
+    // Raycast spheres
+    for ( index, sphere ) in scene.spheres.iter().enumerate() {
+        if let Some( dist ) = ray_sphere( origin, dir, sphere ) {
+            if is_closer( &closest, dist ) {
+                let pos = origin + dir * dist;
+                closest = Some( RayHit {
+                    dist,
+                    pos,
+                    normal: ( pos - glm::Vec3::from( sphere.center ) ).normalize(),
+                    primitive: Primitive::Sphere { index },
+                    object_id: sphere.object_id,
+                    material_id: sphere.material_id,
+                } );
+            }
+        }
+    }
+
+    // Raycast meshes (triangles)
+    for ( mesh_index, mesh ) in scene.meshes.iter().enumerate() {
+        if !ray_bounding_box( origin, dir, &mesh.boundingbox_min.into(), &mesh.boundingbox_max.into() ) {
+            continue;
+        }
+
+        for index in mesh.start_index as usize..( mesh.start_index + mesh.count ) as usize {
+            let triangle = &scene.triangles[index];
+            if let Some( ( dist, u, v ) ) = ray_triangle( origin, dir, &scene.triangle_positions( triangle ) ) {
+                if is_closer( &closest, dist ) {
+                    let [ normal0, normal1, normal2 ] = scene.triangle_normals( triangle );
+                    let normal = normal0 * ( 1.0 - u - v ) + normal1 * u + normal2 * v;
+                    closest = Some( RayHit {
+                        dist,
+                        pos: origin + dir * dist,
+                        normal: normal.normalize(),
+                        primitive: Primitive::Triangle { mesh: mesh_index, index },
+                        object_id: mesh.object_id,
+                        material_id: triangle.material_id,
+                    } );
+                }
+            }
+        }
+    }
+
+    closest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raytracing::{RTTriangle, RTMeshInfo, NO_MOTION};
+    use crate::scene::{Geometry, Scene, Transform};
+
+    /**
+     * Creates a flattened scene with a sphere of radius 1 at (0, 0, -5) (material 3),
+     * and a square of two triangles facing +Z at z = -10, spanning [-4, 4] in X and Y (material 7).
+     */
+    fn scene() -> FlatScene {
+        let mut scene = Scene::new();
+        scene.add_node( "ball", None, Transform::from_translation( glm::vec3( 0.0, 0.0, -5.0 ) ), Geometry::Sphere { radius: 1.0, material_id: 3 } );
+        scene.add_node( "wall", None, Transform::from_translation( glm::vec3( 0.0, 0.0, -10.0 ) ), Geometry::Mesh {
+            positions: vec![ glm::vec3( -4.0, -4.0, 0.0 ), glm::vec3( 4.0, -4.0, 0.0 ), glm::vec3( 4.0, 4.0, 0.0 ), glm::vec3( -4.0, 4.0, 0.0 ) ],
+            normals: vec![ glm::vec3( 0.0, 0.0, 1.0 ); 4 ],
+            colors: vec![ glm::vec4( 1.0, 1.0, 1.0, 1.0 ); 4 ],
+            triangles: vec![
+                RTTriangle { vertices: [ 0, 1, 2 ], material_id: 7 },
+                RTTriangle { vertices: [ 0, 2, 3 ], material_id: 7 },
+            ],
+            meshes: vec![ RTMeshInfo {
+                start_index: 0,
+                count: 2,
+                object_id: 0,
+                motion_index: NO_MOTION,
+                boundingbox_min: glm::Vec3::zeros().into(),
+                boundingbox_max: glm::Vec3::zeros().into(),
+            } ],
+        } );
+        scene.flatten().unwrap()
+    }
+
+    /**
+     * Asserts that two vectors are (almost) equal.
+     */
+    fn assert_vec3_eq( actual: glm::Vec3, expected: glm::Vec3 ) {
+        assert!( ( actual - expected ).norm() < 1e-4, "expected {expected:?}, got {actual:?}" );
+    }
+
+    #[test]
+    fn spheres_are_hit_at_their_surface() {
+        let sphere = RTSphere { radius: 2.0, center: glm::vec3( 0.0, 0.0, -10.0 ).into(), ..RTSphere::new() };
+        let origin = glm::Vec3::zeros();
+        assert_eq!( ray_sphere( &origin, &glm::vec3( 0.0, 0.0, -1.0 ), &sphere ), Some( 8.0 ) );
+        assert_eq!( ray_sphere( &origin, &glm::vec3( 0.0, 0.0, 1.0 ), &sphere ), None );
+        assert_eq!( ray_sphere( &origin, &glm::vec3( 0.0, 1.0, 0.0 ), &sphere ), None );
+
+        // The distance is measured in the length of the direction
+        assert_eq!( ray_sphere( &origin, &glm::vec3( 0.0, 0.0, -2.0 ), &sphere ), Some( 4.0 ) );
+    }
+
+    #[test]
+    fn triangles_are_only_hit_from_the_front() {
+        let positions = [ glm::vec3( 0.0, 0.0, -5.0 ), glm::vec3( 1.0, 0.0, -5.0 ), glm::vec3( 0.0, 1.0, -5.0 ) ];
+        let ( dist, u, v ) = ray_triangle( &glm::vec3( 0.25, 0.5, 0.0 ), &glm::vec3( 0.0, 0.0, -1.0 ), &positions ).unwrap();
+        assert!( ( dist - 5.0 ).abs() < 1e-5 );
+        assert!( ( u - 0.25 ).abs() < 1e-5 && ( v - 0.5 ).abs() < 1e-5 );
+
+        // The same triangle seen from behind is culled, and points outside of it are missed
+        assert!( ray_triangle( &glm::vec3( 0.25, 0.5, -10.0 ), &glm::vec3( 0.0, 0.0, 1.0 ), &positions ).is_none() );
+        assert!( ray_triangle( &glm::vec3( 0.75, 0.75, 0.0 ), &glm::vec3( 0.0, 0.0, -1.0 ), &positions ).is_none() );
+    }
+
+    #[test]
+    fn bounding_boxes_are_hit_or_missed() {
+        let ( box_min, box_max ) = ( glm::vec3( -1.0, -1.0, -6.0 ), glm::vec3( 1.0, 1.0, -4.0 ) );
+        assert!( ray_bounding_box( &glm::Vec3::zeros(), &glm::vec3( 0.0, 0.0, -1.0 ), &box_min, &box_max ) );
+        assert!( ray_bounding_box( &glm::vec3( 0.0, 3.0, 0.0 ), &glm::vec3( 0.0, -0.6, -1.0 ), &box_min, &box_max ) );
+        assert!( !ray_bounding_box( &glm::vec3( 0.0, 3.0, 0.0 ), &glm::vec3( 0.0, 0.0, -1.0 ), &box_min, &box_max ) );
+        assert!( !ray_bounding_box( &glm::vec3( 3.0, 0.0, 0.0 ), &glm::vec3( 0.0, 0.0, -1.0 ), &box_min, &box_max ) );
+    }
+
+    #[test]
+    fn the_closest_hit_is_returned() {
+        let scene = scene();
+
+        // Straight ahead, the sphere is in front of the wall
+        let hit = cast_ray( &glm::Vec3::zeros(), &glm::vec3( 0.0, 0.0, -1.0 ), &scene ).unwrap();
+        assert!( hit.primitive == Primitive::Sphere { index: 0 } );
+        assert!( ( hit.dist - 4.0 ).abs() < 1e-5 );
+        assert_vec3_eq( hit.pos, glm::vec3( 0.0, 0.0, -4.0 ) );
+        assert_vec3_eq( hit.normal, glm::vec3( 0.0, 0.0, 1.0 ) );
+
+        // Next to the sphere, the wall is hit
+        let hit = cast_ray( &glm::vec3( 2.0, 1.0, 0.0 ), &glm::vec3( 0.0, 0.0, -1.0 ), &scene ).unwrap();
+        assert!( matches!( hit.primitive, Primitive::Triangle { mesh: 0, .. } ) );
+        assert!( ( hit.dist - 10.0 ).abs() < 1e-5 );
+        assert_vec3_eq( hit.normal, glm::vec3( 0.0, 0.0, 1.0 ) );
+
+        // Past the wall, or away from everything, nothing is hit
+        assert!( cast_ray( &glm::vec3( 5.0, 0.0, 0.0 ), &glm::vec3( 0.0, 0.0, -1.0 ), &scene ).is_none() );
+        assert!( cast_ray( &glm::Vec3::zeros(), &glm::vec3( 0.0, 0.0, 1.0 ), &scene ).is_none() );
+    }
+
+    #[test]
+    fn hits_carry_the_object_and_material_ids() {
+        let scene = scene();
+
+        let hit = cast_ray( &glm::Vec3::zeros(), &glm::vec3( 0.0, 0.0, -1.0 ), &scene ).unwrap();
+        assert_eq!( ( hit.object_id, hit.material_id ), ( 1, 3 ) );
+
+        let hit = cast_ray( &glm::vec3( -2.0, 2.0, 0.0 ), &glm::vec3( 0.0, 0.0, -1.0 ), &scene ).unwrap();
+        assert_eq!( ( hit.object_id, hit.material_id ), ( 2, 7 ) );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::shader::Shader;
+use crate::camera::Camera;
 
 /**
  * Vec3 for GLSL, put after normal floats.
@@ -6,6 +7,7 @@ use crate::shader::Shader;
  * https://www.khronos.org/opengl/wiki/Interface_Block_(GLSL)#Memory_layout
  * https://stackoverflow.com/questions/38172696/should-i-ever-use-a-vec3-inside-of-a-uniform-buffer-or-shader-storage-buffer-o
  */
+#[derive(Clone, Copy, PartialEq)]
 #[repr(C, align(16))]
 pub struct Vec3a16 {
     pub x: f32,
@@ -33,14 +35,77 @@ impl From<Vec3a16> for glm::Vec3 {
     }
 }
 
+/**
+ * Enum for the debug view modes, which show information about the scene or the raytracer instead of the lit image.
+ * The values match the DEBUG_ constants in the raytracing shader.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DebugMode {
+    // Normal, lit rendering
+    Off = 0,
+    // World space normal of the first surface hit
+    Normals = 1,
+    // Barycentric coordinates of the first triangle hit
+    Barycentrics = 2,
+    // Heatmap of the amount of mesh bounding boxes a ray passed through
+    BoundingBoxes = 3,
+    // Heatmap of the amount of ray-triangle tests
+    TriangleTests = 4,
+    // Heatmap of the amount of surfaces a ray path hit
+    Bounces = 5,
+    // Distance to the first surface hit
+    HitDistance = 6,
+    // A random color for each material
+    MaterialId = 7,
+}
+
+/**
+ * DebugMode functions.
+ */
+impl DebugMode {
+    /**
+     * Gets the next mode, for cycling through them.
+     */
+    pub fn next( self ) -> DebugMode {
+        match self {
+            DebugMode::Off => DebugMode::Normals,
+            DebugMode::Normals => DebugMode::Barycentrics,
+            DebugMode::Barycentrics => DebugMode::BoundingBoxes,
+            DebugMode::BoundingBoxes => DebugMode::TriangleTests,
+            DebugMode::TriangleTests => DebugMode::Bounces,
+            DebugMode::Bounces => DebugMode::HitDistance,
+            DebugMode::HitDistance => DebugMode::MaterialId,
+            DebugMode::MaterialId => DebugMode::Off,
+        }
+    }
+
+    /**
+     * Gets the name of the mode.
+     */
+    pub fn name( self ) -> &'static str {
+        match self {
+            DebugMode::Off => "off",
+            DebugMode::Normals => "normals",
+            DebugMode::Barycentrics => "barycentrics",
+            DebugMode::BoundingBoxes => "bounding-boxes",
+            DebugMode::TriangleTests => "triangle-tests",
+            DebugMode::Bounces => "bounces",
+            DebugMode::HitDistance => "hit-distance",
+            DebugMode::MaterialId => "material-id",
+        }
+    }
+}
+
 /**
  * Struct for storing raytracing settings.
  */
+#[derive(Clone, Copy, PartialEq)]
 #[repr(C, align(16))]
 pub struct RTSettings {
     pub max_bounces: u32,
     pub rays_per_frag: u32,
     pub diverge_strength: f32,
+    pub debug_mode: DebugMode,
 }
 
 /**
@@ -63,6 +128,7 @@ impl RTSettings {
         gl::Uniform1ui( shader.get_uniform_location( format!("{uniform_name}.maxBounces").as_str() ), self.max_bounces);
         gl::Uniform1ui( shader.get_uniform_location( format!("{uniform_name}.raysPerFrag").as_str() ), self.rays_per_frag);
         gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.divergeStrength").as_str() ), self.diverge_strength);
+        gl::Uniform1ui( shader.get_uniform_location( format!("{uniform_name}.debugMode").as_str() ), self.debug_mode as u32);
         
         // Switch back and return
         gl::UseProgram( prev_pid as u32 );
@@ -70,14 +136,180 @@ impl RTSettings {
 }
 
 /**
- * Struct for a raytracing material.
+ * Struct for the environment, the light coming from rays which hit nothing.
+ * It is a gradient from the ground to the horizon and the zenith of the sky, with a sun.
+ */
+#[derive(Clone, Copy, PartialEq)]
+pub struct RTEnvironment {
+    // When disabled, the environment is black and the scene is only lit by emissive objects
+    pub enabled: bool,
+    pub sky_color_horizon: glm::Vec3,
+    pub sky_color_zenith: glm::Vec3,
+    pub ground_color: glm::Vec3,
+    // Position of the sun, in degrees
+    pub sun_elevation: f32,
+    pub sun_azimuth: f32,
+    // Higher values give a smaller sun
+    pub sun_focus: f32,
+    pub sun_intensity: f32,
+}
+
+/**
+ * RTEnvironment functions.
+ */
+impl RTEnvironment {
+    /**
+     * Creates the default environment: a disabled daylight sky.
+     */
+    pub fn new() -> RTEnvironment {
+        RTEnvironment {
+            enabled: false,
+            sky_color_horizon: glm::vec3( 1.0, 1.0, 1.0 ),
+            sky_color_zenith: glm::vec3( 0.08, 0.37, 0.73 ),
+            ground_color: glm::vec3( 0.35, 0.3, 0.35 ),
+            sun_elevation: 45.0,
+            sun_azimuth: 30.0,
+            sun_focus: 500.0,
+            sun_intensity: 10.0,
+        }
+    }
+
+    /**
+     * Calculates the direction towards the sun.
+     *
+     * @return The normalized direction.
+     */
+    pub fn sun_direction( &self ) -> glm::Vec3 {
+        let ( elevation, azimuth ) = ( self.sun_elevation.to_radians(), self.sun_azimuth.to_radians() );
+        glm::vec3( elevation.cos() * azimuth.sin(), elevation.sin(), elevation.cos() * azimuth.cos() )
+    }
+
+    /**
+     * Sends the RTEnvironment's data to a uniform variable in a given shader.
+     *
+     * @param shader The shader.
+     * @param uniform_name The name of the uniform variable in the shader.
+     */
+    pub unsafe fn send_uniform( self, shader: &Shader, uniform_name: &str ) {
+        // Temporarily switch to the shader we're setting uniforms for
+        let mut prev_pid: gl::types::GLint = 0;
+        gl::GetIntegerv(gl::CURRENT_PROGRAM,&mut prev_pid);
+        shader.activate();
+
+        // Set uniforms
+        let sun_direction = self.sun_direction();
+        gl::Uniform1i( shader.get_uniform_location( format!("{uniform_name}.enabled").as_str() ), self.enabled as i32);
+        gl::Uniform3f( shader.get_uniform_location( format!("{uniform_name}.skyColorHorizon").as_str() ), self.sky_color_horizon.x, self.sky_color_horizon.y, self.sky_color_horizon.z);
+        gl::Uniform3f( shader.get_uniform_location( format!("{uniform_name}.skyColorZenith").as_str() ), self.sky_color_zenith.x, self.sky_color_zenith.y, self.sky_color_zenith.z);
+        gl::Uniform3f( shader.get_uniform_location( format!("{uniform_name}.groundColor").as_str() ), self.ground_color.x, self.ground_color.y, self.ground_color.z);
+        gl::Uniform3f( shader.get_uniform_location( format!("{uniform_name}.sunDirection").as_str() ), sun_direction.x, sun_direction.y, sun_direction.z);
+        gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.sunFocus").as_str() ), self.sun_focus);
+        gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.sunIntensity").as_str() ), self.sun_intensity);
+
+        // Switch back and return
+        gl::UseProgram( prev_pid as u32 );
+    }
+}
+
+/**
+ * Enum for the models used to scatter light off a material.
+ * The values match the MATERIAL_ constants in the raytracing shader, and it is stored as a u32 in the material buffers.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[repr(u32)]
+pub enum MaterialKind {
+    // The original model: a diffuse or (with the specular chance) a specular bounce, blended by the smoothness
+    Legacy = 0,
+    // Energy conserving Lambert diffuse and GGX microfacet specular, set by the roughness and metallic values
+    Pbr = 1,
+}
+
+/**
+ * MaterialKind functions.
+ */
+impl MaterialKind {
+    /**
+     * Gets the next kind, for cycling through them.
+     */
+    pub fn next( self ) -> MaterialKind {
+        match self {
+            MaterialKind::Legacy => MaterialKind::Pbr,
+            MaterialKind::Pbr => MaterialKind::Legacy,
+        }
+    }
+
+    /**
+     * Gets the name of the kind.
+     */
+    pub fn name( self ) -> &'static str {
+        match self {
+            MaterialKind::Legacy => "legacy",
+            MaterialKind::Pbr => "pbr",
+        }
+    }
+}
+
+/**
+ * Enum for the optional layers and lobes of PBR materials, each enabled by a bit in the material's features.
+ * Features which are not enabled are skipped by the shader, so they cost nothing.
+ * The values match the FEATURE_ constants in the raytracing shader.
+ */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum MaterialFeature {
+    // A smooth dielectric layer on top of the material, like the varnish of car paint
+    Clearcoat = 1,
+    // Extra reflection at grazing angles, like the fibers of cloth
+    Sheen = 2,
+    // Interference in a thin layer on the surface, like oil or soap bubbles, which colors the specular reflection by angle
+    ThinFilm = 4,
+    // Light entering the surface and scattering inside the object before leaving it, like skin or wax. Replaces the diffuse lobe
+    Subsurface = 8,
+}
+
+// All material features, in order
+pub const MATERIAL_FEATURES: [MaterialFeature; 4] = [MaterialFeature::Clearcoat, MaterialFeature::Sheen, MaterialFeature::ThinFilm, MaterialFeature::Subsurface];
+
+/**
+ * MaterialFeature functions.
+ */
+impl MaterialFeature {
+    /**
+     * Gets the name of the feature.
+     */
+    pub fn name( self ) -> &'static str {
+        match self {
+            MaterialFeature::Clearcoat => "clearcoat",
+            MaterialFeature::Sheen => "sheen",
+            MaterialFeature::ThinFilm => "thin-film",
+            MaterialFeature::Subsurface => "subsurface",
+        }
+    }
+}
+
+/**
+ * Struct for a raytracing material, stored in the material table (see MaterialLibrary) and referred to by its id.
+ * Legacy materials use the specular color and smoothness, PBR materials use the roughness and metallic values instead.
+ * The color and emission are used by both.
+ * The remaining values belong to the features of PBR materials, and are only used when their feature is enabled.
  */
+#[derive(Clone, Copy, Debug)]
 #[repr(C, align(16))]
 pub struct RTMaterial {
     pub color: glm::Vec4,
     pub emission_color: glm::Vec4,
     pub specular_color: glm::Vec4,
     pub smoothness: f32,
+    pub roughness: f32,
+    pub metallic: f32,
+    pub kind: MaterialKind,
+    pub sheen_color: glm::Vec4,         // Strength in the alpha
+    pub subsurface_color: glm::Vec4,    // Color of the light scattered inside the object, mean free path (world units) in the alpha
+    pub clearcoat: f32,                 // Strength of the clearcoat layer
+    pub clearcoat_roughness: f32,
+    pub clearcoat_ior: f32,
+    pub features: u32,                  // MaterialFeature bits
+    pub thin_film_thickness: f32,       // In nanometers
+    pub thin_film_ior: f32,
 }
 
 /**
@@ -86,73 +318,130 @@ pub struct RTMaterial {
 impl RTMaterial {
     /**
      * Creates a new, blank, RTMaterial.
+     * No features are enabled, but their values are set to typical ones so that enabling a feature has a visible effect.
      */
     pub fn new() -> RTMaterial {
-        RTMaterial { color: glm::zero(), emission_color: glm::zero(), specular_color: glm::zero(), smoothness: 0.0 }
+        RTMaterial {
+            color: glm::zero(),
+            emission_color: glm::zero(),
+            specular_color: glm::zero(),
+            smoothness: 0.0,
+            roughness: 0.0,
+            metallic: 0.0,
+            kind: MaterialKind::Legacy,
+            sheen_color: glm::vec4( 1.0, 1.0, 1.0, 1.0 ),
+            subsurface_color: glm::vec4( 1.0, 0.8, 0.7, 0.1 ),
+            clearcoat: 1.0,
+            clearcoat_roughness: 0.05,
+            clearcoat_ior: 1.5,
+            features: 0,
+            thin_film_thickness: 300.0,
+            thin_film_ior: 1.8,
+        }
+    }
+
+    /**
+     * Creates a PBR material, which does not emit light.
+     *
+     * @param color The base color: the diffuse color of dielectrics, or the reflected color of metals.
+     * @param roughness The perceptual roughness, within [0, 1].
+     * @param metallic How metallic the material is, within [0, 1].
+     */
+    pub fn pbr( color: glm::Vec3, roughness: f32, metallic: f32 ) -> RTMaterial {
+        RTMaterial {
+            color: glm::vec4( color.x, color.y, color.z, 1.0 ),
+            roughness,
+            metallic,
+            kind: MaterialKind::Pbr,
+            ..RTMaterial::new()
+        }
+    }
+
+    /**
+     * Creates a material which only emits light, for lights.
+     *
+     * @param color The color of the light.
+     * @param strength The strength of the light.
+     */
+    pub fn emissive( color: glm::Vec3, strength: f32 ) -> RTMaterial {
+        RTMaterial {
+            color: glm::vec4( color.x, color.y, color.z, 1.0 ),
+            emission_color: glm::vec4( color.x, color.y, color.z, strength ),
+            ..RTMaterial::new()
+        }
+    }
+
+    /**
+     * Checks if a feature is enabled.
+     */
+    pub fn has_feature( &self, feature: MaterialFeature ) -> bool {
+        self.features & feature as u32 != 0
+    }
+
+    /**
+     * Enables or disables a feature.
+     */
+    pub fn set_feature( &mut self, feature: MaterialFeature, enabled: bool ) {
+        if enabled {
+            self.features |= feature as u32;
+        } else {
+            self.features &= !( feature as u32 );
+        }
     }
 }
 
 /**
  * Struct for a raytraced sphere.
  */
+#[derive(Clone, Copy)]
 #[repr(C, align(16))]
 pub struct RTSphere {
     pub radius: f32,
+    pub object_id: u32,
+    pub material_id: u32,
     pub center: Vec3a16,
-    pub material: RTMaterial,
+    pub center_end: Vec3a16,
 }
 
 /**
  * RTSphere functions.
  */
+#[allow(dead_code)]
 impl RTSphere {
     /**
      * Creates a new, blank, RTSphere.
      */
     pub fn new() -> RTSphere {
-        RTSphere { radius: 0.0, center: glm::vec3(0.0, 0.0, 0.0).into(), material: RTMaterial::new() }
+        RTSphere { radius: 0.0, object_id: 0, material_id: 0, center: glm::vec3(0.0, 0.0, 0.0).into(), center_end: glm::vec3(0.0, 0.0, 0.0).into() }
     }
 }
 
 // RTTriangle
+// (The vertices are indices into the vertex buffers: 3 tightly packed floats per vertex in the position and normal buffers, and a vec4 in the color buffer)
+#[derive(Clone, Copy)]
 #[repr(C, align(16))]
 pub struct RTTriangle {
-    pub p0: Vec3a16,
-    pub p1: Vec3a16,
-    pub p2: Vec3a16,
-    pub normal0: Vec3a16,
-    pub normal1: Vec3a16,
-    pub normal2: Vec3a16,
-    pub material: RTMaterial,
+    pub vertices: [u32; 3],
+    pub material_id: u32,
 }
 
-/**
- * RTTriangle functions.
- */
-impl RTTriangle {
-    /**
-     * Creates a new, blank, RTTriangle.
-     */
-    pub fn new() -> RTTriangle {
-        RTTriangle { 
-            p0: glm::Vec3::zeros().into(), 
-            p1: glm::Vec3::zeros().into(), 
-            p2: glm::Vec3::zeros().into(), 
-            normal0: glm::Vec3::zeros().into(), 
-            normal1: glm::Vec3::zeros().into(), 
-            normal2: glm::Vec3::zeros().into(), 
-            material: RTMaterial::new(),
-        }
-    }
-}
+// Amount of matrices each moving mesh has in the motion buffer, spread evenly over the shutter interval
+pub const MOTION_SAMPLES: usize = 9;
+// Motion index of meshes which do not move while the shutter is open
+pub const NO_MOTION: u32 = u32::MAX;
 
 /**
  * Struct for holding mesh information.
+ * Moving meshes refer to MOTION_SAMPLES matrices in the motion buffer, starting at the motion index,
+ * which transform world space at each sample time into world space when the shutter opens (where the vertices are).
  */
+#[derive(Clone, Copy)]
 #[repr(C, align(16))]
 pub struct RTMeshInfo {
     pub start_index: u32,
     pub count: u32,
+    pub object_id: u32,
+    pub motion_index: u32,
     pub boundingbox_min: Vec3a16,
     pub boundingbox_max: Vec3a16,
 }
@@ -160,11 +449,20 @@ pub struct RTMeshInfo {
 /**
  * Struct for a raytracing camera.
  */
+#[derive(Clone, Copy, PartialEq)]
 #[repr(C, align(16))]
 pub struct RTCamera {
     pub screen_size: glm::Vec2,
     pub fov: f32,
     pub focus_distance: f32,
+    pub aperture: f32,
+    pub blade_count: u32,
+    pub anamorphic_ratio: f32,
+    pub projection: u32,
+    pub ortho_height: f32,
+    pub stereo_mode: u32,
+    pub ipd: f32,
+    pub convergence: f32,
     pub pos: Vec3a16,
     pub local_to_world: glm::Mat4,
 }
@@ -173,6 +471,30 @@ pub struct RTCamera {
  * Functions for dealing with the raytracing camera.
  */
 impl RTCamera {
+    /**
+     * Creates an RTCamera from a camera.
+     * 
+     * @param camera The camera.
+     * @param screen_size The size of the screen (or render target), in pixels.
+     */
+    pub fn from_camera( camera: &Camera, screen_size: glm::Vec2 ) -> RTCamera {
+        RTCamera {
+            screen_size,
+            fov: camera.fov(),
+            focus_distance: camera.focus_distance(),
+            aperture: camera.aperture(),
+            blade_count: camera.blade_count(),
+            anamorphic_ratio: camera.anamorphic_ratio(),
+            projection: camera.projection() as u32,
+            ortho_height: camera.ortho_height(),
+            stereo_mode: camera.stereo_mode() as u32,
+            ipd: camera.ipd(),
+            convergence: camera.convergence(),
+            pos: camera.pos().into(),
+            local_to_world: camera.rts(),
+        }
+    }
+
     /**
      * Sends the RTCamera's data to a uniform variable in a given shader.
      * 
@@ -189,6 +511,14 @@ impl RTCamera {
         gl::Uniform2f( shader.get_uniform_location( format!("{uniform_name}.screenSize").as_str() ), self.screen_size.x, self.screen_size.y);
         gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.fov").as_str() ), self.fov);
         gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.focusDistance").as_str() ), self.focus_distance);
+        gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.aperture").as_str() ), self.aperture);
+        gl::Uniform1ui( shader.get_uniform_location( format!("{uniform_name}.bladeCount").as_str() ), self.blade_count);
+        gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.anamorphicRatio").as_str() ), self.anamorphic_ratio);
+        gl::Uniform1ui( shader.get_uniform_location( format!("{uniform_name}.projection").as_str() ), self.projection);
+        gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.orthoHeight").as_str() ), self.ortho_height);
+        gl::Uniform1ui( shader.get_uniform_location( format!("{uniform_name}.stereoMode").as_str() ), self.stereo_mode);
+        gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.ipd").as_str() ), self.ipd);
+        gl::Uniform1f( shader.get_uniform_location( format!("{uniform_name}.convergence").as_str() ), self.convergence);
         gl::Uniform3f( shader.get_uniform_location( format!("{uniform_name}.pos").as_str() ), self.pos.x, self.pos.y, self.pos.z);
         shader.set_uniform_mat4( format!("{uniform_name}.localToWorld").as_str(), self.local_to_world);
 
@@ -0,0 +1,642 @@
+use std::collections::HashMap;
+
+use crate::raytracing::{RTSphere, RTTriangle, RTMeshInfo, MOTION_SAMPLES, NO_MOTION};
+
+/**
+ * Struct for a local transformation.
+ * The rotation is given as euler angles (radians), and is applied in the same order as the camera's (Y * X * Z).
+ */
+#[derive(Clone, Copy)]
+pub struct Transform {
+    pub translation: glm::Vec3,
+    pub rotation: glm::Vec3,
+    pub scale: glm::Vec3,
+}
+
+/**
+ * Transform functions.
+ */
+impl Transform {
+    /**
+     * Creates a new, identity, transform.
+     */
+    pub fn new() -> Transform {
+        Transform { translation: glm::zero(), rotation: glm::zero(), scale: glm::vec3(1.0, 1.0, 1.0) }
+    }
+
+    /**
+     * Creates a transform which only translates.
+     *
+     * @param translation The translation.
+     */
+    pub fn from_translation( translation: glm::Vec3 ) -> Transform {
+        Transform { translation, ..Transform::new() }
+    }
+
+    /**
+     * Calculates the transformation matrix (translation * rotation * scale).
+     *
+     * @return The transformation matrix.
+     */
+    pub fn matrix( &self ) -> glm::Mat4 {
+        let ( rotation_x, rotation_y, rotation_z ) = (
+            glm::rotation(self.rotation.x, &glm::vec3(1.0, 0.0, 0.0)),
+            glm::rotation(self.rotation.y, &glm::vec3(0.0, 1.0, 0.0)),
+            glm::rotation(self.rotation.z, &glm::vec3(0.0, 0.0, 1.0)),
+        );
+
+        glm::translation( &self.translation ) * rotation_y * rotation_x * rotation_z * glm::scaling( &self.scale )
+    }
+
+    /**
+     * Interpolates linearly between two transforms, component by component.
+     *
+     * @param other The transform at t = 1.
+     * @param t The interpolation factor, within [0, 1].
+     *
+     * @return The interpolated transform.
+     */
+    pub fn lerp( &self, other: &Transform, t: f32 ) -> Transform {
+        Transform {
+            translation: glm::lerp( &self.translation, &other.translation, t ),
+            rotation: glm::lerp( &self.rotation, &other.rotation, t ),
+            scale: glm::lerp( &self.scale, &other.scale, t ),
+        }
+    }
+}
+
+/**
+ * Enum for the geometry attached to a scene node.
+ * Materials are referred to by their id in the MaterialLibrary.
+ */
+pub enum Geometry {
+    // Nodes without geometry are only used for grouping/transforming their children
+    Empty,
+    Sphere { radius: f32, material_id: u32 },
+    // The vertices are given in the node's local space, and the triangles index into them, as generated by Model::generate_raytracing_structs()
+    Mesh { positions: Vec<glm::Vec3>, normals: Vec<glm::Vec3>, colors: Vec<glm::Vec4>, triangles: Vec<RTTriangle>, meshes: Vec<RTMeshInfo> },
+    // Lights are spheres with an emissive material (see RTMaterial::emissive())
+    Light { radius: f32, material_id: u32 },
+}
+
+/**
+ * Struct for a node in the scene graph.
+ */
+#[allow(dead_code)]
+pub struct SceneNode {
+    pub name: String,
+    pub transform: Transform,
+    pub geometry: Geometry,
+    parent: Option<usize>,
+    children: Vec<usize>,
+}
+
+/**
+ * SceneNode functions.
+ */
+#[allow(dead_code)]
+impl SceneNode {
+    // --- Getters
+    pub fn parent( &self )      -> Option<usize> { self.parent }
+    pub fn children( &self )    -> &[usize] { &self.children }
+}
+
+/**
+ * Struct for the flattened scene, ready to be sent to the GPU buffers.
+ * The vertices of all meshes are shared in world space, and referred to by the triangles' indices.
+ * The motion matrices of moving meshes are referred to by their motion index (see RTMeshInfo).
+ */
+pub struct FlatScene {
+    pub spheres: Vec<RTSphere>,
+    pub positions: Vec<glm::Vec3>,
+    pub normals: Vec<glm::Vec3>,
+    pub colors: Vec<glm::Vec4>,
+    pub triangles: Vec<RTTriangle>,
+    pub meshes: Vec<RTMeshInfo>,
+    pub motion: Vec<glm::Mat4>,
+}
+
+/**
+ * FlatScene functions.
+ */
+impl FlatScene {
+    /**
+     * Gets the positions of a triangle's vertices.
+     *
+     * @param triangle The triangle.
+     * @return The positions of its three vertices.
+     */
+    pub fn triangle_positions( &self, triangle: &RTTriangle ) -> [glm::Vec3; 3] {
+        triangle.vertices.map( |vertex| self.positions[vertex as usize] )
+    }
+
+    /**
+     * Gets the normals of a triangle's vertices.
+     *
+     * @param triangle The triangle.
+     * @return The normals of its three vertices.
+     */
+    pub fn triangle_normals( &self, triangle: &RTTriangle ) -> [glm::Vec3; 3] {
+        triangle.vertices.map( |vertex| self.normals[vertex as usize] )
+    }
+}
+
+/**
+ * Struct for a scene graph.
+ * Nodes are stored in a flat vector and referenced by index, or looked up by their (unique) name.
+ */
+pub struct Scene {
+    nodes: Vec<SceneNode>,
+    names: HashMap<String, usize>,
+    dirty: bool,
+    // Local transforms of the nodes when the shutter opens, used for motion blur
+    shutter_open: Option<Vec<Transform>>,
+}
+
+/**
+ * Scene functions.
+ */
+#[allow(dead_code)]
+impl Scene {
+    /**
+     * Creates a new, empty, scene.
+     */
+    pub fn new() -> Scene {
+        Scene { nodes: Vec::new(), names: HashMap::new(), dirty: true, shutter_open: None }
+    }
+
+    /**
+     * Adds a node to the scene.
+     *
+     * @param name The name of the node. Must be unique within the scene.
+     * @param parent The name of the parent node, or None if the node should be placed at the root.
+     * @param transform The node's transformation relative to its parent.
+     * @param geometry The geometry attached to the node.
+     *
+     * @return The index of the new node.
+     */
+    pub fn add_node( &mut self, name: &str, parent: Option<&str>, transform: Transform, geometry: Geometry ) -> usize {
+        if self.names.contains_key( name ) {
+            panic!( "ERROR::SCENE::DUPLICATE_NODE_NAME\n{}", name );
+        }
+        let parent_index = parent.map( |parent_name| self.index_of( parent_name )
+            .unwrap_or_else( || panic!( "ERROR::SCENE::PARENT_NOT_FOUND\n{}", parent_name ) ) );
+
+        // Insert node and register it with its parent
+        let index = self.nodes.len();
+        self.nodes.push( SceneNode {
+            name: name.to_string(),
+            transform,
+            geometry,
+            parent: parent_index,
+            children: Vec::new(),
+        } );
+        self.names.insert( name.to_string(), index );
+        if let Some( parent_index ) = parent_index {
+            self.nodes[parent_index].children.push( index );
+        }
+
+        self.dirty = true;
+        index
+    }
+
+    /**
+     * Moves a node to a new parent, keeping its local transform.
+     *
+     * @param name The name of the node.
+     * @param parent The name of the new parent, or None to move the node to the root.
+     *
+     * @return Ok if the node was moved, an error message otherwise.
+     */
+    pub fn set_parent( &mut self, name: &str, parent: Option<&str> ) -> Result<(), String> {
+        let index = self.index_of( name ).ok_or( format!( "Node '{name}' does not exist" ) )?;
+        let parent_index = match parent {
+            Some( parent_name ) => Some( self.index_of( parent_name ).ok_or( format!( "Node '{parent_name}' does not exist" ) )? ),
+            None => None,
+        };
+
+        // Make sure the new parent is not the node itself or one of its descendants
+        let mut ancestor = parent_index;
+        while let Some( ancestor_index ) = ancestor {
+            if ancestor_index == index {
+                return Err( format!( "Node '{name}' cannot be parented to itself or its descendants" ) );
+            }
+            ancestor = self.nodes[ancestor_index].parent;
+        }
+
+        // Detach from old parent and attach to the new one
+        if let Some( old_parent ) = self.nodes[index].parent {
+            self.nodes[old_parent].children.retain( |&child| child != index );
+        }
+        if let Some( parent_index ) = parent_index {
+            self.nodes[parent_index].children.push( index );
+        }
+        self.nodes[index].parent = parent_index;
+
+        self.dirty = true;
+        Ok( () )
+    }
+
+    /**
+     * Gets the index of a node.
+     *
+     * @param name The name of the node.
+     * @return The index of the node, or None if it does not exist.
+     */
+    pub fn index_of( &self, name: &str ) -> Option<usize> {
+        self.names.get( name ).copied()
+    }
+
+    /**
+     * Gets a node by name.
+     *
+     * @param name The name of the node.
+     * @return The node, or None if it does not exist.
+     */
+    pub fn node( &self, name: &str ) -> Option<&SceneNode> {
+        self.index_of( name ).map( |index| &self.nodes[index] )
+    }
+
+    /**
+     * Gets a mutable node by name.
+     * Since the node may be changed, the scene is marked as dirty.
+     *
+     * @param name The name of the node.
+     * @return The node, or None if it does not exist.
+     */
+    pub fn node_mut( &mut self, name: &str ) -> Option<&mut SceneNode> {
+        let index = self.index_of( name )?;
+        self.dirty = true;
+        Some( &mut self.nodes[index] )
+    }
+
+    /**
+     * Gets all nodes in the scene, in the order they were added.
+     */
+    pub fn nodes( &self ) -> &[SceneNode] {
+        &self.nodes
+    }
+
+    /**
+     * Calculates the transformation of a node relative to the world, including all of its parents' transformations.
+     *
+     * @param index The index of the node.
+     * @return The node's local-to-world matrix.
+     */
+    pub fn world_matrix( &self, index: usize ) -> glm::Mat4 {
+        let mut matrix = self.nodes[index].transform.matrix();
+        let mut parent = self.nodes[index].parent;
+        while let Some( parent_index ) = parent {
+            matrix = self.nodes[parent_index].transform.matrix() * matrix;
+            parent = self.nodes[parent_index].parent;
+        }
+        matrix
+    }
+
+    /**
+     * Calculates the world matrix of a node at a time within the shutter interval.
+     * The local transforms of the node and its parents are interpolated from their pose when the shutter opens to their current pose,
+     * so children of rotating parents move along arcs.
+     *
+     * @param index The index of the node.
+     * @param time The time within the shutter interval, within [0, 1].
+     *
+     * @return The node's local-to-world matrix at that time, or its current one if no shutter pose has been stored.
+     */
+    pub fn world_matrix_at( &self, index: usize, time: f32 ) -> glm::Mat4 {
+        let transform_at = |index: usize| {
+            let transform = &self.nodes[index].transform;
+            self.shutter_open.as_ref().and_then( |transforms| transforms.get( index ) )
+                .map_or( *transform, |open| open.lerp( transform, time ) )
+        };
+
+        let mut matrix = transform_at( index ).matrix();
+        let mut parent = self.nodes[index].parent;
+        while let Some( parent_index ) = parent {
+            matrix = transform_at( parent_index ).matrix() * matrix;
+            parent = self.nodes[parent_index].parent;
+        }
+        matrix
+    }
+
+    /**
+     * Checks if the scene has changed since it was last flattened.
+     */
+    pub fn is_dirty( &self ) -> bool {
+        self.dirty
+    }
+
+    /**
+     * Marks the scene as changed, forcing the next flatten() to rebuild the buffers.
+     */
+    pub fn mark_dirty( &mut self ) {
+        self.dirty = true;
+    }
+
+    /**
+     * Stores the current pose of the scene as the pose when the camera's shutter opens.
+     * The pose when flattening is then used as the pose when the shutter closes, and objects are motion blurred between the two
+     * by interpolating the nodes' local transforms (see world_matrix_at()).
+     */
+    pub fn store_shutter_open( &mut self ) {
+        self.shutter_open = Some( self.nodes.iter().map( |node| node.transform ).collect() );
+        self.dirty = true;
+    }
+
+    /**
+     * Removes the pose stored by store_shutter_open(), disabling motion blur.
+     */
+    pub fn clear_shutter_open( &mut self ) {
+        if self.shutter_open.take().is_some() {
+            self.dirty = true;
+        }
+    }
+
+    /**
+     * Flattens the scene graph into the raytracing structs used by the shader.
+     * Nothing is done if the scene has not changed since the last time it was flattened.
+     * If a shutter pose has been stored, the structs are placed there, and carry their motion until the current pose:
+     * spheres move in a line to their end center, while meshes get MOTION_SAMPLES matrices over the shutter interval,
+     * following their rotation, scale and parents' motion too.
+     *
+     * @return The flattened scene, or None if the scene was not dirty.
+     */
+    pub fn flatten( &mut self ) -> Option<FlatScene> {
+        if !self.dirty {
+            return None
+        }
+        self.dirty = false;
+
+        let mut flat = FlatScene { spheres: Vec::new(), positions: Vec::new(), normals: Vec::new(), colors: Vec::new(), triangles: Vec::new(), meshes: Vec::new(), motion: Vec::new() };
+        for ( index, node ) in self.nodes.iter().enumerate() {
+            let ( world, world_end ) = ( self.world_matrix_at( index, 0.0 ), self.world_matrix( index ) );
+            match &node.geometry {
+                Geometry::Empty => { },
+
+                Geometry::Sphere { radius, material_id } | Geometry::Light { radius, material_id } => {
+                    flat.spheres.push( RTSphere {
+                        radius: radius * max_scale( &world ),
+                        object_id: index as u32 + 1,
+                        material_id: *material_id,
+                        center: transform_point( &world, &glm::Vec3::zeros() ).into(),
+                        center_end: transform_point( &world_end, &glm::Vec3::zeros() ).into(),
+                    } );
+                },
+
+                Geometry::Mesh { positions, normals, colors, triangles, meshes } => {
+                    // Normals are transformed by the inverse transpose to stay perpendicular under non-uniform scaling
+                    let normal_matrix = glm::transpose( &glm::inverse( &glm::mat4_to_mat3( &world ) ) );
+                    let ( vertex_offset, triangle_offset ) = ( flat.positions.len() as u32, flat.triangles.len() as u32 );
+
+                    // The vertices are placed where the mesh is when the shutter opens, so rays are moved there from where the mesh is at their time
+                    let motion_index = if world == world_end { NO_MOTION } else {
+                        let motion_index = flat.motion.len() as u32;
+                        flat.motion.extend( ( 0..MOTION_SAMPLES ).map( |sample| {
+                            let time = sample as f32 / ( MOTION_SAMPLES - 1 ) as f32;
+                            world * glm::inverse( &self.world_matrix_at( index, time ) )
+                        } ) );
+                        motion_index
+                    };
+
+                    flat.positions.extend( positions.iter().map( |position| transform_point( &world, position ) ) );
+                    flat.normals.extend( normals.iter().map( |normal| ( normal_matrix * normal ).normalize() ) );
+                    flat.colors.extend_from_slice( colors );
+                    flat.triangles.extend( triangles.iter().map( |triangle| RTTriangle {
+                        vertices: triangle.vertices.map( |vertex| vertex_offset + vertex ),
+                        material_id: triangle.material_id,
+                    } ) );
+
+                    // The bounding boxes must be recalculated, as the transformed box of the local bounding box is not tight
+                    for mesh in meshes {
+                        let start_index = triangle_offset + mesh.start_index;
+                        let ( mut boundingbox_min, mut boundingbox_max ) = (
+                            glm::vec3( f32::MAX, f32::MAX, f32::MAX ),
+                            glm::vec3( f32::MIN, f32::MIN, f32::MIN ),
+                        );
+                        for triangle in &flat.triangles[start_index as usize..(start_index + mesh.count) as usize] {
+                            for point in flat.triangle_positions( triangle ) {
+                                boundingbox_min = glm::min2( &boundingbox_min, &point );
+                                boundingbox_max = glm::max2( &boundingbox_max, &point );
+                            }
+                        }
+
+                        flat.meshes.push( RTMeshInfo {
+                            start_index,
+                            count: mesh.count,
+                            object_id: index as u32 + 1,
+                            motion_index,
+                            boundingbox_min: boundingbox_min.into(),
+                            boundingbox_max: boundingbox_max.into(),
+                        } );
+                    }
+                },
+            }
+        }
+
+        Some( flat )
+    }
+}
+
+/**
+ * Transforms a point by a matrix.
+ *
+ * @param matrix The transformation matrix.
+ * @param point The point.
+ *
+ * @return The transformed point.
+ */
+fn transform_point( matrix: &glm::Mat4, point: &glm::Vec3 ) -> glm::Vec3 {
+    ( matrix * glm::vec4( point.x, point.y, point.z, 1.0 ) ).xyz()
+}
+
+/**
+ * Gets the largest scaling factor of a matrix' axes.
+ * Used for scaling spheres, which cannot be scaled non-uniformly.
+ *
+ * @param matrix The transformation matrix.
+ * @return The largest scaling factor.
+ */
+fn max_scale( matrix: &glm::Mat4 ) -> f32 {
+    let ( x, y, z ) = (
+        matrix.column(0).xyz().norm(),
+        matrix.column(1).xyz().norm(),
+        matrix.column(2).xyz().norm(),
+    );
+    x.max( y ).max( z )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Creates a scene with a single triangle mesh at the node "mesh", parented to the node "parent".
+     */
+    fn scene_with_mesh( parent: Transform, mesh: Transform ) -> Scene {
+        let mut scene = Scene::new();
+        scene.add_node( "parent", None, parent, Geometry::Empty );
+        scene.add_node( "mesh", Some( "parent" ), mesh, Geometry::Mesh {
+            positions: vec![ glm::vec3( 0.0, 0.0, 0.0 ), glm::vec3( 1.0, 0.0, 0.0 ), glm::vec3( 0.0, 1.0, 0.0 ) ],
+            normals: vec![ glm::vec3( 0.0, 0.0, 1.0 ); 3 ],
+            colors: vec![ glm::vec4( 1.0, 1.0, 1.0, 1.0 ); 3 ],
+            triangles: vec![ RTTriangle { vertices: [ 0, 1, 2 ], material_id: 0 } ],
+            meshes: vec![ RTMeshInfo {
+                start_index: 0,
+                count: 1,
+                object_id: 0,
+                motion_index: NO_MOTION,
+                boundingbox_min: glm::Vec3::zeros().into(),
+                boundingbox_max: glm::Vec3::zeros().into(),
+            } ],
+        } );
+        scene
+    }
+
+    /**
+     * Gets the matrix moving points at a time into where they were when the shutter opened, from the flattened motion samples.
+     */
+    fn motion_sample( flat: &FlatScene, sample: usize ) -> glm::Mat4 {
+        flat.motion[flat.meshes[0].motion_index as usize + sample]
+    }
+
+    /**
+     * Asserts that two vectors are (almost) equal.
+     */
+    fn assert_vec3_eq( actual: glm::Vec3, expected: glm::Vec3 ) {
+        assert!( ( actual - expected ).norm() < 1e-4, "expected {expected:?}, got {actual:?}" );
+    }
+
+    #[test]
+    fn world_matrices_include_every_parent() {
+        let mut scene = Scene::new();
+        scene.add_node( "root", None, Transform::from_translation( glm::vec3( 10.0, 0.0, 0.0 ) ), Geometry::Empty );
+        scene.add_node( "arm", Some( "root" ), Transform {
+            translation: glm::vec3( 0.0, 2.0, 0.0 ),
+            rotation: glm::vec3( 0.0, 0.0, std::f32::consts::FRAC_PI_2 ),
+            scale: glm::vec3( 2.0, 2.0, 2.0 ),
+        }, Geometry::Empty );
+        let hand = scene.add_node( "hand", Some( "arm" ), Transform::from_translation( glm::vec3( 1.0, 0.0, 0.0 ) ), Geometry::Empty );
+
+        // The hand's origin is scaled and turned by the arm, then moved by the arm and the root
+        let world = scene.world_matrix( hand );
+        assert_vec3_eq( transform_point( &world, &glm::Vec3::zeros() ), glm::vec3( 10.0, 4.0, 0.0 ) );
+        assert_vec3_eq( transform_point( &world, &glm::vec3( 1.0, 0.0, 0.0 ) ), glm::vec3( 10.0, 6.0, 0.0 ) );
+        assert_eq!( scene.node( "arm" ).unwrap().children(), &[ hand ] );
+        assert_eq!( scene.node( "hand" ).unwrap().parent(), scene.index_of( "arm" ) );
+    }
+
+    #[test]
+    fn nodes_cannot_be_parented_to_their_descendants() {
+        let mut scene = Scene::new();
+        scene.add_node( "a", None, Transform::new(), Geometry::Empty );
+        scene.add_node( "b", Some( "a" ), Transform::new(), Geometry::Empty );
+        scene.add_node( "c", Some( "b" ), Transform::new(), Geometry::Empty );
+
+        assert!( scene.set_parent( "a", Some( "c" ) ).is_err() );
+        assert!( scene.set_parent( "a", Some( "a" ) ).is_err() );
+        assert!( scene.set_parent( "a", Some( "missing" ) ).is_err() );
+        assert!( scene.set_parent( "missing", None ).is_err() );
+        assert_eq!( scene.node( "a" ).unwrap().parent(), None );
+
+        // Moving a node updates the children of both its old and new parent
+        scene.set_parent( "c", Some( "a" ) ).unwrap();
+        let c = scene.index_of( "c" ).unwrap();
+        assert_eq!( scene.node( "c" ).unwrap().parent(), scene.index_of( "a" ) );
+        assert!( scene.node( "a" ).unwrap().children().contains( &c ) );
+        assert!( scene.node( "b" ).unwrap().children().is_empty() );
+    }
+
+    #[test]
+    #[should_panic( expected = "ERROR::SCENE::DUPLICATE_NODE_NAME" )]
+    fn node_names_must_be_unique() {
+        let mut scene = Scene::new();
+        scene.add_node( "a", None, Transform::new(), Geometry::Empty );
+        scene.add_node( "a", None, Transform::new(), Geometry::Empty );
+    }
+
+    #[test]
+    fn only_changed_scenes_are_flattened() {
+        let mut scene = scene_with_mesh( Transform::new(), Transform::new() );
+        assert!( scene.flatten().is_some() );
+        assert!( !scene.is_dirty() );
+        assert!( scene.flatten().is_none() );
+
+        // Looking nodes up does not change the scene, but borrowing them mutably might
+        scene.node( "mesh" );
+        assert!( scene.flatten().is_none() );
+        scene.node_mut( "mesh" ).unwrap().transform.translation = glm::vec3( 1.0, 0.0, 0.0 );
+        let flat = scene.flatten().unwrap();
+        assert_vec3_eq( flat.positions[0], glm::vec3( 1.0, 0.0, 0.0 ) );
+        assert!( scene.flatten().is_none() );
+    }
+
+    #[test]
+    fn static_meshes_have_no_motion() {
+        let mut scene = scene_with_mesh( Transform::new(), Transform::from_translation( glm::vec3( 1.0, 2.0, 3.0 ) ) );
+        let flat = scene.flatten().unwrap();
+        assert_eq!( flat.meshes[0].motion_index, NO_MOTION );
+        assert!( flat.motion.is_empty() );
+
+        scene.store_shutter_open();
+        let flat = scene.flatten().unwrap();
+        assert_eq!( flat.meshes[0].motion_index, NO_MOTION );
+        assert_vec3_eq( flat.positions[1], glm::vec3( 2.0, 2.0, 3.0 ) );
+    }
+
+    #[test]
+    fn moving_meshes_are_placed_where_the_shutter_opens() {
+        let mut scene = scene_with_mesh( Transform::new(), Transform::new() );
+        scene.store_shutter_open();
+        scene.node_mut( "mesh" ).unwrap().transform.translation = glm::vec3( 4.0, 0.0, 0.0 );
+        let flat = scene.flatten().unwrap();
+
+        assert_vec3_eq( flat.positions[1], glm::vec3( 1.0, 0.0, 0.0 ) );
+        assert_eq!( flat.meshes[0].motion_index, 0 );
+        assert_eq!( flat.motion.len(), MOTION_SAMPLES );
+
+        // Points where the mesh is at each sample time are moved back to where they were when the shutter opened
+        for sample in 0..MOTION_SAMPLES {
+            let offset = 4.0 * sample as f32 / ( MOTION_SAMPLES - 1 ) as f32;
+            assert_vec3_eq( transform_point( &motion_sample( &flat, sample ), &glm::vec3( 1.0 + offset, 0.0, 0.0 ) ), glm::vec3( 1.0, 0.0, 0.0 ) );
+        }
+    }
+
+    #[test]
+    fn rotating_meshes_are_blurred_by_their_rotation() {
+        let mut scene = scene_with_mesh( Transform::new(), Transform::from_translation( glm::vec3( 5.0, 0.0, 0.0 ) ) );
+        scene.store_shutter_open();
+        scene.node_mut( "mesh" ).unwrap().transform.rotation = glm::vec3( 0.0, 0.0, std::f32::consts::FRAC_PI_2 );
+        let flat = scene.flatten().unwrap();
+        assert_ne!( flat.meshes[0].motion_index, NO_MOTION );
+
+        // Halfway through, the vertex at (1, 0, 0) has turned 45 degrees around the node's origin, which stays in place
+        let half = ( MOTION_SAMPLES - 1 ) / 2;
+        let turned = glm::vec3( 5.0, 0.0, 0.0 ) + glm::vec3( 1.0, 1.0, 0.0 ).normalize();
+        assert_vec3_eq( transform_point( &motion_sample( &flat, half ), &turned ), glm::vec3( 6.0, 0.0, 0.0 ) );
+        assert_vec3_eq( transform_point( &motion_sample( &flat, half ), &glm::vec3( 5.0, 0.0, 0.0 ) ), glm::vec3( 5.0, 0.0, 0.0 ) );
+    }
+
+    #[test]
+    fn children_of_rotating_parents_move_along_arcs() {
+        let mut scene = scene_with_mesh( Transform::new(), Transform::from_translation( glm::vec3( 2.0, 0.0, 0.0 ) ) );
+        scene.store_shutter_open();
+        scene.node_mut( "parent" ).unwrap().transform.rotation = glm::vec3( 0.0, std::f32::consts::PI, 0.0 );
+        let flat = scene.flatten().unwrap();
+
+        // Halfway through, the child's origin has orbited a quarter turn around the parent, instead of passing through it
+        let half = ( MOTION_SAMPLES - 1 ) / 2;
+        let orbited = glm::vec3( 0.0, 0.0, -2.0 );
+        assert_vec3_eq( transform_point( &motion_sample( &flat, half ), &orbited ), glm::vec3( 2.0, 0.0, 0.0 ) );
+    }
+
+    #[test]
+    fn scaling_meshes_are_blurred_by_their_scale() {
+        let mut scene = scene_with_mesh( Transform::new(), Transform::new() );
+        scene.store_shutter_open();
+        scene.node_mut( "mesh" ).unwrap().transform.scale = glm::vec3( 3.0, 3.0, 3.0 );
+        let flat = scene.flatten().unwrap();
+
+        let last = motion_sample( &flat, MOTION_SAMPLES - 1 );
+        assert_vec3_eq( transform_point( &last, &glm::vec3( 0.0, 3.0, 0.0 ) ), glm::vec3( 0.0, 1.0, 0.0 ) );
+    }
+}
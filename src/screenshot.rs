@@ -0,0 +1,190 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::camera::Camera;
+use crate::postprocess::PostSettings;
+use crate::raytracing::RTSettings;
+
+/**
+ * Struct for the information needed to reproduce a screenshot.
+ */
+pub struct ScreenshotInfo<'a> {
+    pub camera: &'a Camera,
+    pub settings: &'a RTSettings,
+    pub post: &'a PostSettings,
+    pub samples: u32,
+    pub time: f32,
+}
+
+/**
+ * Saves a screenshot as a timestamped png, along with a json file describing how it was rendered.
+ *
+ * @param dir The directory to save the screenshot in. Created if it does not exist.
+ * @param pixels The pixels as RGBA bytes, starting at the top left corner.
+ * @param width The width of the screenshot, in pixels.
+ * @param height The height of the screenshot, in pixels.
+ * @param info The camera, settings and sample count used to render the screenshot.
+ *
+ * @return The path of the png, or an error message if it could not be saved.
+ */
+pub fn save_screenshot( dir: &Path, pixels: Vec<u8>, width: u32, height: u32, info: &ScreenshotInfo ) -> Result<PathBuf, String> {
+    std::fs::create_dir_all( dir ).map_err( |err| err.to_string() )?;
+    let path = dir.join( format!( "screenshot_{}.png", timestamp() ) );
+
+    // Save image
+    // (The alpha channel of the window is meaningless, so it is dropped)
+    let rgb: Vec<u8> = pixels.chunks_exact( 4 ).flat_map( |pixel| pixel[..3].to_vec() ).collect();
+    image::RgbImage::from_raw( width, height, rgb )
+        .ok_or( "The pixel buffer does not match the screenshot size".to_string() )?
+        .save( &path )
+        .map_err( |err| err.to_string() )?;
+
+    // Save metadata
+    // (Values are written with json_number and json_string, so the file is valid JSON even for non-finite numbers or unusual file names)
+    let ( camera, settings ) = ( info.camera, info.settings );
+    let json = format!(
+r#"{{
+    "image": {},
+    "resolution": [{}, {}],
+    "samples": {},
+    "time": {},
+    "camera": {{
+        "position": [{}, {}, {}],
+        "angle": [{}, {}, {}],
+        "fov": {},
+        "aperture": {},
+        "focus_distance": {},
+        "blade_count": {},
+        "anamorphic_ratio": {},
+        "projection": {},
+        "ortho_height": {},
+        "stereo_mode": {},
+        "ipd": {},
+        "convergence": {},
+        "shutter": [{}, {}]
+    }},
+    "settings": {{
+        "max_bounces": {},
+        "rays_per_frag": {},
+        "diverge_strength": {},
+        "debug_mode": {}
+    }},
+    "post": {{
+        "exposure": {},
+        "tone_mapping": {},
+        "temperature": {},
+        "tint": {},
+        "srgb": {}
+    }}
+}}
+"#,
+        json_string( &path.file_name().unwrap_or_default().to_string_lossy() ),
+        width, height,
+        info.samples,
+        json_number( info.time ),
+        json_number( camera.pos().x ), json_number( camera.pos().y ), json_number( camera.pos().z ),
+        json_number( camera.ang().x ), json_number( camera.ang().y ), json_number( camera.ang().z ),
+        json_number( camera.fov() ),
+        json_number( camera.aperture() ),
+        json_number( camera.focus_distance() ),
+        camera.blade_count(),
+        json_number( camera.anamorphic_ratio() ),
+        json_string( camera.projection().name() ),
+        json_number( camera.ortho_height() ),
+        json_string( camera.stereo_mode().name() ),
+        json_number( camera.ipd() ),
+        json_number( camera.convergence() ),
+        json_number( camera.shutter_open() ), json_number( camera.shutter_close() ),
+        settings.max_bounces,
+        settings.rays_per_frag,
+        json_number( settings.diverge_strength ),
+        json_string( settings.debug_mode.name() ),
+        json_number( info.post.exposure ),
+        json_string( info.post.tone_mapping.name() ),
+        json_number( info.post.temperature ),
+        json_number( info.post.tint ),
+        info.post.srgb,
+    );
+    std::fs::write( path.with_extension( "json" ), json ).map_err( |err| err.to_string() )?;
+
+    Ok( path )
+}
+
+/**
+ * Formats a number as a JSON value.
+ * JSON has no representation of NaN or infinity, so they are written as null.
+ */
+fn json_number( value: f32 ) -> String {
+    if value.is_finite() { value.to_string() } else { "null".to_string() }
+}
+
+/**
+ * Formats a string as a quoted JSON string, escaping quotes, backslashes and control characters.
+ */
+fn json_string( value: &str ) -> String {
+    let mut json = String::from( '"' );
+    for c in value.chars() {
+        match c {
+            '"'  => { json.push_str( "\\\"" ); },
+            '\\' => { json.push_str( "\\\\" ); },
+            '\n' => { json.push_str( "\\n" ); },
+            '\r' => { json.push_str( "\\r" ); },
+            '\t' => { json.push_str( "\\t" ); },
+            c if c.is_control() => { json.push_str( &format!( "\\u{:04x}", c as u32 ) ); },
+            c => { json.push( c ); },
+        }
+    }
+    json.push( '"' );
+    json
+}
+
+/**
+ * Creates a timestamp (UTC) for use in file names, on the form YYYYMMDD_HHMMSS_mmm.
+ */
+fn timestamp() -> String {
+    let duration = SystemTime::now().duration_since( UNIX_EPOCH ).unwrap_or_default();
+    let ( secs, millis ) = ( duration.as_secs() as i64, duration.subsec_millis() );
+    let ( days, secs_of_day ) = ( secs.div_euclid( 86400 ), secs.rem_euclid( 86400 ) );
+
+    // Convert days since epoch to a civil date
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid( 146097 );
+    let day_of_era = z.rem_euclid( 146097 );
+    let year_of_era = ( day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096 ) / 365;
+    let day_of_year = day_of_era - ( 365 * year_of_era + year_of_era / 4 - year_of_era / 100 );
+    let month_offset = ( 5 * day_of_year + 2 ) / 153;
+    let day = day_of_year - ( 153 * month_offset + 2 ) / 5 + 1;
+    let month = if month_offset < 10 { month_offset + 3 } else { month_offset - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}{:02}{:02}_{:02}{:02}{:02}_{:03}",
+        year, month, day,
+        secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60,
+        millis
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn non_finite_numbers_are_null() {
+        assert_eq!( json_number( 1.5 ), "1.5" );
+        assert_eq!( json_number( -0.25 ), "-0.25" );
+        assert_eq!( json_number( 1e20 ), "100000000000000000000" );
+        assert_eq!( json_number( f32::NAN ), "null" );
+        assert_eq!( json_number( f32::INFINITY ), "null" );
+        assert_eq!( json_number( f32::NEG_INFINITY ), "null" );
+    }
+
+    #[test]
+    fn strings_are_quoted_and_escaped() {
+        assert_eq!( json_string( "screenshot.png" ), r#""screenshot.png""# );
+        assert_eq!( json_string( r#"a "b" \c"# ), r#""a \"b\" \\c""# );
+        assert_eq!( json_string( "line\nbreak\ttab\u{1}" ), r#""line\nbreak\ttab\u0001""# );
+        assert_eq!( json_string( "skärmbild" ), "\"skärmbild\"" );
+    }
+}
@@ -261,6 +261,7 @@ pub struct SSBOBuilder<T> {
     bid: u32,
     binding: u32,
     data: Vec<T>,
+    data_size: isize,
 }
 
 /**
@@ -281,6 +282,7 @@ impl<T> SSBOBuilder<T> {
             bid: buffer_id,
             binding: 0,
             data: Vec::new(),
+            data_size: 0,
         }
     }
 
@@ -291,13 +293,13 @@ impl<T> SSBOBuilder<T> {
      * @param data The data.
      */
     #[must_use = "The SSBO must have data to be initialized."]
-    pub unsafe fn set_data( self, data: Vec<T> ) -> SSBOBuilder<T> {
+    pub unsafe fn set_data( mut self, data: &[T] ) -> SSBOBuilder<T> {
         //let data = &data[..];
 
         // Get data size and pointer reference
         let ( data_size, data_ref ) = (
-            byte_size_of_array( &data ),
-            pointer_to_array( &data ),
+            byte_size_of_array( data ),
+            pointer_to_array( data ),
         );
 
         // Set buffer data
@@ -306,6 +308,7 @@ impl<T> SSBOBuilder<T> {
         gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, 0);
 
         // Return
+        self.data_size = data_size;
         self
     }
 
@@ -339,7 +342,7 @@ impl<T> SSBOBuilder<T> {
 
     /**
      * Links the SSBO, finalizing it.
-     * The data can be changed, but the total size of the new data cannot be greater than the original data's size.
+     * The data can be changed later through update_data().
      * 
      * @return The fully initialized SSBO object.
      */
@@ -350,7 +353,7 @@ impl<T> SSBOBuilder<T> {
             bid: self.bid,
             binding: self.binding,
             data: self.data,
-            data_size: 0,//byte_size_of_array( &self.data ),
+            data_size: self.data_size,
         }
     }
 }
@@ -361,22 +364,28 @@ impl<T> SSBOBuilder<T> {
 impl<T> SSBO<T> {
     /**
      * Updates the data in the SSBO.
-     * The new data size cannot exceed the original data size.
+     * If the new data is larger than the buffer, the buffer is reallocated.
      * 
      * @param new_data The new data.
      */
-    pub unsafe fn update_data( &mut self, new_data: Vec<T> ) -> &SSBO<T> {
+    pub unsafe fn update_data( &mut self, new_data: &[T] ) -> &SSBO<T> {
         // Get data size and ref
         let ( new_data_size, new_data_ref ) = (
-            byte_size_of_array( &new_data ),
-            pointer_to_array( &new_data ),
+            byte_size_of_array( new_data ),
+            pointer_to_array( new_data ),
         );
 
-        // Copy new data into buffer
         gl::BindBuffer( gl::SHADER_STORAGE_BUFFER, self.bid );
-        let p = gl::MapBuffer( gl::SHADER_STORAGE_BUFFER, gl::WRITE_ONLY );
-        p.copy_from( new_data_ref, new_data_size as usize );
-        gl::UnmapBuffer(gl::SHADER_STORAGE_BUFFER);
+        if new_data_size > self.data_size {
+            // Reallocate buffer to fit the new data
+            gl::BufferData( gl::SHADER_STORAGE_BUFFER, new_data_size, new_data_ref, gl::DYNAMIC_COPY );
+            self.data_size = new_data_size;
+        } else if new_data_size > 0 {
+            // Copy new data into buffer
+            let p = gl::MapBuffer( gl::SHADER_STORAGE_BUFFER, gl::WRITE_ONLY );
+            p.copy_from( new_data_ref, new_data_size as usize );
+            gl::UnmapBuffer(gl::SHADER_STORAGE_BUFFER);
+        }
         gl::BindBuffer( gl::SHADER_STORAGE_BUFFER, 0 );
 
         // Return
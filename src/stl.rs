@@ -0,0 +1,239 @@
+use crate::mesh::{Mesh, DEFAULT_COLOR};
+
+// Sizes of the header and of each triangle of a binary STL file, in bytes
+const BINARY_HEADER_SIZE: usize = 84;
+const BINARY_TRIANGLE_SIZE: usize = 50;
+
+/**
+ * Loads an .stl file as meshes.
+ *
+ * @param path The path to the .stl file.
+ * @return The meshes, or an error message if the file could not be read or parsed.
+ */
+pub fn load( path: &str ) -> Result<Vec<Mesh>, String> {
+    let data = std::fs::read( path ).map_err( |err| format!( "Failed to read STL file '{path}': {err}" ) )?;
+    parse( &data ).map_err( |err| format!( "{path}: {err}" ) )
+}
+
+/**
+ * Parses the contents of an STL file as meshes, in the binary or ASCII format.
+ * Binary files are recognized by their size matching their triangle count, as they may start with "solid" too.
+ * Some exporters pad binary files after the last triangle, so files larger than their triangle count are binary as well,
+ * unless they are valid ASCII files.
+ * Each solid of an ASCII file becomes its own mesh, while a binary file is a single mesh.
+ * STL files do not share vertices between triangles, so each triangle gets three vertices with the facet's normal
+ * (or the normal of its winding, if the file leaves it out).
+ *
+ * @param data The contents of the file.
+ * @return The meshes, or an error message if the file could not be parsed.
+ */
+pub fn parse( data: &[u8] ) -> Result<Vec<Mesh>, String> {
+    let binary_count = data.get( 80..84 ).map( |bytes| u32::from_le_bytes( [bytes[0], bytes[1], bytes[2], bytes[3]] ) as usize );
+    let binary_size = |count: usize| BINARY_HEADER_SIZE + count * BINARY_TRIANGLE_SIZE;
+    if let Some( count ) = binary_count.filter( |&count| data.len() == binary_size( count ) ) {
+        return Ok( vec![ parse_binary( data, count ) ] )
+    }
+
+    let padded_count = binary_count.filter( |&count| data.len() > binary_size( count ) );
+    let ascii = data.trim_ascii_start().starts_with( b"solid" ).then( || parse_ascii( data ) );
+    match ( ascii, padded_count ) {
+        ( Some( Ok( meshes ) ), _ ) => Ok( meshes ),
+        ( _, Some( count ) ) => Ok( vec![ parse_binary( data, count ) ] ),
+        ( Some( Err( err ) ), None ) => Err( err ),
+        ( None, None ) => Err( "Not an STL file".to_string() ),
+    }
+}
+
+/**
+ * Parses a binary STL file.
+ * The two attribute bytes of each triangle are ignored, as there is no standard for the colors some programs store in them.
+ *
+ * @param data The contents of the file, which must be large enough for the triangle count.
+ * @param count The amount of triangles.
+ *
+ * @return The mesh.
+ */
+fn parse_binary( data: &[u8], count: usize ) -> Mesh {
+    let mut mesh = empty_mesh();
+    for triangle in data[BINARY_HEADER_SIZE..].chunks_exact( BINARY_TRIANGLE_SIZE ).take( count ) {
+        let float = |index: usize| f32::from_le_bytes( [ triangle[index*4], triangle[index*4+1], triangle[index*4+2], triangle[index*4+3] ] );
+        let vec = |index: usize| glm::vec3( float( index ), float( index + 1 ), float( index + 2 ) );
+        push_triangle( &mut mesh, [ vec( 3 ), vec( 6 ), vec( 9 ) ], vec( 0 ) );
+    }
+    mesh
+}
+
+/**
+ * Parses an ASCII STL file, made of facets like:
+ *  solid <name>
+ *    facet normal <nx> <ny> <nz>
+ *      outer loop
+ *        vertex <x> <y> <z>
+ *        vertex <x> <y> <z>
+ *        vertex <x> <y> <z>
+ *      endloop
+ *    endfacet
+ *  endsolid <name>
+ *
+ * @param data The contents of the file.
+ * @return The meshes, one per solid, or an error message if the file could not be parsed.
+ */
+fn parse_ascii( data: &[u8] ) -> Result<Vec<Mesh>, String> {
+    let source = std::str::from_utf8( data ).map_err( |_| "The file is not valid ASCII" )?;
+
+    let ( mut meshes, mut mesh, mut normal, mut points ) = ( Vec::new(), None, glm::Vec3::zeros(), Vec::<glm::Vec3>::new() );
+    for ( line_number, line ) in source.lines().enumerate() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let error = |message: String| format!( "line {}: {message}", line_number + 1 );
+        let parse_vec3 = |words: &[&str]| -> Result<glm::Vec3, String> {
+            let parse = |word: &str| word.parse::<f32>().map_err( |_| error( format!( "Invalid number '{word}'" ) ) );
+            match words {
+                [x, y, z] => Ok( glm::vec3( parse( x )?, parse( y )?, parse( z )? ) ),
+                _ => Err( error( format!( "Expected 3 numbers in '{}'", line.trim() ) ) ),
+            }
+        };
+
+        match words.as_slice() {
+            [] => { },
+            ["solid", ..] => { mesh = Some( empty_mesh() ); },
+            ["facet", "normal", values @ ..] => {
+                normal = parse_vec3( values )?;
+                points.clear();
+            },
+            ["outer", "loop"] | ["endloop"] => { },
+            ["vertex", values @ ..] => { points.push( parse_vec3( values )? ); },
+            ["endfacet"] => {
+                let mesh = mesh.as_mut().ok_or_else( || error( "Facet outside of a solid".to_string() ) )?;
+                let [ p0, p1, p2 ] = points[..] else {
+                    return Err( error( format!( "Facet has {} vertices instead of 3", points.len() ) ) )
+                };
+                push_triangle( mesh, [ p0, p1, p2 ], normal );
+            },
+            ["endsolid", ..] => {
+                meshes.extend( mesh.take() );
+            },
+            _ => { return Err( error( format!( "Could not parse '{}'", line.trim() ) ) ) },
+        }
+    }
+
+    // (Some exporters leave out the final endsolid)
+    meshes.extend( mesh );
+    Ok( meshes )
+}
+
+/**
+ * Creates a mesh without any vertices.
+ */
+fn empty_mesh() -> Mesh {
+    Mesh { vertices: Vec::new(), normals: Vec::new(), colors: Vec::new(), indices: Vec::new(), index_count: 0 }
+}
+
+/**
+ * Adds a triangle to a mesh, with three new vertices.
+ *
+ * @param mesh The mesh.
+ * @param points The positions of the triangle's corners.
+ * @param normal The facet normal from the file, replaced by the normal of the winding if it is zero (or by up, if the triangle is degenerate).
+ */
+fn push_triangle( mesh: &mut Mesh, points: [glm::Vec3; 3], normal: glm::Vec3 ) {
+    let winding_normal = ( points[1] - points[0] ).cross( &( points[2] - points[0] ) );
+    let normal = if normal.norm() > f32::EPSILON {
+        normal.normalize()
+    } else if winding_normal.norm() > f32::EPSILON {
+        winding_normal.normalize()
+    } else {
+        glm::vec3( 0.0, 1.0, 0.0 )
+    };
+
+    for point in points {
+        mesh.indices.push( ( mesh.vertices.len() / 3 ) as u32 );
+        mesh.vertices.extend_from_slice( point.as_slice() );
+        mesh.normals.extend_from_slice( normal.as_slice() );
+        mesh.colors.extend_from_slice( &DEFAULT_COLOR );
+    }
+    mesh.index_count = mesh.indices.len() as i32;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /**
+     * Gets the vertex of a mesh as a vector.
+     */
+    fn vec3( values: &[f32], vertex: usize ) -> glm::Vec3 {
+        glm::vec3( values[vertex * 3], values[vertex * 3 + 1], values[vertex * 3 + 2] )
+    }
+
+    #[test]
+    fn ascii_solids_become_separate_meshes() {
+        let meshes = load( "resources/tests/two_solids.stl" ).unwrap();
+        assert_eq!( meshes.len(), 2 );
+        assert_eq!( meshes[0].index_count, 3 );
+        assert_eq!( meshes[1].index_count, 6 );
+        assert_eq!( meshes[1].indices, vec![ 0, 1, 2, 3, 4, 5 ] );
+        assert_eq!( vec3( &meshes[1].vertices, 4 ), glm::vec3( 0.0, 3.0, 1.0 ) );
+        assert_eq!( meshes[1].colors, DEFAULT_COLOR.repeat( 6 ) );
+    }
+
+    #[test]
+    fn facet_normals_are_used_or_calculated_from_the_winding() {
+        let meshes = load( "resources/tests/two_solids.stl" ).unwrap();
+        for vertex in 0..3 {
+            assert_eq!( vec3( &meshes[0].normals, vertex ), glm::vec3( 0.0, 0.0, 1.0 ) );
+            // (The first facet of the second solid leaves its normal out)
+            assert_eq!( vec3( &meshes[1].normals, vertex ), glm::vec3( 0.0, 0.0, -1.0 ) );
+            assert_eq!( vec3( &meshes[1].normals, vertex + 3 ), glm::vec3( 0.0, 1.0, 0.0 ) );
+        }
+    }
+
+    #[test]
+    fn binary_files_starting_with_solid_are_binary() {
+        let meshes = load( "resources/tests/square_binary.stl" ).unwrap();
+        assert_eq!( meshes.len(), 1 );
+
+        let mesh = &meshes[0];
+        assert_eq!( mesh.index_count, 6 );
+        assert_eq!( mesh.vertices.len(), 18 );
+        assert_eq!( vec3( &mesh.vertices, 4 ), glm::vec3( 1.0, 1.0, 0.0 ) );
+        for vertex in 0..6 {
+            assert_eq!( vec3( &mesh.normals, vertex ), glm::vec3( 0.0, 0.0, 1.0 ) );
+        }
+    }
+
+    #[test]
+    fn padded_binary_files_are_binary() {
+        let meshes = load( "resources/tests/square_binary_padded.stl" ).unwrap();
+        assert_eq!( meshes.len(), 1 );
+        assert_eq!( meshes[0].index_count, 6 );
+        assert_eq!( meshes[0].vertices, load( "resources/tests/square_binary.stl" ).unwrap()[0].vertices );
+    }
+
+    #[test]
+    fn padded_ascii_files_are_ascii() {
+        // The bytes where binary files store their triangle count are zeros, so the file is large enough to be a padded binary file too
+        let mut data = b"solid a".to_vec();
+        data.resize( 80, b' ' );
+        data.extend_from_slice( &[ 0, 0, 0, 0 ] );
+        data.extend_from_slice( b"\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 1 0\nendloop\nendfacet\nendsolid a\n" );
+        let meshes = parse( &data ).unwrap();
+        assert_eq!( meshes.len(), 1 );
+        assert_eq!( vec3( &meshes[0].vertices, 1 ), glm::vec3( 1.0, 0.0, 0.0 ) );
+    }
+
+    #[test]
+    fn invalid_files_are_errors() {
+        let cases = [
+            ( "ply\nformat ascii 1.0\n", "Not an STL file" ),
+            ( "solid a\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nendloop\nendfacet\n", "2 vertices" ),
+            ( "solid a\nfacet normal 0 0 one\n", "Invalid number" ),
+            ( "solid a\nfacet normal 0 0\n", "Expected 3 numbers" ),
+            ( "solid a\nendsolid a\nfacet normal 0 0 1\nendfacet\n", "outside of a solid" ),
+        ];
+        for ( data, message ) in cases {
+            let error = parse( data.as_bytes() ).err().unwrap_or_else( || panic!( "{data:?} was parsed" ) );
+            assert!( error.contains( message ), "'{error}' does not mention '{message}'" );
+        }
+        assert!( load( "resources/tests/missing.stl" ).is_err() );
+    }
+}